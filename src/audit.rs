@@ -0,0 +1,150 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use anyhow::{anyhow, Context, Result};
+use ipnet::IpNet;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// A list of addresses and CIDR ranges, loaded from a file with one entry per
+/// line. Blank lines and everything after a `#` are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct IpList {
+    nets: Vec<IpNet>,
+}
+
+impl IpList {
+    pub fn load(path: &Path) -> Result<IpList> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("reading IP list {}", path.display()))?;
+
+        let nets = contents
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let entry = line.split('#').next().unwrap_or_default().trim();
+                (!entry.is_empty()).then_some((i + 1, entry))
+            })
+            .map(|(line, entry)| {
+                entry
+                    .parse::<IpNet>()
+                    .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| {
+                        anyhow!(
+                            "invalid address '{}' on line {} of {}",
+                            entry,
+                            line,
+                            path.display()
+                        )
+                    })
+            })
+            .collect::<Result<_>>()?;
+        Ok(IpList { nets })
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        self.nets.iter().any(|net| net.contains(ip))
+    }
+}
+
+/// Flags client addresses which are not on the allowlist or are on the denylist
+#[derive(Debug, Clone, Default)]
+pub struct IpAudit {
+    pub allow: Option<IpList>,
+    pub deny: Option<IpList>,
+}
+
+impl IpAudit {
+    pub fn is_enabled(&self) -> bool {
+        self.allow.is_some() || self.deny.is_some()
+    }
+
+    pub fn flags(&self, ip: &IpAddr) -> bool {
+        self.allow.as_ref().is_some_and(|allow| !allow.contains(ip))
+            || self.deny.as_ref().is_some_and(|deny| deny.contains(ip))
+    }
+
+    /// Describes which lists were checked, for the section title
+    pub fn describe(&self) -> &'static str {
+        match (&self.allow, &self.deny) {
+            (Some(_), Some(_)) => "outside the allowlist or on the denylist",
+            (Some(_), None) => "outside the allowlist",
+            _ => "on the denylist",
+        }
+    }
+}
+
+/// The hostnames covered by TLS certificates, loaded from a file with one name
+/// per line, like the subject alternative names of the certificates. A
+/// wildcard like `*.example.com` covers the names one label below
+/// `example.com`, like a certificate's. Blank lines and everything after a `#`
+/// are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct HostList {
+    names: Vec<String>,
+}
+
+impl HostList {
+    pub fn load(path: &Path) -> Result<HostList> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("reading host list {}", path.display()))?;
+
+        let names = contents
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let entry = line.split('#').next().unwrap_or_default().trim();
+                (!entry.is_empty()).then_some((i + 1, entry))
+            })
+            .map(|(line, entry)| {
+                let name = entry.trim_end_matches('.').to_lowercase();
+                let labels = name.strip_prefix("*.").unwrap_or(&name);
+                if labels.is_empty()
+                    || labels.split('.').any(|label| {
+                        label.is_empty()
+                            || !label
+                                .chars()
+                                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                    })
+                {
+                    return Err(anyhow!(
+                        "invalid hostname '{}' on line {} of {}",
+                        entry,
+                        line,
+                        path.display()
+                    ));
+                }
+                Ok(name)
+            })
+            .collect::<Result<_>>()?;
+        Ok(HostList { names })
+    }
+
+    /// True if a name of the list covers `host`, a request host which may
+    /// carry a port, like `api.example.com:8443`
+    pub fn covers(&self, host: &str) -> bool {
+        let host = host.trim().to_lowercase();
+        let host = match host.rsplit_once(':') {
+            Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+            _ => host.as_str(),
+        };
+        let host = host.trim_end_matches('.');
+        self.names.iter().any(|name| match name.strip_prefix("*.") {
+            Some(domain) => host
+                .split_once('.')
+                .is_some_and(|(label, rest)| !label.is_empty() && rest == domain),
+            None => name == host,
+        })
+    }
+}
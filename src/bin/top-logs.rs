@@ -1,66 +1,1630 @@
-use anyhow::{anyhow, Context, Result};
-use clap::{command, Arg, ArgAction};
-use top_logs::TopInfo;
-
-fn main() -> Result<()> {
-    let app = command!()
-                    .arg(Arg::new("top")
-                            .short('t')
-                            .long("top")
+use access_log_parser::LogType;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Duration, Local};
+use clap::parser::ValueSource;
+use clap::{command, Arg, ArgAction, ArgMatches, Command};
+use regex::Regex;
+use std::env;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::thread;
+use std::time::{self, Instant};
+use top_logs::diagnostics::{self, Kind};
+use top_logs::{
+    BreakdownKey, Config, ExtraFields, Filter, FixtureRecorder, FormatMap, Interim, LogFollower,
+    Manifest, NumberFormat, OutputFile, OutputFormat, Preset, Profile, ReadOptions, Report,
+    ReportOptions, Section, SortOrder, StdinFollower, SyslogListener, Tags, TimeResolution,
+    TopInfo,
+};
+
+// how long --follow waits for new lines
+const FOLLOW_POLL_INTERVAL: time::Duration = time::Duration::from_millis(250);
+
+// true if the user asked for a value, as opposed to clap filling in the default
+fn is_explicit(app: &ArgMatches, id: &str) -> bool {
+    app.ids().any(|i| i == id)
+        && matches!(
+            app.value_source(id),
+            Some(ValueSource::CommandLine) | Some(ValueSource::EnvVariable)
+        )
+}
+
+// like the ArgMatches getters, but tolerate ids the (sub)command doesn't define
+fn string(app: &ArgMatches, id: &str) -> Option<String> {
+    app.try_get_one::<String>(id).ok().flatten().cloned()
+}
+
+fn strings(app: &ArgMatches, id: &str) -> Option<Vec<String>> {
+    app.try_get_many::<String>(id)
+        .ok()
+        .flatten()
+        .map(|v| v.cloned().collect())
+}
+
+fn flag(app: &ArgMatches, id: &str) -> bool {
+    app.try_get_one::<bool>(id)
+        .ok()
+        .flatten()
+        .copied()
+        .unwrap_or(false)
+}
+
+fn parse_usize(app: &ArgMatches, id: &str) -> Result<usize> {
+    string(app, id)
+        .with_context(|| format!("missing {id}"))?
+        .parse()
+        .with_context(|| format!("parsing {id}"))
+}
+
+fn force_arg() -> Arg {
+    Arg::new("force")
+        .long("force")
+        .env("TOP_LOGS_FORCE")
+        .action(ArgAction::SetTrue)
+        .help("Overwrite the --out-file, --also-write, --save and --manifest files if they exist")
+}
+
+fn format_arg() -> Arg {
+    Arg::new("format")
+        .short('f')
+        .long("format")
+        .value_name("LOG_FORMAT")
+        .env("TOP_LOGS_FORMAT")
+        .value_delimiter(',')
+        .help("access log format, `auto` detects it from the first lines of each file. Further comma separated formats are tried in order on lines that don't parse.")
+        .value_parser(["common", "combined", "gorouter", "cloud_controller", "auto"])
+}
+
+// the arguments for parsing and filtering log lines, wherever they come from
+fn parse_args() -> Vec<Arg> {
+    vec![
+        Arg::new("ignore_parse_errors")
+            .short('i')
+            .long("ignore-parse-errors")
+            .env("TOP_LOGS_IGNORE_PARSE_ERRORS")
+            .action(ArgAction::SetTrue)
+            .help("Don't log any parsing error"),
+        Arg::new("quiet")
+            .short('q')
+            .long("quiet")
+            .env("TOP_LOGS_QUIET")
+            .action(ArgAction::SetTrue)
+            .help("Machine mode, STDOUT only contains the report and diagnostics are written to STDERR as single lines prefixed with 'top-logs: <kind>:'"),
+        Arg::new("config")
+            .short('c')
+            .long("config")
+            .value_name("FILE")
+            .env("TOP_LOGS_CONFIG")
+            .help("Configuration file with user defined presets [default: <config dir>/top-logs/config.toml]"),
+        Arg::new("preset")
+            .short('p')
+            .long("preset")
+            .value_name("NAME")
+            .env("TOP_LOGS_PRESET")
+            .help("Named analysis preset, built-in presets are cf-incident, web-traffic and security. Other options override the preset."),
+        Arg::new("strip_prefix")
+            .long("strip-prefix")
+            .value_name("REGEX")
+            .env("TOP_LOGS_STRIP_PREFIX")
+            .help("Remove the start of every line matching this regular expression before parsing, like the timestamp and stream Kubernetes adds: '\\S+ (stdout|stderr) [FP] '"),
+        Arg::new("cf_logs")
+            .long("cf-logs")
+            .env("TOP_LOGS_CF_LOGS")
+            .action(ArgAction::SetTrue)
+            .help("Read the output of `cf logs APP`, only the gorouter lines ([RTR/...]) are counted, without the Loggregator prefix. Implies --format gorouter."),
+        Arg::new("status")
+            .long("status")
+            .value_name("STATUS")
+            .value_delimiter(',')
+            .env("TOP_LOGS_STATUS")
+            .help("Only count requests with these comma separated status codes or classes, like 404,5xx"),
+        Arg::new("method")
+            .long("method")
+            .value_name("METHOD")
+            .value_delimiter(',')
+            .env("TOP_LOGS_METHOD")
+            .help("Only count requests with these comma separated request methods"),
+        Arg::new("path")
+            .long("path")
+            .value_name("PREFIX")
+            .env("TOP_LOGS_PATH")
+            .help("Only count requests whose path starts with this prefix"),
+        Arg::new("business_hours")
+            .long("business-hours")
+            .value_name("HH:MM-HH:MM")
+            .env("TOP_LOGS_BUSINESS_HOURS")
+            .help("Only count requests logged during this time of every day, like 09:00-17:00. A window ending before it starts, like 22:00-06:00, spans midnight."),
+        Arg::new("exclude_hours")
+            .long("exclude-hours")
+            .value_name("HH:MM-HH:MM")
+            .action(ArgAction::Append)
+            .value_delimiter(',')
+            .env("TOP_LOGS_EXCLUDE_HOURS")
+            .help("Don't count requests logged during this time of every day, like 01:00-04:00 for nightly batch jobs. May be repeated."),
+        Arg::new("weekdays_only")
+            .long("weekdays-only")
+            .env("TOP_LOGS_WEEKDAYS_ONLY")
+            .action(ArgAction::SetTrue)
+            .help("Only count requests logged from Monday to Friday"),
+        Arg::new("utc_offset")
+            .long("utc-offset")
+            .value_name("+HH:MM")
+            .env("TOP_LOGS_UTC_OFFSET")
+            .allow_hyphen_values(true)
+            .help("Time zone of --business-hours, --exclude-hours and --weekdays-only, like +02:00 or -05:00 [default: the offset of each log entry]"),
+        Arg::new("tag")
+            .long("tag")
+            .value_name("KEY=VALUE")
+            .action(ArgAction::Append)
+            .value_delimiter(',')
+            .env("TOP_LOGS_TAGS")
+            .help("Label the report with this tag, like 'env=prod', in machine readable outputs and exports. May be repeated."),
+        Arg::new("file_tag")
+            .long("file-tag")
+            .value_name("PATTERN=KEY=VALUE")
+            .action(ArgAction::Append)
+            .value_delimiter(',')
+            .env("TOP_LOGS_FILE_TAGS")
+            .help("Label the report with a tag when a file matching the pattern is read, like 'us-east/**=dc=us-east'. Files with different values for a key have them joined with commas. May be repeated."),
+    ]
+}
+
+// the arguments every command which reads access logs has in common
+fn input_args(env_access_logs: bool) -> Vec<Arg> {
+    let mut args = vec![format_arg().required_unless_present_any(["format_map", "cf_logs"])];
+    args.extend(parse_args());
+    args.extend([
+        Arg::new("format_map")
+            .long("format-map")
+            .value_name("PATTERN=FORMAT")
+            .value_delimiter(',')
+            .env("TOP_LOGS_FORMAT_MAP")
+            .help("Comma separated log formats by path pattern, like 'gorouter/**=gorouter,nginx/**=combined'. The first matching pattern wins, files without a match use --format."),
+        Arg::new("recursive")
+            .short('r')
+            .long("recursive")
+            .env("TOP_LOGS_RECURSIVE")
+            .action(ArgAction::SetTrue)
+            .help("Read the files in subdirectories of directory arguments too"),
+        Arg::new("join_continuations")
+            .long("join-continuations")
+            .value_name("REGEX")
+            .env("TOP_LOGS_JOIN_CONTINUATIONS")
+            .help("Lines matching this regular expression, like '^\\s', continue the entry on the line before and are joined to it before parsing"),
+        Arg::new("tar_members")
+            .long("tar-members")
+            .value_name("PATTERN")
+            .env("TOP_LOGS_TAR_MEMBERS")
+            .help("Only read the files in tar archives whose path matches this pattern, '*' matches any characters, like '*/access.log*' [default: every file]"),
+        Arg::new("mmap")
+            .long("mmap")
+            .env("TOP_LOGS_MMAP")
+            .action(ArgAction::SetTrue)
+            .help("Map uncompressed local files into memory and parse their lines in place, which is faster for large files. Don't use it on logs which may be truncated while they're read."),
+        Arg::new("access_logs")
+            .value_name("ACCESS_LOG")
+            .help("Access logs, directories or glob patterns to process or '-' (a dash) to read from STDIN, may be gzip, zstd, bzip2 or xz compressed or a tar archive [env: TOP_LOGS_ACCESS_LOGS=]")
+            .index(1)
+            .action(ArgAction::Append)
+            .required(!env_access_logs),
+    ]);
+    args
+}
+
+// the arguments which select and shape the report
+fn report_args() -> Vec<Arg> {
+    vec![
+        Arg::new("top")
+            .short('t')
+            .long("top")
+            .value_name("NUM")
+            .env("TOP_LOGS_TOP")
+            .default_value("10")
+            .help("number of results to display"),
+        Arg::new("min_response_time_threshold")
+            .short('m')
+            .long("min-response-time-threshold")
+            .value_name("MIN_THRESHOLD")
+            .env("TOP_LOGS_MIN_RESPONSE_TIME_THRESHOLD")
+            .help("Minimum threshold in number of requests for a response time bucket to be displayed. Smaller buckets are grouped together.")
+            .default_value("100"),
+        Arg::new("time_resolution")
+            .long("time-resolution")
+            .value_name("RESOLUTION")
+            .env("TOP_LOGS_TIME_RESOLUTION")
+            .default_value("s")
+            .help("Resolution of the response and gorouter time buckets, 's' for whole seconds, 'ms' for milliseconds, a number of seconds or milliseconds like '2s' or '10ms' or 'log2' for millisecond buckets that double in width"),
+        Arg::new("bucket_width")
+            .long("bucket-width")
+            .value_name("WIDTH")
+            .env("TOP_LOGS_BUCKET_WIDTH")
+            .conflicts_with("time_resolution")
+            .help("Width of the response and gorouter time buckets, a number of seconds or milliseconds like '2s', '250ms' or '50ms'"),
+        Arg::new("interval")
+            .long("interval")
+            .value_name("INTERVAL")
+            .env("TOP_LOGS_INTERVAL")
+            .default_value("1m")
+            .help("Length of the time series intervals, a number of seconds, minutes or hours like '10s', '5m' or '1h'"),
+        Arg::new("by_interval")
+            .long("by-interval")
+            .value_name("INTERVAL")
+            .env("TOP_LOGS_BY_INTERVAL")
+            .help("Add a summary of every slice of this length to the report, like '1h' or '15m', with its requests, 5xx error rate, p95 response time and top path"),
+        Arg::new("output")
+            .short('o')
+            .long("output")
+            .value_name("OUTPUT_FORMAT")
+            .env("TOP_LOGS_OUTPUT")
+            .default_value("table")
+            .help("report output format")
+            .value_parser(["table", "json", "yaml", "markdown", "html", "prometheus", "influx", "parquet"]),
+        Arg::new("out_file")
+            .long("out-file")
+            .value_name("FILE")
+            .env("TOP_LOGS_OUT_FILE")
+            .help("Write the report to this file instead of STDOUT"),
+        Arg::new("also_write")
+            .long("also-write")
+            .value_name("FORMAT=FILE")
+            .env("TOP_LOGS_ALSO_WRITE")
+            .action(ArgAction::Append)
+            .value_delimiter(',')
+            .value_parser(|value: &str| {
+                also_write(value).map(|_| value.to_string())
+            })
+            .help("Also write the report in this output format to this file, like json=report.json, from the same run. Repeat it or separate several with commas."),
+        force_arg(),
+        Arg::new("template")
+            .long("template")
+            .value_name("FILE")
+            .env("TOP_LOGS_TEMPLATE")
+            .help("Render the report through this Tera template instead of an output format"),
+        Arg::new("no_headline")
+            .long("no-headline")
+            .env("TOP_LOGS_NO_HEADLINE")
+            .action(ArgAction::SetTrue)
+            .help("Don't show the headline summary (error rate, p95 response time, busiest minute, top error path and top client) ahead of the tables"),
+        Arg::new("no_sparkline")
+            .long("no-sparkline")
+            .env("TOP_LOGS_NO_SPARKLINE")
+            .action(ArgAction::SetTrue)
+            .help("Don't show the sparkline of the requests over the log duration below the duration"),
+        Arg::new("bars")
+            .long("bars")
+            .env("TOP_LOGS_BARS")
+            .action(ArgAction::SetTrue)
+            .help("Draw a bar proportional to the count next to the counts of the tables, only in the table output"),
+        Arg::new("concentration")
+            .long("concentration")
+            .env("TOP_LOGS_CONCENTRATION")
+            .action(ArgAction::SetTrue)
+            .help("Show the share of the requests from the busiest 1% and 10% of the client IPs and paths and their Gini index"),
+        Arg::new("outliers")
+            .long("outliers")
+            .env("TOP_LOGS_OUTLIERS")
+            .action(ArgAction::SetTrue)
+            .help("List the slowest requests beyond the 99.9th percentile response time, up to --top of them, with their timestamp, path, status, backend and app index"),
+        Arg::new("slowest")
+            .long("slowest")
+            .value_name("NUM")
+            .env("TOP_LOGS_SLOWEST")
+            .help("List the NUM slowest requests, whatever their percentile, with their timestamp, response time, path, status, backend and raw log line"),
+        Arg::new("unique")
+            .long("unique")
+            .env("TOP_LOGS_UNIQUE")
+            .action(ArgAction::SetTrue)
+            .help("Show the number of distinct client IPs, paths and user agents. Up to 4096 values are counted exactly, more are estimated to within about 1%."),
+        Arg::new("sections")
+            .long("sections")
+            .value_name("SECTIONS")
+            .value_delimiter(',')
+            .env("TOP_LOGS_SECTIONS")
+            .help("Comma separated list of report sections to display [default: all]"),
+        Arg::new("drill")
+            .long("drill")
+            .value_name("FIELD=VALUE")
+            .env("TOP_LOGS_DRILL")
+            .action(ArgAction::Append)
+            .help("Add a summary of the requests with this path, client-ip, host, user-agent or status, like path=/api/v1/orders, with their top client IPs, statuses, paths, user agents and response times. Can be given more than once."),
+        Arg::new("timeline")
+            .long("timeline")
+            .value_name("FIELD=VALUE")
+            .env("TOP_LOGS_TIMELINE")
+            .action(ArgAction::Append)
+            .help("Add the number of requests with this path, client-ip, host, user-agent or status, like status=502 or path=/checkout, in every --interval of the logs. Can be given more than once."),
+        Arg::new("extract")
+            .long("extract")
+            .value_name("NAME=REGEX")
+            .env("TOP_LOGS_EXTRACT")
+            .action(ArgAction::Append)
+            .help("Add the top values and the number of distinct values the first group of this regex captures from the paths, like order_id=/orders/([0-9]+). Can be given more than once."),
+        Arg::new("latency_by")
+            .long("latency-by")
+            .value_name("KEY")
+            .env("TOP_LOGS_LATENCY_BY")
+            .action(ArgAction::Append)
+            .value_parser(BreakdownKey::ALL.map(|key| key.name()))
+            .help("Add the top paths, backend addresses of gorouter logs or hosts of gorouter and cloud_controller logs, by requests with their 5xx error rate and mean and p95 response times, to see which endpoints, Diego cells, platform VMs or routes are slow or failing. Can be given more than once."),
+        Arg::new("extra_fields")
+            .long("extra-fields")
+            .value_name("NAMES")
+            .value_delimiter(',')
+            .env("TOP_LOGS_EXTRA_FIELDS")
+            .help("Comma separated names of gorouter fields, like x_b3_traceid,instance_id, whose values are counted in the extra-fields section. Any name:\"value\" field of the line can be used."),
+        Arg::new("allowlist")
+            .long("allowlist")
+            .value_name("FILE")
+            .env("TOP_LOGS_ALLOWLIST")
+            .help("File with the client IPs and CIDR ranges allowed to connect, one per line. The ip-audit section shows the traffic from everywhere else."),
+        Arg::new("denylist")
+            .long("denylist")
+            .value_name("FILE")
+            .env("TOP_LOGS_DENYLIST")
+            .help("File with the client IPs and CIDR ranges that should not connect, one per line. The ip-audit section shows the traffic from them."),
+        Arg::new("cert_hosts")
+            .long("cert-hosts")
+            .value_name("FILE")
+            .env("TOP_LOGS_CERT_HOSTS")
+            .help("File with the hostnames the TLS certificates cover, one per line, wildcards like *.example.com included. The cert-coverage section shows the traffic for every other host."),
+        Arg::new("funnel")
+            .long("funnel")
+            .value_name("PATHS")
+            .value_delimiter(',')
+            .env("TOP_LOGS_FUNNEL")
+            .help("Comma separated, ordered path patterns, like /login,/cart,/checkout. Shows how many sessions reached each step, '*' matches any characters."),
+        Arg::new("session_timeout")
+            .long("session-timeout")
+            .value_name("MINUTES")
+            .env("TOP_LOGS_SESSION_TIMEOUT")
+            .default_value("30")
+            .help("A client's session ends after this many minutes without a request"),
+        Arg::new("sort")
+            .long("sort")
+            .value_name("ORDER")
+            .env("TOP_LOGS_SORT")
+            .value_parser(["value", "key"])
+            .help("Sort every table by count (value) or name (key) [default: depends on the table]"),
+        Arg::new("segment")
+            .long("segment")
+            .value_name("FIELD=VALUE")
+            .env("TOP_LOGS_SEGMENT")
+            .help("Add paired tables of the top requests with and without this path, client-ip, host, user-agent or referrer domain, like referrer=campaign.example.com"),
+        Arg::new("profile")
+            .long("profile")
+            .value_name("FILE")
+            .env("TOP_LOGS_PROFILE")
+            .help("JSON report of a healthy run, written with --output json, to compare the count sections with. The keys whose share of the requests changed the most are listed for each section."),
+        Arg::new("decimals")
+            .long("decimals")
+            .value_name("PLACES")
+            .env("TOP_LOGS_DECIMALS")
+            .help("Round percentiles, percentages and forecast bands to this many decimal places in every output format, a section layout in the configuration file can override it"),
+        Arg::new("decimal_separator")
+            .long("decimal-separator")
+            .value_name("SEPARATOR")
+            .env("TOP_LOGS_DECIMAL_SEPARATOR")
+            .default_value(".")
+            .value_parser([".", ","])
+            .help("Decimal separator of percentiles, percentages and forecast bands in the table, Markdown and HTML output, JSON and YAML always use '.'"),
+    ]
+}
+
+fn refresh_arg() -> Arg {
+    Arg::new("refresh")
+        .long("refresh")
+        .value_name("SECONDS")
+        .env("TOP_LOGS_REFRESH")
+        .default_value("5")
+        .help("How often --follow and listen render the report")
+}
+
+fn jobs_arg() -> Arg {
+    Arg::new("jobs")
+        .short('j')
+        .long("jobs")
+        .value_name("NUM")
+        .env("TOP_LOGS_JOBS")
+        .default_value("0")
+        .help("How many access logs are read at the same time, 0 reads one per CPU")
+}
+
+// the number of threads for --jobs
+fn jobs(app: &ArgMatches) -> Result<usize> {
+    if !app.ids().any(|id| id == "jobs") {
+        return Ok(1);
+    }
+    match parse_usize(app, "jobs")? {
+        0 => Ok(thread::available_parallelism().map_or(1, |n| n.get())),
+        jobs => Ok(jobs),
+    }
+}
+
+// the arguments for consuming access logs from Kafka
+#[cfg(feature = "kafka")]
+fn kafka_args() -> Vec<Arg> {
+    vec![
+        Arg::new("kafka")
+            .long("kafka")
+            .value_name("BROKERS")
+            .env("TOP_LOGS_KAFKA")
+            .requires("kafka_topic")
+            .conflicts_with("schedule")
+            .help("Consume access log lines from a Kafka topic on these comma separated brokers, like host1:9092,host2:9092, and render the report every --refresh seconds like --follow"),
+        Arg::new("kafka_topic")
+            .long("kafka-topic")
+            .value_name("TOPIC")
+            .env("TOP_LOGS_KAFKA_TOPIC")
+            .help("Kafka topic with the access log lines, one or more lines per message"),
+        Arg::new("kafka_group")
+            .long("kafka-group")
+            .value_name("GROUP")
+            .env("TOP_LOGS_KAFKA_GROUP")
+            .default_value("top-logs")
+            .help("Kafka consumer group, instances in the same group share the topic's partitions"),
+    ]
+}
+
+fn main() {
+    // clap's env support only handles a single value, so multiple access logs
+    // are read from an OS path list (like PATH) instead. Empty entries are
+    // left out and a variable without any paths, like one set to "" by a
+    // template, counts as unset.
+    let env_access_logs: Option<Vec<String>> = env::var_os("TOP_LOGS_ACCESS_LOGS")
+        .map(|paths| {
+            env::split_paths(&paths)
+                .map(|p| p.to_string_lossy().into_owned())
+                .filter(|p| !p.trim().is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|paths| !paths.is_empty());
+
+    let mut app = command!()
+                    .args_conflicts_with_subcommands(true)
+                    .subcommand_negates_reqs(true)
+                    .args(input_args(env_access_logs.is_some()))
+                    .mut_arg("format", |arg| arg.required(false).required_unless_present_any(["list_sections", "load"]))
+                    .args(report_args())
+                    .arg(Arg::new("list_sections")
+                            .long("list-sections")
+                            .action(ArgAction::SetTrue)
+                            .help("Print the section names accepted by --sections and exit"))
+                    .arg(Arg::new("explain")
+                            .long("explain")
+                            .value_name("NUM")
+                            .num_args(0..=1)
+                            .require_equals(true)
+                            .default_missing_value("10")
+                            .env("TOP_LOGS_EXPLAIN")
+                            .help("Don't count anything, show how the first lines of every access log are parsed, whether the filters let them through and which sections they'd be counted in. Give the number of lines as --explain=NUM [default: 10]"))
+                    .arg(Arg::new("estimate")
+                            .long("estimate")
+                            .env("TOP_LOGS_ESTIMATE")
+                            .action(ArgAction::SetTrue)
+                            .conflicts_with_all(["explain", "follow", "schedule"])
+                            .help("Don't count everything, parse the first and last few MB of every access log and estimate the lines, distinct clients, paths and user agents, runtime and memory of the run, with suggestions for large runs"))
+                    .arg(Arg::new("follow")
+                            .long("follow")
+                            .env("TOP_LOGS_FOLLOW")
+                            .action(ArgAction::SetTrue)
+                            .help("Keep reading the access logs as they grow, like tail -f, and render the report every --refresh seconds until interrupted"))
+                    .arg(refresh_arg())
+                    .arg(jobs_arg())
+                    .arg(Arg::new("interim_lines")
+                            .long("interim-lines")
                             .value_name("NUM")
-                            .default_value("10")
-                            .help("number of results to display"))
-                    .arg(Arg::new("format")
-                            .short('f')
-                            .long("format")
-                            .value_name("LOG_FORMAT")
-                            .required(true)
-                            .help("access log format")
-                            .value_parser(["common", "combined", "gorouter", "cloud_controller"]))
-                    .arg(Arg::new("ignore_parse_errors")
-                            .short('i')
-                            .long("ignore-parse-errors")
+                            .env("TOP_LOGS_INTERIM_LINES")
+                            .help("Print a summary of the counts so far to STDERR after every this many log entries, like 1000000"))
+                    .arg(Arg::new("interim_seconds")
+                            .long("interim-seconds")
+                            .value_name("SECONDS")
+                            .env("TOP_LOGS_INTERIM_SECONDS")
+                            .help("Print a summary of the counts so far to STDERR every this many seconds"))
+                    .arg(Arg::new("checkpoint")
+                            .long("checkpoint")
+                            .value_name("FILE")
+                            .env("TOP_LOGS_CHECKPOINT")
+                            .help("Write the counts and how far every file has been read to this file every minute, and resume from it if it exists. Resume with the same options and files."))
+                    .arg(Arg::new("save")
+                            .long("save")
+                            .value_name("FILE")
+                            .env("TOP_LOGS_SAVE")
+                            .help("Write the counts to this file after reading the access logs, to render the report again with --load"))
+                    .arg(Arg::new("load")
+                            .long("load")
+                            .value_name("FILE")
+                            .env("TOP_LOGS_LOAD")
+                            .conflicts_with_all(["access_logs", "explain", "estimate", "follow", "schedule", "checkpoint"])
+                            .help("Don't read access logs, render the report from the counts written with --save, for example with another --top or --min-response-time-threshold. Use the same sections, drills, extra fields and funnel as when saving."))
+                    .arg(Arg::new("manifest")
+                            .long("manifest")
+                            .value_name("FILE")
+                            .env("TOP_LOGS_MANIFEST")
+                            .conflicts_with_all(["load", "explain", "estimate", "follow", "schedule"])
+                            .help("Write a JSON manifest to this file with the SHA-256 and bytes read of every access log, the filters, sections and version of top-logs, and the SHA-256 of the report and the --save and --also-write files, to check later with the verify-manifest subcommand"))
+                    .arg(Arg::new("record_fixture")
+                            .long("record-fixture")
+                            .value_name("DIR")
+                            .env("TOP_LOGS_RECORD_FIXTURE")
+                            .conflicts_with_all(["load", "explain", "estimate", "follow", "schedule"])
+                            .help("For developers: after the report, write the first 1000 lines of every access log, with IP addresses, UUIDs and PII replaced, and the JSON report of those lines to this directory, to check later versions of top-logs against"))
+                    .arg(Arg::new("no_progress")
+                            .long("no-progress")
+                            .env("TOP_LOGS_NO_PROGRESS")
                             .action(ArgAction::SetTrue)
-                            .help("Don't log any parsing error"))
-                    .arg(Arg::new("min_response_time_threshold")
-                            .short('m')
-                            .long("min-response-time-threshold")
-                            .value_name("MIN_THRESHOLD")
-                            .help("Minimum threshold in number of requests for a response time bucket to be displayed. Smaller buckets are grouped together.")
-                            .default_value("100"))
-                    .arg(Arg::new("access_logs")
-                            .value_name("ACCESS_LOG")
-                            .help("Access logs to process or '-' (a dash) to read from STDIN")
-                            .index(1)
-                            .action(ArgAction::Append)
-                            .required(true))
-                    .get_matches();
-
-    let mut ti = TopInfo::new(
-        app.get_one::<String>("top")
-            .unwrap()
-            .parse()
-            .with_context(|| "parsing top")?,
-        app.contains_id("ignore_parse_errors"),
+                            .help("Don't show a progress bar for every file being read, they're only shown on a terminal and not with --quiet or machine readable output formats"))
+                    .arg(Arg::new("buffer_lines")
+                            .long("buffer-lines")
+                            .value_name("NUM")
+                            .env("TOP_LOGS_BUFFER_LINES")
+                            .default_value("10000")
+                            .help("The most lines --follow reads ahead from STDIN and counts per poll. When they're full, reading pauses and the program writing to the pipe waits."))
+                    .arg(Arg::new("schedule")
+                            .long("schedule")
+                            .value_name("CRON")
+                            .env("TOP_LOGS_SCHEDULE")
+                            .conflicts_with("follow")
+                            .help("Keep running and write a report at every tick of this cron expression, like \"0 6 * * *\", covering the requests since the tick before. strftime patterns in --out-file, like %Y-%m-%d, are replaced with the start of the period."))
+                    .subcommand(Command::new("export")
+                            .about("Export every counted value, instead of a report")
+                            .subcommand_required(true)
+                            .subcommand(Command::new("sqlite")
+                                    .about("Write the counts into tables of a SQLite database, appending to an existing database")
+                                    .args(input_args(env_access_logs.is_some()))
+                                    .arg(jobs_arg())
+                                    .arg(Arg::new("database")
+                                            .short('d')
+                                            .long("database")
+                                            .value_name("FILE")
+                                            .env("TOP_LOGS_DATABASE")
+                                            .required(true)
+                                            .help("SQLite database file, created if it doesn't exist")))
+                            .subcommand(Command::new("jsonl")
+                                    .about("Write every log entry as a JSON Lines record with the same fields for every log format")
+                                    .args(input_args(env_access_logs.is_some()))
+                                    .arg(Arg::new("out_file")
+                                            .long("out-file")
+                                            .value_name("FILE")
+                                            .env("TOP_LOGS_OUT_FILE")
+                                            .help("Write the records to this file instead of STDOUT"))
+                                    .arg(force_arg())))
+                    .subcommand(Command::new("trend")
+                            .about("Collect the headline metrics of runs in a database and show how they change week over week")
+                            .subcommand_required(true)
+                            .arg(Arg::new("db")
+                                    .long("db")
+                                    .value_name("FILE")
+                                    .env("TOP_LOGS_TREND_DB")
+                                    .required(true)
+                                    .help("SQLite database with the trend data, created if it doesn't exist"))
+                            .subcommand(Command::new("add")
+                                    .about("Add the request volume, error rate, p95 response time and top paths of the access logs as a new run")
+                                    .args(input_args(env_access_logs.is_some()))
+                                    .arg(jobs_arg()))
+                            .subcommand(Command::new("report")
+                                    .about("Show the metrics of every week with the change from the week before")
+                                    .arg(Arg::new("weeks")
+                                            .long("weeks")
+                                            .value_name("NUM")
+                                            .env("TOP_LOGS_TREND_WEEKS")
+                                            .default_value("12")
+                                            .help("Number of most recent weeks to show"))))
+                    .subcommand(Command::new("merge")
+                            .about("Combine the counts of checkpoints written with --checkpoint or --save, like those of runs on several machines, into one report")
+                            .args(parse_args().into_iter().filter(|arg| {
+                                ["quiet", "config", "preset", "tag"].contains(&arg.get_id().as_str())
+                            }))
+                            .args(report_args())
+                            .arg(Arg::new("checkpoints")
+                                    .value_name("CHECKPOINT")
+                                    .required(true)
+                                    .num_args(1..)
+                                    .help("Checkpoints of finished runs or counts written with --save, made with the same sections, drills, extra fields and funnel")))
+                    .subcommand(Command::new("view")
+                            .about("Explore counts written with --save or --checkpoint without the logs they came from, reading commands like `show client-ips` or `top 50` from STDIN. The counts are never changed.")
+                            .args(parse_args().into_iter().filter(|arg| {
+                                ["quiet", "config", "preset", "tag"].contains(&arg.get_id().as_str())
+                            }))
+                            .args(report_args().into_iter().filter(|arg| {
+                                !["out_file", "also_write", "force", "template"].contains(&arg.get_id().as_str())
+                            }))
+                            .arg(Arg::new("counts")
+                                    .value_name("FILE")
+                                    .required(true)
+                                    .help("Counts written with --save or --checkpoint, made with the same sections, drills, extra fields and funnel")))
+                    .subcommand(Command::new("verify-manifest")
+                            .about("Check that the access logs and reports of a manifest written with --manifest are unchanged, failing if any file differs or is missing")
+                            .arg(Arg::new("manifest")
+                                    .value_name("MANIFEST")
+                                    .required(true)
+                                    .help("Manifest written with --manifest")))
+                    .subcommand(Command::new("selftest")
+                            .about("Check the installation by counting the sample logs bundled with top-logs, one of every log format, and checking that the totals add up"))
+                    .subcommand(Command::new("listen")
+                            .about("Receive access logs as syslog messages, like the ones gorouter forwards, and render the report every --refresh seconds until interrupted")
+                            .arg(format_arg().required_unless_present("cf_logs"))
+                            .args(parse_args())
+                            .args(report_args())
+                            .arg(refresh_arg())
+                            .arg(Arg::new("bind")
+                                    .long("bind")
+                                    .value_name("ADDRESS")
+                                    .env("TOP_LOGS_LISTEN_BIND")
+                                    .default_value("0.0.0.0:5514")
+                                    .help("Address and port to receive syslog messages on"))
+                            .arg(Arg::new("protocol")
+                                    .long("protocol")
+                                    .value_name("PROTOCOL")
+                                    .env("TOP_LOGS_LISTEN_PROTOCOL")
+                                    .default_value("both")
+                                    .value_parser(["udp", "tcp", "both"])
+                                    .help("Receive syslog messages over UDP, TCP or both")));
+    #[cfg(feature = "kafka")]
+    {
+        app = app.args(kafka_args());
+    }
+    // messages from Kafka replace the access logs
+    let unless: &[&str] = if cfg!(feature = "kafka") {
+        &["list_sections", "load", "kafka"]
+    } else {
+        &["list_sections", "load"]
+    };
+    if env_access_logs.is_none() {
+        app = app.mut_arg("access_logs", |arg| {
+            arg.required(false).required_unless_present_any(unless)
+        });
+    }
+    let app = app.get_matches();
+
+    let result = match app.subcommand() {
+        Some(("export", export)) => match export.subcommand() {
+            Some(("sqlite", sqlite)) => {
+                diagnostics::set_structured(flag(sqlite, "quiet"));
+                export_sqlite(sqlite, env_access_logs)
+            }
+            Some(("jsonl", jsonl)) => {
+                diagnostics::set_structured(flag(jsonl, "quiet"));
+                export_jsonl(jsonl, env_access_logs)
+            }
+            _ => unreachable!("export requires a subcommand"),
+        },
+        Some(("trend", trend)) => {
+            let db = PathBuf::from(string(trend, "db").unwrap_or_default());
+            match trend.subcommand() {
+                Some(("add", add)) => {
+                    diagnostics::set_structured(flag(add, "quiet"));
+                    trend_add(add, &db, env_access_logs)
+                }
+                Some(("report", report)) => trend_report(report, &db),
+                _ => unreachable!("trend requires a subcommand"),
+            }
+        }
+        Some(("merge", merge)) => {
+            diagnostics::set_structured(flag(merge, "quiet"));
+            merge_checkpoints(merge)
+        }
+        Some(("view", view)) => {
+            diagnostics::set_structured(flag(view, "quiet"));
+            view_counts(view)
+        }
+        Some(("verify-manifest", verify)) => {
+            verify_manifest(Path::new(&string(verify, "manifest").unwrap_or_default()))
+        }
+        Some(("selftest", _)) => selftest(),
+        Some(("listen", listen)) => {
+            diagnostics::set_structured(flag(listen, "quiet"));
+            run(listen, None)
+        }
+        _ => {
+            diagnostics::set_structured(flag(&app, "quiet"));
+            run(&app, env_access_logs)
+        }
+    };
+
+    if let Err(err) = result {
+        if diagnostics::is_structured() {
+            diagnostics::emit(Kind::Error, &format!("{err:#}"));
+        } else {
+            eprintln!("Error: {err:?}");
+        }
+        process::exit(1);
+    }
+}
+
+fn config(app: &ArgMatches) -> Result<Config> {
+    Config::load(string(app, "config").as_deref().map(Path::new))
+}
+
+// combines the values given on the command line with the selected preset
+fn settings(app: &ArgMatches) -> Result<Preset> {
+    let config = config(app)?;
+    let preset = match string(app, "preset") {
+        Some(name) => config.preset(&name)?,
+        None => Preset::default(),
+    };
+
+    Ok(Preset {
+        sections: strings(app, "sections"),
+        top: is_explicit(app, "top")
+            .then(|| parse_usize(app, "top"))
+            .transpose()?,
+        min_response_time_threshold: is_explicit(app, "min_response_time_threshold")
+            .then(|| parse_usize(app, "min_response_time_threshold"))
+            .transpose()?,
+        sort: string(app, "sort"),
+        status: strings(app, "status"),
+        methods: strings(app, "method"),
+        path: string(app, "path"),
+        business_hours: string(app, "business_hours"),
+        exclude_hours: strings(app, "exclude_hours"),
+        weekdays_only: flag(app, "weekdays_only").then_some(true),
+        utc_offset: string(app, "utc_offset"),
+        headline: flag(app, "no_headline").then_some(false),
+        funnel: strings(app, "funnel"),
+        session_timeout: is_explicit(app, "session_timeout")
+            .then(|| parse_usize(app, "session_timeout"))
+            .transpose()?,
+        allowlist: string(app, "allowlist").map(PathBuf::from),
+        denylist: string(app, "denylist").map(PathBuf::from),
+        cert_hosts: string(app, "cert_hosts").map(PathBuf::from),
+    }
+    .or(preset))
+}
+
+// the files to read with the log format of each, --format-map takes
+// precedence over --format
+fn access_logs(
+    app: &ArgMatches,
+    env_access_logs: Option<Vec<String>>,
+) -> Result<Vec<(String, LogType)>> {
+    let files = top_logs::expand_paths(
+        &match strings(app, "access_logs") {
+            Some(files) => files,
+            None => env_access_logs.unwrap_or_default(),
+        },
+        flag(app, "recursive"),
+    )?;
+    let format_map = FormatMap::parse(&strings(app, "format_map").unwrap_or_default())?;
+    let auto = string(app, "format").as_deref() == Some("auto");
+    let default = (!auto && (string(app, "format").is_some() || flag(app, "cf_logs")))
+        .then(|| log_type(app))
+        .transpose()?;
+
+    files
+        .into_iter()
+        .map(|file| match format_map.log_type(&file).or(default) {
+            Some(log_type) => Ok((file, log_type)),
+            None if auto => {
+                let log_type = detect_format(app, &file)?;
+                Ok((file, log_type))
+            }
+            None => bail!("no log format for {file}, add it to --format-map or set --format"),
+        })
+        .collect()
+}
+
+fn detect_format(app: &ArgMatches, file: &str) -> Result<LogType> {
+    if file.trim() == "-" {
+        bail!("--format auto can't detect the format of STDIN, set --format");
+    }
+    let (log_type, parsed) = top_logs::detect_format(file, &read_options(app)?)?
+        .ok_or_else(|| anyhow!("couldn't detect the log format of {file}, set --format"))?;
+
+    let name = match log_type {
+        LogType::CommonLog => "common",
+        LogType::CombinedLog => "combined",
+        LogType::GorouterLog => "gorouter",
+        LogType::CloudControllerLog => "cloud_controller",
+    };
+    diagnostics::emit(
+        Kind::Progress,
+        &format!(
+            "Detected {name} format for {file} ({:.0}% of sampled lines parsed)",
+            parsed * 100.0
+        ),
     );
+    Ok(log_type)
+}
+
+fn read_options(app: &ArgMatches) -> Result<ReadOptions> {
+    Ok(ReadOptions {
+        ignore_parse_errors: flag(app, "ignore_parse_errors"),
+        tar_members: string(app, "tar_members"),
+        fallback_formats: fallback_formats(app)?,
+        join_continuations: join_continuations(app)?,
+        cf_logs: flag(app, "cf_logs"),
+        strip_prefix: strip_prefix(app)?,
+        mmap: flag(app, "mmap"),
+    })
+}
+
+// the pattern is anchored, so it only ever matches at the start of a line
+fn strip_prefix(app: &ArgMatches) -> Result<Option<Regex>> {
+    string(app, "strip_prefix")
+        .map(|pattern| {
+            Regex::new(&format!("^(?:{pattern})"))
+                .with_context(|| format!("invalid --strip-prefix '{pattern}'"))
+        })
+        .transpose()
+}
 
-    for file in app.get_many::<String>("access_logs").unwrap() {
-        ti.process_file(
-            file,
-            app.get_one::<String>("format")
-                .unwrap()
-                .parse()
+fn join_continuations(app: &ArgMatches) -> Result<Option<Regex>> {
+    string(app, "join_continuations")
+        .map(|pattern| {
+            Regex::new(&pattern)
+                .with_context(|| format!("invalid --join-continuations '{pattern}'"))
+        })
+        .transpose()
+}
+
+// the formats after the first one of --format
+fn fallback_formats(app: &ArgMatches) -> Result<Vec<LogType>> {
+    let formats = strings(app, "format").unwrap_or_default();
+    if formats.len() > 1 && formats.iter().any(|f| f == "auto") {
+        bail!("--format auto can't be combined with other formats");
+    }
+    if flag(app, "cf_logs") && formats.iter().any(|f| f == "auto") {
+        bail!("--format auto can't detect the format of `cf logs` output, leave --format out");
+    }
+    formats
+        .iter()
+        .skip(1)
+        .map(|f| {
+            f.parse()
                 .map_err(|e| anyhow!("parse error: {}", e))
-                .with_context(|| "parsing format")?,
-        )?;
+                .with_context(|| "parsing format")
+        })
+        .collect()
+}
+
+fn log_type(app: &ArgMatches) -> Result<LogType> {
+    // the router lines of `cf logs` are gorouter access logs
+    if flag(app, "cf_logs") && string(app, "format").is_none() {
+        return Ok(LogType::GorouterLog);
+    }
+    string(app, "format")
+        .unwrap_or_default()
+        .parse()
+        .map_err(|e| anyhow!("parse error: {}", e))
+        .with_context(|| "parsing format")
+}
+
+fn tags(app: &ArgMatches) -> Result<Tags> {
+    Tags::new(
+        &strings(app, "tag").unwrap_or_default(),
+        &strings(app, "file_tag").unwrap_or_default(),
+    )
+}
+
+// a TopInfo set up by the settings, without any log read yet
+fn top_info(
+    app: &ArgMatches,
+    settings: &Preset,
+    max_results: usize,
+    session_timeout: Option<Duration>,
+) -> Result<TopInfo> {
+    let mut ti = TopInfo::new(max_results, flag(app, "ignore_parse_errors"));
+    ti.set_filter(settings.filter()?);
+    if let Some(segment) = string(app, "segment") {
+        ti.set_segment(segment.parse()?);
+    }
+    if let Some(pattern) = string(app, "tar_members") {
+        ti.set_tar_members(&pattern);
+    }
+    ti.set_fallback_formats(fallback_formats(app)?);
+    ti.set_tags(tags(app)?);
+    ti.set_cf_logs(flag(app, "cf_logs"));
+    if let Some(width) = string(app, "bucket_width") {
+        ti.set_time_resolution(TimeResolution::width(&width).map_err(|e| anyhow!(e))?);
+    } else if let Some(resolution) = string(app, "time_resolution") {
+        ti.set_time_resolution(resolution.parse().map_err(|e: String| anyhow!(e))?);
+    }
+    if let Some(interval) = string(app, "interval") {
+        ti.set_interval(interval.parse().map_err(|e: String| anyhow!(e))?);
+    }
+    if string(app, "slowest").is_some() {
+        let count = parse_usize(app, "slowest")?;
+        if count == 0 {
+            bail!("--slowest needs at least 1 request");
+        }
+        ti.set_slowest(count);
     }
+    if let Some(interval) = string(app, "by_interval") {
+        ti.set_slices(interval.parse().map_err(|e: String| anyhow!(e))?);
+    }
+    if let Some(pattern) = strip_prefix(app)? {
+        ti.set_strip_prefix(pattern);
+    }
+    if let Some(pattern) = join_continuations(app)? {
+        ti.set_join_continuations(pattern);
+    }
+    ti.set_extra_fields(ExtraFields::new(
+        strings(app, "extra_fields").unwrap_or_default(),
+    )?);
+    for drill in strings(app, "drill").unwrap_or_default() {
+        ti.add_drill(drill.parse()?);
+    }
+    for timeline in strings(app, "timeline").unwrap_or_default() {
+        ti.add_timeline(timeline.parse()?);
+    }
+    for extract in strings(app, "extract").unwrap_or_default() {
+        ti.add_extract(extract.parse()?);
+    }
+    for key in strings(app, "latency_by").unwrap_or_default() {
+        ti.add_breakdown(key.parse()?);
+    }
+    if let Some(timeout) = session_timeout {
+        ti.track_sessions(timeout);
+    }
+    Ok(ti)
+}
 
-    ti.print_summary(
-        app.get_one::<String>("min_response_time_threshold")
-            .unwrap()
-            .parse()
-            .with_context(|| "parsing min_response_time_threshold")?,
+fn analyze(
+    app: &ArgMatches,
+    settings: &Preset,
+    max_results: usize,
+    session_timeout: Option<Duration>,
+    env_access_logs: Option<Vec<String>>,
+) -> Result<TopInfo> {
+    let mut ti = top_info(app, settings, max_results, session_timeout)?;
+    ti.set_interim(Interim {
+        lines: string(app, "interim_lines")
+            .map(|_| parse_usize(app, "interim_lines"))
+            .transpose()?
+            .filter(|lines| *lines > 0),
+        every: string(app, "interim_seconds")
+            .map(|_| parse_usize(app, "interim_seconds"))
+            .transpose()?
+            .filter(|seconds| *seconds > 0)
+            .map(|seconds| time::Duration::from_secs(seconds as u64)),
+    });
+    if let Some(path) = string(app, "checkpoint") {
+        if ti.set_checkpoint(Path::new(&path))? {
+            diagnostics::emit(Kind::Progress, &format!("Resuming from checkpoint {path}"));
+        }
+    }
+    // the bars are drawn on STDERR, but only make sense to someone watching the report
+    let readable = string(app, "template").is_some()
+        || matches!(
+            string(app, "output").as_deref(),
+            None | Some("table" | "markdown" | "html")
+        );
+    top_logs::progress::set_enabled(
+        readable && !flag(app, "quiet") && !flag(app, "no_progress") && io::stdout().is_terminal(),
     );
+    let files = access_logs(app, env_access_logs)?;
+    ti.process_files(&files, jobs(app)?)?;
+    if let Some(dir) = string(app, "record_fixture") {
+        record_fixtures(app, Path::new(&dir), &files)?;
+    }
+    Ok(ti)
+}
+
+// STDIN has been read by now, so there's nothing left to record of it
+fn record_fixtures(app: &ArgMatches, dir: &Path, files: &[(String, LogType)]) -> Result<()> {
+    let options = read_options(app)?;
+    let mut recorder = FixtureRecorder::open(dir)?;
+    for (file, log_type) in files {
+        if file.trim() == "-" {
+            diagnostics::emit(
+                Kind::Warning,
+                "Can't record a fixture of STDIN, skipping it",
+            );
+            continue;
+        }
+        recorder.record(file, *log_type, &options)?;
+    }
+    recorder.finish()
+}
+
+// keeps reading the access logs as they grow and renders the report every
+// --refresh seconds, until interrupted
+fn follow(
+    app: &ArgMatches,
+    mut ti: TopInfo,
+    options: &ReportOptions,
+    env_access_logs: Option<Vec<String>>,
+) -> Result<()> {
+    let refresh = time::Duration::from_secs(parse_usize(app, "refresh")?.max(1) as u64);
+    let mut followers = Vec::new();
+    let mut stdin = None;
+    for (file, log_type) in access_logs(app, env_access_logs)? {
+        if file.trim() != "-" {
+            followers.push((LogFollower::open(&file)?, log_type));
+        } else if stdin.is_none() {
+            let buffer_lines = parse_usize(app, "buffer_lines")?;
+            stdin = Some((StdinFollower::spawn(buffer_lines), log_type));
+        } else {
+            bail!("STDIN can only be read once");
+        }
+    }
+    #[cfg(feature = "kafka")]
+    let mut kafka = match string(app, "kafka") {
+        Some(brokers) => {
+            if string(app, "format").as_deref() == Some("auto") {
+                bail!("--format auto can't detect the format of Kafka messages, set --format");
+            }
+            let follower = top_logs::KafkaFollower::connect(
+                &brokers,
+                &string(app, "kafka_topic").unwrap_or_default(),
+                &string(app, "kafka_group").unwrap_or_default(),
+            )?;
+            Some((follower, log_type(app)?))
+        }
+        None => None,
+    };
+    // syslog messages received by the listen subcommand
+    let mut syslog = match string(app, "bind") {
+        Some(address) => {
+            if string(app, "format").as_deref() == Some("auto") {
+                bail!("--format auto can't detect the format of syslog messages, set --format");
+            }
+            let protocol = string(app, "protocol").unwrap_or_default();
+            let listener = SyslogListener::bind(&address, protocol != "tcp", protocol != "udp")?;
+            diagnostics::emit(
+                Kind::Progress,
+                &format!(
+                    "Listening for syslog messages on {}",
+                    listener.addresses().join(" and ")
+                ),
+            );
+            Some((listener, log_type(app)?))
+        }
+        None => None,
+    };
+
+    // redraw in place when watching on a terminal
+    let clear = string(app, "out_file").is_none() && io::stdout().is_terminal();
+    let mut rendered: Option<Instant> = None;
+    loop {
+        for (follower, log_type) in &mut followers {
+            ti.process_new_lines(follower, *log_type)?;
+        }
+        if let Some((follower, log_type)) = &mut stdin {
+            ti.process_stdin(follower, *log_type)?;
+        }
+        #[cfg(feature = "kafka")]
+        if let Some((follower, log_type)) = &mut kafka {
+            ti.process_new_messages(follower, *log_type)?;
+        }
+        if let Some((listener, log_type)) = &mut syslog {
+            ti.process_syslog(listener, *log_type)?;
+        }
+        if rendered.is_none_or(|at| at.elapsed() >= refresh) {
+            if clear {
+                print!("\x1b[2J\x1b[H");
+            }
+            // later refreshes replace the file written by the first one
+            write_output(
+                app,
+                &ti.report(options),
+                string(app, "out_file"),
+                rendered.is_some(),
+            )?;
+            rendered = Some(Instant::now());
+        }
+        thread::sleep(FOLLOW_POLL_INTERVAL);
+    }
+}
+
+fn report_options(app: &ArgMatches, settings: &Preset) -> Result<ReportOptions> {
+    Ok(ReportOptions {
+        min_response_time_threshold: settings
+            .min_response_time_threshold
+            .map_or_else(|| parse_usize(app, "min_response_time_threshold"), Ok)?,
+        sections: settings.sections()?,
+        sort: settings.sort_order()?,
+        headline: settings.headline.unwrap_or(true),
+        sparkline: !flag(app, "no_sparkline"),
+        funnel: settings.funnel.clone().unwrap_or_default(),
+        ip_audit: settings.ip_audit()?,
+        cert_hosts: settings.cert_hosts()?,
+        unique: flag(app, "unique"),
+        concentration: flag(app, "concentration"),
+        outliers: flag(app, "outliers"),
+        bars: flag(app, "bars"),
+        layouts: config(app)?.layouts()?,
+        profile: string(app, "profile")
+            .map(|path| Profile::load(Path::new(&path)))
+            .transpose()?,
+        numbers: NumberFormat {
+            decimals: is_explicit(app, "decimals")
+                .then(|| parse_usize(app, "decimals"))
+                .transpose()?,
+            separator: string(app, "decimal_separator")
+                .and_then(|separator| separator.chars().next())
+                .unwrap_or('.'),
+        },
+    })
+}
+
+// sessions hold on to every request, only track them when needed
+fn session_timeout(
+    app: &ArgMatches,
+    settings: &Preset,
+    options: &ReportOptions,
+) -> Result<Option<Duration>> {
+    if !options.needs_sessions() {
+        return Ok(None);
+    }
+    let minutes = settings
+        .session_timeout
+        .map_or_else(|| parse_usize(app, "session_timeout"), Ok)?;
+    Ok(Some(Duration::minutes(minutes as i64)))
+}
+
+fn run(app: &ArgMatches, env_access_logs: Option<Vec<String>>) -> Result<()> {
+    if flag(app, "list_sections") {
+        let width = Section::ALL
+            .iter()
+            .map(|s| s.name().len())
+            .max()
+            .unwrap_or(0);
+        for section in Section::ALL {
+            println!("{:width$}  {}", section.name(), section.description());
+        }
+        return Ok(());
+    }
+
+    let settings = settings(app)?;
+    let top = settings.top.map_or_else(|| parse_usize(app, "top"), Ok)?;
+    let options = report_options(app, &settings)?;
+    let session_timeout = session_timeout(app, &settings, &options)?;
+    if let Some(path) = string(app, "load") {
+        let mut ti = top_info(app, &settings, top, session_timeout)?;
+        ti.load(Path::new(&path))?;
+        return write_output(app, &ti.report(&options), string(app, "out_file"), false);
+    }
+    if string(app, "explain").is_some() {
+        // fails on invalid drills and extra fields, like a real run
+        top_info(app, &settings, top, session_timeout)?;
+        return explain(app, &settings, &options, env_access_logs);
+    }
+    if flag(app, "estimate") {
+        let ti = top_info(app, &settings, top, session_timeout)?;
+        let mut out = BufWriter::new(io::stdout().lock());
+        ti.estimate(&access_logs(app, env_access_logs)?, jobs(app)?, &mut out)?;
+        out.flush()?;
+        return Ok(());
+    }
+    // the listen subcommand always binds an address
+    if flag(app, "follow") || string(app, "kafka").is_some() || string(app, "bind").is_some() {
+        let ti = top_info(app, &settings, top, session_timeout)?;
+        return follow(app, ti, &options, env_access_logs);
+    }
+    if let Some(expression) = string(app, "schedule") {
+        return schedule(
+            app,
+            &expression,
+            &settings,
+            top,
+            session_timeout,
+            &options,
+            env_access_logs,
+        );
+    }
+
+    check_outputs(app)?;
+    // the access logs are hashed before they're read, a file that grows
+    // in between is caught by Manifest::check_inputs
+    let manifest = string(app, "manifest")
+        .map(|path| {
+            let files = access_logs(app, env_access_logs.clone())?;
+            let manifest = Manifest::new(&files, &settings.filter()?, &options.sections)?;
+            Ok::<_, anyhow::Error>((path, manifest))
+        })
+        .transpose()?;
+
+    let ti = analyze(app, &settings, top, session_timeout, env_access_logs)?;
+    if let Some(path) = string(app, "save") {
+        ti.save(Path::new(&path), flag(app, "force"))?;
+    }
+    write_output(app, &ti.report(&options), string(app, "out_file"), false)?;
+
+    if let Some((path, mut manifest)) = manifest {
+        manifest.check_inputs()?;
+        let also = strings(app, "also_write").unwrap_or_default();
+        let outputs = string(app, "save")
+            .into_iter()
+            .chain(string(app, "out_file"))
+            .chain(
+                also.iter()
+                    .filter_map(|value| also_write(value).ok().map(|(_, path)| path)),
+            );
+        for output in outputs {
+            manifest.add_output(&output)?;
+        }
+        manifest.save(Path::new(&path), flag(app, "force"))?;
+    }
+    Ok(())
+}
+
+// fails on an existing output file without --force before any logs are read,
+// rather than once they're counted
+fn check_outputs(app: &ArgMatches) -> Result<()> {
+    if flag(app, "force") {
+        return Ok(());
+    }
+    let also = strings(app, "also_write").unwrap_or_default();
+    let outputs = string(app, "out_file")
+        .into_iter()
+        .chain(
+            also.iter()
+                .filter_map(|value| also_write(value).ok().map(|(_, path)| path)),
+        )
+        .chain(string(app, "save"))
+        .chain(string(app, "manifest"));
+    for output in outputs {
+        if Path::new(&output).exists() {
+            bail!("{output} already exists, use --force to overwrite it");
+        }
+    }
+    Ok(())
+}
+
+// hashes the files of a manifest again, failing if any changed
+fn verify_manifest(path: &Path) -> Result<()> {
+    let manifest = Manifest::load(path)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mismatches = manifest.verify(&mut out)?;
+    out.flush()?;
+    let files = manifest.inputs.len() + manifest.outputs.len();
+    if mismatches > 0 {
+        bail!(
+            "{mismatches} of {files} files don't match the manifest {}",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+// shows how the first lines of every access log would be counted
+fn explain(
+    app: &ArgMatches,
+    settings: &Preset,
+    options: &ReportOptions,
+    env_access_logs: Option<Vec<String>>,
+) -> Result<()> {
+    let lines = parse_usize(app, "explain")?;
+    let filter = settings.filter()?;
+    let read_options = read_options(app)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+
+    // like in the report, these sections are only shown when they're set up
+    let sections: Vec<Section> = options
+        .sections
+        .iter()
+        .copied()
+        .filter(|section| match section {
+            Section::Funnel => !options.funnel.is_empty(),
+            Section::IpAudit => options.ip_audit.is_enabled(),
+            Section::CertCoverage => options.cert_hosts.is_some(),
+            _ => true,
+        })
+        .collect();
+
+    let conditions = filter.describe();
+    if conditions.is_empty() {
+        writeln!(out, "Filters: none, every parsed line is counted")?;
+    } else {
+        writeln!(out, "Filters: {}", conditions.join(", "))?;
+    }
+    for (file, log_type) in access_logs(app, env_access_logs)? {
+        writeln!(out)?;
+        top_logs::explain(
+            &file,
+            log_type,
+            &read_options,
+            &filter,
+            &sections,
+            lines,
+            &mut out,
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+// writes a report at every tick of the cron `expression`, for the requests
+// logged since the tick before, until interrupted
+fn schedule(
+    app: &ArgMatches,
+    expression: &str,
+    settings: &Preset,
+    max_results: usize,
+    session_timeout: Option<Duration>,
+    options: &ReportOptions,
+    env_access_logs: Option<Vec<String>>,
+) -> Result<()> {
+    // the usual five fields of crontab, without seconds, are accepted too
+    let full = if expression.split_whitespace().count() == 5 {
+        format!("0 {expression}")
+    } else {
+        expression.to_string()
+    };
+    let schedule = cron::Schedule::from_str(&full)
+        .with_context(|| format!("invalid --schedule '{expression}'"))?;
+
+    loop {
+        let Some(tick) = schedule.upcoming(Local).next() else {
+            bail!("--schedule '{expression}' has no upcoming ticks");
+        };
+        let Some(start) = schedule.after(&tick).next_back() else {
+            bail!("--schedule '{expression}' has no tick before {tick}");
+        };
+        diagnostics::emit(
+            Kind::Progress,
+            &format!("Next report at {tick} covers {start} to {tick}"),
+        );
+        while Local::now() < tick {
+            let wait = (tick - Local::now()).to_std().unwrap_or_default();
+            thread::sleep(wait.min(time::Duration::from_secs(60)));
+        }
+
+        // a failed run, like a log missing after rotation, shouldn't stop the schedule
+        let report = || -> Result<()> {
+            let mut ti = top_info(app, settings, max_results, session_timeout)?;
+            ti.set_filter(Filter {
+                since: Some(start.fixed_offset()),
+                until: Some(tick.fixed_offset()),
+                ..settings.filter()?
+            });
+            // paths are expanded again, as rotation may have added files
+            ti.process_files(&access_logs(app, env_access_logs.clone())?, jobs(app)?)?;
+            let out_file = string(app, "out_file").map(|path| start.format(&path).to_string());
+            write_output(app, &ti.report(options), out_file, true)
+        };
+        if let Err(err) = report() {
+            diagnostics::emit(Kind::Error, &format!("{err:#}"));
+        }
+    }
+}
+
+// the format and file of an --also-write value, like json=report.json
+fn also_write(value: &str) -> Result<(OutputFormat, String), String> {
+    let (format, path) = value
+        .split_once('=')
+        .ok_or_else(|| format!("invalid --also-write '{value}', expected FORMAT=FILE"))?;
+    if path.trim().is_empty() {
+        return Err(format!(
+            "invalid --also-write '{value}', the file is missing"
+        ));
+    }
+    Ok((format.parse()?, path.trim().to_string()))
+}
+
+// writes the report in the selected format or template to `out_file` or STDOUT,
+// an existing `out_file` is only replaced with `overwrite` or --force
+fn write_output(
+    app: &ArgMatches,
+    report: &Report,
+    out_file: Option<String>,
+    overwrite: bool,
+) -> Result<()> {
+    let format = string(app, "output")
+        .unwrap_or_default()
+        .parse::<OutputFormat>()
+        .map_err(|e| anyhow!(e))?;
+    let template = string(app, "template");
+    // created up front, so an existing file fails before anything is written
+    let also = strings(app, "also_write")
+        .unwrap_or_default()
+        .iter()
+        .map(|value| {
+            let (format, path) = also_write(value).map_err(|e| anyhow!(e))?;
+            Ok((
+                format,
+                OutputFile::create(&path, overwrite || flag(app, "force"))?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // a template replaces the output format
+    let write = |mut out: &mut dyn Write| match &template {
+        Some(template) => top_logs::write_template(report, Path::new(template), &mut out),
+        None => top_logs::write_report(report, format, &mut out),
+    };
+
+    match out_file {
+        Some(path) => {
+            let mut out = OutputFile::create(&path, overwrite || flag(app, "force"))?;
+            write(&mut out)?;
+            out.finish()?;
+        }
+        None => {
+            if template.is_none() && format.is_binary() && io::stdout().is_terminal() {
+                bail!("{format} output is binary, use --out-file or redirect STDOUT");
+            }
+            write(&mut io::stdout().lock())?
+        }
+    }
+
+    for (format, mut out) in also {
+        top_logs::write_report(report, format, &mut out)?;
+        out.finish()?;
+    }
+    Ok(())
+}
+
+fn export_sqlite(app: &ArgMatches, env_access_logs: Option<Vec<String>>) -> Result<()> {
+    let settings = settings(app)?;
+    let ti = analyze(app, &settings, usize::MAX, None, env_access_logs)?;
+    ti.export_sqlite(Path::new(&string(app, "database").unwrap_or_default()))
+}
+
+fn trend_add(app: &ArgMatches, db: &Path, env_access_logs: Option<Vec<String>>) -> Result<()> {
+    let settings = settings(app)?;
+    let ti = analyze(app, &settings, usize::MAX, None, env_access_logs)?;
+    ti.add_trend(db)
+}
 
+fn merge_checkpoints(app: &ArgMatches) -> Result<()> {
+    let settings = settings(app)?;
+    let top = settings.top.map_or_else(|| parse_usize(app, "top"), Ok)?;
+    let options = report_options(app, &settings)?;
+    let session_timeout = session_timeout(app, &settings, &options)?;
+
+    let mut ti = top_info(app, &settings, top, session_timeout)?;
+    for path in strings(app, "checkpoints").unwrap_or_default() {
+        ti.load(Path::new(&path))?;
+    }
+    write_output(app, &ti.report(&options), string(app, "out_file"), false)
+}
+
+const VIEW_HELP: &str = "\
+sections            list the sections that can be shown
+show [SECTION...]   show these sections, or the whole report
+top NUM             show this many rows per table
+sort value|key      sort every table by count or name, `sort default` undoes it
+help                show this help
+quit                stop, like the end of STDIN";
+
+// reads commands from STDIN and shows the saved counts the way they ask for,
+// bad commands are reported and the next one is read
+fn view_counts(app: &ArgMatches) -> Result<()> {
+    let settings = settings(app)?;
+    let mut options = report_options(app, &settings)?;
+    let session_timeout = session_timeout(app, &settings, &options)?;
+    let format = string(app, "output")
+        .unwrap_or_default()
+        .parse::<OutputFormat>()
+        .map_err(|e| anyhow!(e))?;
+    if format.is_binary() {
+        bail!("{format} output is binary, view can't show it");
+    }
+    let path = string(app, "counts").unwrap_or_default();
+    // the top entries are picked while loading, so another --top loads the counts again
+    let load = |top: usize| -> Result<TopInfo> {
+        let mut ti = top_info(app, &settings, top, session_timeout)?;
+        ti.load(Path::new(&path))?;
+        Ok(ti)
+    };
+    let mut ti = load(settings.top.map_or_else(|| parse_usize(app, "top"), Ok)?)?;
+    let sections = options.sections.clone();
+
+    let prompt = io::stdin().is_terminal();
+    if prompt {
+        println!("{VIEW_HELP}");
+    }
+    let mut lines = io::stdin().lines();
+    loop {
+        if prompt {
+            print!("> ");
+            io::stdout().flush()?;
+        }
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let mut words = line.split_whitespace();
+        let result = match words.next() {
+            None => Ok(()),
+            Some("quit" | "exit") => break,
+            Some("help") => {
+                println!("{VIEW_HELP}");
+                Ok(())
+            }
+            Some("sections") => {
+                for section in &sections {
+                    println!("{}", section.name());
+                }
+                Ok(())
+            }
+            Some("show") => words
+                .map(|name| name.parse::<Section>().map_err(|e| anyhow!(e)))
+                .collect::<Result<Vec<_>>>()
+                .and_then(|shown| {
+                    // picked sections are shown without the summaries above them
+                    let options = if shown.is_empty() {
+                        options.clone()
+                    } else {
+                        ReportOptions {
+                            sections: shown,
+                            headline: false,
+                            sparkline: false,
+                            unique: false,
+                            concentration: false,
+                            outliers: false,
+                            ..options.clone()
+                        }
+                    };
+                    top_logs::write_report(&ti.report(&options), format, &mut io::stdout().lock())
+                }),
+            Some("top") => match words.next().map(str::parse::<usize>) {
+                Some(Ok(top)) => load(top).map(|loaded| ti = loaded),
+                _ => Err(anyhow!("expected the number of rows, like `top 20`")),
+            },
+            Some("sort") => match words.next() {
+                Some("default") => {
+                    options.sort = None;
+                    Ok(())
+                }
+                Some(order) => order
+                    .parse::<SortOrder>()
+                    .map(|order| options.sort = Some(order))
+                    .map_err(|e| anyhow!(e)),
+                None => Err(anyhow!("expected value, key or default")),
+            },
+            Some(command) => Err(anyhow!("unknown command '{command}', try `help`")),
+        };
+        if let Err(err) = result {
+            eprintln!("Error: {err:#}");
+        }
+    }
+    Ok(())
+}
+
+fn selftest() -> Result<()> {
+    if !top_logs::selftest(&mut io::stdout().lock())? {
+        bail!("the self-test failed");
+    }
+    Ok(())
+}
+
+fn trend_report(app: &ArgMatches, db: &Path) -> Result<()> {
+    let weeks = top_logs::load_trends(db)?;
+    let mut out = BufWriter::new(io::stdout().lock());
+    top_logs::write_trends(&weeks, parse_usize(app, "weeks")?, &mut out)?;
+    out.flush()?;
     Ok(())
 }
+
+fn export_jsonl(app: &ArgMatches, env_access_logs: Option<Vec<String>>) -> Result<()> {
+    let filter = settings(app)?.filter()?;
+    let options = read_options(app)?;
+
+    let tags = tags(app)?;
+
+    let files = access_logs(app, env_access_logs)?;
+    let write = |mut out: &mut dyn Write| -> Result<()> {
+        for (file, log_type) in &files {
+            // every record only has the file tags of its own file
+            let mut file_tags = tags.clone();
+            file_tags.add_file(file);
+            top_logs::write_json_lines(
+                file,
+                *log_type,
+                &options,
+                &filter,
+                &file_tags.values(),
+                &mut out,
+            )?;
+        }
+        Ok(())
+    };
+
+    match string(app, "out_file") {
+        Some(path) => {
+            let mut out = OutputFile::create(&path, flag(app, "force"))?;
+            write(&mut out)?;
+            out.finish()
+        }
+        None => {
+            let mut out = BufWriter::new(io::stdout().lock());
+            write(&mut out)?;
+            out.flush()?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,255 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Response times and errors by a field of the requests, like the path, backend
+//! or host, to see which endpoints, app instances or routes are slow or failing
+//! rather than only how the requests fare overall.
+use crate::report::{percent, round3, top_rows};
+use crate::{Entry, Latencies, SortOrder, Stats};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The field the response times are broken down by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BreakdownKey {
+    /// Without query params
+    Path,
+    /// The IP address of the app instance or platform VM, for gorouter logs
+    Backend,
+    /// The requested host, for gorouter and cloud_controller logs
+    Host,
+}
+
+impl BreakdownKey {
+    pub const ALL: [BreakdownKey; 3] = [
+        BreakdownKey::Path,
+        BreakdownKey::Backend,
+        BreakdownKey::Host,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BreakdownKey::Path => "path",
+            BreakdownKey::Backend => "backend",
+            BreakdownKey::Host => "host",
+        }
+    }
+
+    // the column header and, with an s, the title of the report
+    fn label(&self) -> &'static str {
+        match self {
+            BreakdownKey::Path => "Path",
+            BreakdownKey::Backend => "Backend",
+            BreakdownKey::Host => "Host",
+        }
+    }
+
+    fn value(&self, entry: &Entry) -> Option<String> {
+        match self {
+            BreakdownKey::Path => entry.path.clone(),
+            BreakdownKey::Backend => entry.backend_ip.map(|ip| ip.to_string()),
+            BreakdownKey::Host => entry.host.clone(),
+        }
+    }
+}
+
+impl fmt::Display for BreakdownKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for BreakdownKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match BreakdownKey::ALL
+            .iter()
+            .find(|key| key.name() == s.trim().to_lowercase())
+        {
+            Some(key) => Ok(*key),
+            None => bail!(
+                "invalid latency breakdown '{s}', expected one of: {}",
+                BreakdownKey::ALL.map(|key| key.name()).join(", ")
+            ),
+        }
+    }
+}
+
+// the requests, errors and response times of one value of the key
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct KeyTimes {
+    requests: usize,
+    server_errors: usize,
+    stats: Stats,
+    latencies: Latencies,
+}
+
+impl KeyTimes {
+    fn merge(&mut self, other: &KeyTimes) {
+        self.requests += other.requests;
+        self.server_errors += other.server_errors;
+        self.stats.merge(&other.stats);
+        self.latencies.merge(&other.latencies);
+    }
+}
+
+/// The requests and response times by the value of a key, like every path
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Breakdown {
+    key: BreakdownKey,
+    values: HashMap<String, KeyTimes>,
+}
+
+impl Breakdown {
+    pub fn new(key: BreakdownKey) -> Breakdown {
+        Breakdown {
+            key,
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn key(&self) -> BreakdownKey {
+        self.key
+    }
+
+    // the same key, without any requests
+    pub(crate) fn cleared(&self) -> Breakdown {
+        Breakdown::new(self.key)
+    }
+
+    // adds the requests counted by `other`, a breakdown by the same key
+    pub(crate) fn merge(&mut self, other: &Breakdown) {
+        for (value, times) in &other.values {
+            self.values.entry(value.clone()).or_default().merge(times);
+        }
+    }
+
+    // adds the requests of the saved breakdown by the same key, if there is one
+    pub(crate) fn restore(&mut self, saved: &[Breakdown]) {
+        if let Some(breakdown) = saved.iter().find(|saved| saved.key == self.key) {
+            self.merge(breakdown);
+        }
+    }
+
+    pub(crate) fn record(&mut self, entry: &Entry) {
+        let Some(value) = self.key.value(entry) else {
+            return;
+        };
+        let times = self.values.entry(value).or_default();
+        times.requests += 1;
+        if (500..600).contains(&entry.status) {
+            times.server_errors += 1;
+        }
+        if let Some(response_time) = entry.response_time {
+            times.stats.record(response_time);
+            times.latencies.record(response_time);
+        }
+    }
+
+    /// The `max` values with the most requests
+    pub(crate) fn report(&self, max: usize) -> BreakdownReport {
+        let rows = top_rows(
+            self.values
+                .iter()
+                .map(|(value, times)| (value, &times.requests)),
+            &SortOrder::ByValue,
+            max,
+        )
+        .into_iter()
+        .map(|row| {
+            let times = &self.values[&row.key];
+            BreakdownRow {
+                mean_response_time: times.stats.mean().map(round3),
+                p95_response_time: times.latencies.percentile(95.0),
+                server_errors: times.server_errors,
+                error_rate: percent(times.server_errors, row.count),
+                requests: row.count,
+                value: row.key,
+            }
+        })
+        .collect();
+        BreakdownReport {
+            key: self.key,
+            label: self.key.label(),
+            max,
+            distinct: self.values.len(),
+            rows,
+        }
+    }
+}
+
+/// The errors and response times of the top values of a `--latency-by` key
+#[derive(Debug, Serialize)]
+pub struct BreakdownReport {
+    pub key: BreakdownKey,
+    #[serde(skip)]
+    pub label: &'static str,
+    #[serde(skip)]
+    pub max: usize,
+    /// The number of different values seen
+    pub distinct: usize,
+    pub rows: Vec<BreakdownRow>,
+}
+
+/// The requests, errors and response times of one value
+#[derive(Debug, Serialize)]
+pub struct BreakdownRow {
+    pub value: String,
+    pub requests: usize,
+    pub server_errors: usize,
+    /// Percentage of requests with a 5xx response
+    pub error_rate: f64,
+    /// In seconds, only known for log formats that record response times
+    pub mean_response_time: Option<f64>,
+    pub p95_response_time: Option<f64>,
+}
+
+impl BreakdownReport {
+    /// The column headers of `BreakdownRow::cells`
+    pub fn headers(&self) -> [&'static str; 5] {
+        [
+            self.label,
+            "Requests",
+            "Error Rate (5xx)",
+            "Mean (s)",
+            "p95 (s)",
+        ]
+    }
+
+    pub fn title(&self) -> String {
+        format!(
+            "Errors and Response Times of the Top '{}' {}s by Requests ({} distinct):",
+            self.max, self.label, self.distinct
+        )
+    }
+}
+
+impl BreakdownRow {
+    /// The display value of each column, unknown response times are `-`
+    pub fn cells(&self) -> Vec<String> {
+        let seconds = |t: Option<f64>| t.map_or_else(|| "-".to_string(), |t| t.to_string());
+        vec![
+            self.value.clone(),
+            self.requests.to_string(),
+            format!("{}% ({})", self.error_rate, self.server_errors),
+            seconds(self.mean_response_time),
+            seconds(self.p95_response_time),
+        ]
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checkpoints of long runs, so an interrupted run can be resumed.
+//!
+//! A checkpoint holds the counts so far and how far every file has been read.
+//! Files are read in line aligned chunks and a checkpoint is written after
+//! the chunk or file that ends the checkpoint interval, and at the end.
+//!
+//! `TopInfo::save` writes the same format without any files, so saved
+//! analyses and checkpoints can be loaded and merged alike.
+use crate::diagnostics;
+use crate::state::State;
+use crate::TopInfo;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// how often a checkpoint is written, writing the counts takes a while on huge inputs
+const INTERVAL: Duration = Duration::from_secs(60);
+
+// how far a file has been read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Mark {
+    path: String,
+    // the bytes read, every line before this offset has been counted
+    offset: u64,
+    // the file has been read to its end
+    done: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Saved<'a> {
+    files: Vec<Mark>,
+    state: State<'a>,
+}
+
+/// Where a checkpoint is written and how far the files have been read
+#[derive(Debug)]
+pub(crate) struct Checkpoint {
+    path: PathBuf,
+    files: Vec<Mark>,
+    written: Instant,
+}
+
+impl Checkpoint {
+    // whether the file at `path`, or `range` of it, has been read already. A
+    // range read in part is shortened to the part still to read.
+    pub(crate) fn skip(&self, path: &str, range: &mut Option<Range<u64>>) -> Result<bool> {
+        let Some(mark) = self.files.iter().find(|mark| mark.path == path) else {
+            return Ok(false);
+        };
+        if mark.done {
+            return Ok(true);
+        }
+        match range {
+            Some(range) if mark.offset >= range.end => Ok(true),
+            Some(range) => {
+                range.start = range.start.max(mark.offset);
+                Ok(false)
+            }
+            None if mark.offset == 0 => Ok(false),
+            None => bail!(
+                "{path} was read up to byte {} before, but can't be resumed from there, it may have been compressed or changed since",
+                mark.offset
+            ),
+        }
+    }
+
+    // records that the file at `path` has been read up to `offset`, or to its end
+    pub(crate) fn advance(&mut self, path: &str, offset: u64, done: bool) {
+        match self.files.iter_mut().find(|mark| mark.path == path) {
+            Some(mark) => {
+                mark.offset = offset;
+                mark.done = done;
+            }
+            None => self.files.push(Mark {
+                path: path.to_string(),
+                offset,
+                done,
+            }),
+        }
+    }
+
+    // whether the checkpoint interval has passed since the last write
+    pub(crate) fn is_due(&self) -> bool {
+        self.written.elapsed() >= INTERVAL
+    }
+
+    pub(crate) fn write(&mut self, state: State) -> Result<()> {
+        write(
+            &self.path,
+            &Saved {
+                files: self.files.clone(),
+                state,
+            },
+        )?;
+        self.written = Instant::now();
+        Ok(())
+    }
+}
+
+// writes `saved` next to its final location and then moves it there, so an
+// interrupted write leaves the previous file intact
+fn write(path: &Path, saved: &Saved) -> Result<()> {
+    let partial = PathBuf::from(format!("{}.partial", path.display()));
+    let file =
+        fs::File::create(&partial).with_context(|| format!("creating {}", partial.display()))?;
+    let mut out = io::BufWriter::new(file);
+    bincode::serialize_into(&mut out, saved)
+        .with_context(|| format!("writing {}", partial.display()))?;
+    out.flush()?;
+    fs::rename(&partial, path).with_context(|| format!("writing {}", path.display()))
+}
+
+fn read(path: &Path) -> Result<Saved<'static>> {
+    let file = fs::File::open(path).with_context(|| format!("opening {}", path.display()))?;
+    bincode::deserialize_from(io::BufReader::new(file))
+        .with_context(|| format!("reading {}", path.display()))
+}
+
+impl TopInfo {
+    // writes the checkpoint, if there is one, when it's due or `now`
+    pub(crate) fn write_checkpoint(&mut self, now: bool) -> Result<()> {
+        if let Some(mut checkpoint) = self.checkpoint.take() {
+            let result = if now || checkpoint.is_due() {
+                checkpoint.write(self.state())
+            } else {
+                Ok(())
+            };
+            self.checkpoint = Some(checkpoint);
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Periodically write the counts and how far every file has been read to
+    /// `path` while `process_files` runs. If there's a checkpoint at `path`
+    /// already, its counts are restored and the files it has read, or the
+    /// parts of them, are skipped. Returns whether a checkpoint was resumed.
+    ///
+    /// A checkpoint has to be resumed with the same settings and files it was
+    /// written with, including the order of the files.
+    pub fn set_checkpoint(&mut self, path: &Path) -> Result<bool> {
+        let mut checkpoint = Checkpoint {
+            path: path.to_path_buf(),
+            files: Vec::new(),
+            written: Instant::now(),
+        };
+        let resumed = path.exists();
+        if resumed {
+            let saved = read(path)?;
+            self.restore(saved.state)
+                .with_context(|| format!("resuming checkpoint {}", path.display()))?;
+            checkpoint.files = saved.files;
+        }
+        self.checkpoint = Some(checkpoint);
+        Ok(resumed)
+    }
+
+    /// Writes the counts to `path`, to be rendered again later with `load`,
+    /// for example with another number of top entries or response time
+    /// threshold, without reading the logs again. Fails if `path` exists,
+    /// unless `overwrite` is set.
+    pub fn save(&self, path: &Path, overwrite: bool) -> Result<()> {
+        if !overwrite && path.exists() {
+            bail!(
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            );
+        }
+        write(
+            path,
+            &Saved {
+                files: Vec::new(),
+                state: self.state(),
+            },
+        )
+    }
+
+    /// Adds the counts saved at `path` with `save` or as a checkpoint to the
+    /// ones counted so far. Loading several files combines the results of
+    /// runs on different machines. Like when resuming, the counts have to be
+    /// made with the same sections, drills, extra fields and funnel. The
+    /// checkpoint of an unfinished run only adds what it has read so far.
+    pub fn load(&mut self, path: &Path) -> Result<()> {
+        let saved = read(path)?;
+        if saved.files.iter().any(|mark| !mark.done) {
+            diagnostics::emit(
+                diagnostics::Kind::Warning,
+                &format!(
+                    "{} is the checkpoint of an unfinished run, only the lines it has read are included",
+                    path.display()
+                ),
+            );
+        }
+        self.restore(saved.state)
+            .with_context(|| format!("loading {}", path.display()))
+    }
+}
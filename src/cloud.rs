@@ -0,0 +1,101 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use anyhow::{Context, Result};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{ObjectStore, ObjectStoreExt};
+use std::env;
+use std::io::{self, BufRead, Read};
+use tokio::runtime::Runtime;
+use url::Url;
+
+// URL schemes of the object stores, S3, Google Cloud Storage and Azure Blob Storage
+const SCHEMES: [&str; 5] = ["s3://", "gs://", "az://", "abfs://", "abfss://"];
+
+/// True for access logs in an object store, like `s3://bucket/key`
+pub(crate) fn is_object_url(path: &str) -> bool {
+    SCHEMES.iter().any(|scheme| path.starts_with(scheme))
+}
+
+// the store holding `url` and the object's path in it. Credentials and the
+// region come from the same environment variables as the cloud CLIs use,
+// like AWS_ACCESS_KEY_ID or GOOGLE_APPLICATION_CREDENTIALS.
+fn store(url: &str) -> Result<(Runtime, Box<dyn ObjectStore>, Path)> {
+    let parsed = Url::parse(url).with_context(|| format!("invalid URL {url}"))?;
+    let (store, path) = object_store::parse_url_opts(&parsed, env::vars())
+        .with_context(|| format!("opening {url}"))?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok((runtime, store, path))
+}
+
+/// The URLs of every object whose key starts with the prefix of `url`,
+/// sorted by key
+pub(crate) fn list_objects(url: &str) -> Result<Vec<String>> {
+    let (runtime, store, prefix) = store(url)?;
+    let mut keys: Vec<String> = runtime
+        .block_on(
+            store
+                .list(Some(&prefix))
+                .map_ok(|meta| meta.location.to_string())
+                .try_collect(),
+        )
+        .with_context(|| format!("listing {url}"))?;
+    keys.sort();
+
+    // the bucket or container is everything up to the key
+    let parsed = Url::parse(url)?;
+    let base = &parsed[..url::Position::BeforePath];
+    Ok(keys
+        .into_iter()
+        .map(|key| format!("{base}/{key}"))
+        .collect())
+}
+
+/// Streams the object at `url`, it's downloaded as it is read
+pub(crate) fn open_object(url: &str) -> Result<Box<dyn BufRead>> {
+    let (runtime, store, path) = store(url)?;
+    let stream = runtime
+        .block_on(store.get(&path))
+        .with_context(|| format!("fetching {url}"))?
+        .into_stream();
+    Ok(Box::new(io::BufReader::new(ObjectReader {
+        runtime,
+        stream,
+        chunk: Default::default(),
+    })))
+}
+
+// reads the chunks of an object's body, waiting for each in turn
+struct ObjectReader {
+    runtime: Runtime,
+    stream: BoxStream<'static, object_store::Result<bytes::Bytes>>,
+    chunk: bytes::Bytes,
+}
+
+impl Read for ObjectReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.runtime.block_on(self.stream.next()) {
+                Some(Ok(chunk)) => self.chunk = chunk,
+                Some(Err(err)) => return Err(io::Error::other(err)),
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk.split_to(len));
+        Ok(len)
+    }
+}
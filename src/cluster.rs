@@ -0,0 +1,93 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Groups near identical paths, like `/users/1` and `/users/2`, without
+//! normalization rules. Paths are compared segment by segment, paths with the
+//! same number of segments and the same first segment end up in one cluster
+//! when at most a third of their segments differ. The segments that differ
+//! become `*` in the path representing the cluster.
+use crate::DefaultHashMap;
+use std::collections::HashMap;
+
+const WILDCARD: &str = "*";
+
+struct Cluster {
+    segments: Vec<String>,
+    wildcards: usize,
+    requests: usize,
+}
+
+impl Cluster {
+    // adds `segments` if the cluster stays within `limit` wildcards with them
+    fn join(&mut self, segments: &[&str], requests: usize, limit: usize) -> bool {
+        let differing: Vec<usize> = self
+            .segments
+            .iter()
+            .zip(segments)
+            .enumerate()
+            .filter(|(_, (own, other))| *own != WILDCARD && own != *other)
+            .map(|(i, _)| i)
+            .collect();
+        if self.wildcards + differing.len() > limit {
+            return false;
+        }
+        for i in differing {
+            self.segments[i] = WILDCARD.to_string();
+        }
+        self.wildcards = self.segments.iter().filter(|s| *s == WILDCARD).count();
+        self.requests += requests;
+        true
+    }
+}
+
+/// The requests of `paths` by cluster, keyed by the path representing the
+/// cluster. Busier paths are clustered first, so the result doesn't depend on
+/// the order of `paths`.
+pub(crate) fn cluster_paths(
+    paths: &DefaultHashMap<String, usize>,
+) -> DefaultHashMap<String, usize> {
+    let mut sorted: Vec<(&String, &usize)> = paths.iter().collect();
+    sorted.sort_unstable_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+    // only paths with the same number of segments and first segment can share a cluster
+    let mut groups: HashMap<(usize, &str), Vec<Cluster>> = HashMap::new();
+    for (path, requests) in sorted {
+        let segments: Vec<&str> = path.split('/').collect();
+        let first = segments
+            .iter()
+            .find(|s| !s.is_empty())
+            .copied()
+            .unwrap_or("");
+        // the empty segment before the leading `/` isn't counted
+        let limit = (segments.len().saturating_sub(1) / 3).max(1);
+
+        let clusters = groups.entry((segments.len(), first)).or_default();
+        if !clusters
+            .iter_mut()
+            .any(|cluster| cluster.join(&segments, *requests, limit))
+        {
+            clusters.push(Cluster {
+                segments: segments.iter().map(|s| s.to_string()).collect(),
+                wildcards: 0,
+                requests: *requests,
+            });
+        }
+    }
+
+    let mut clustered = DefaultHashMap::new();
+    for cluster in groups.into_values().flatten() {
+        clustered[cluster.segments.join("/")] += cluster.requests;
+    }
+    clustered
+}
@@ -0,0 +1,184 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use anyhow::{bail, Context, Result};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Compression formats for access logs and output files, picked by file
+/// extension
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    pub(crate) fn from_extension(path: &str) -> Option<Compression> {
+        match Path::new(path).extension()?.to_str()? {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            "bz2" => Some(Compression::Bzip2),
+            "xz" => Some(Compression::Xz),
+            "tgz" => Some(Compression::Gzip),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn from_magic(header: &[u8]) -> Option<Compression> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if header.starts_with(b"BZh") {
+            Some(Compression::Bzip2)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Compression::Xz)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn decoder<'a, R: BufRead + 'a>(self, reader: R) -> Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            // log rotation and `cat` of several files produce multiple members
+            Compression::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::Decoder::with_buffer(reader)?),
+            Compression::Bzip2 => Box::new(bzip2::bufread::MultiBzDecoder::new(reader)),
+            Compression::Xz => Box::new(xz2::bufread::XzDecoder::new_multi_decoder(reader)),
+        })
+    }
+}
+
+enum Encoder {
+    Plain(BufWriter<File>),
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+    Bzip2(bzip2::write::BzEncoder<BufWriter<File>>),
+    Xz(xz2::write::XzEncoder<BufWriter<File>>),
+}
+
+// the temporary file an output is written to, removed again unless it
+// replaces the output file
+struct TempFile {
+    temp: PathBuf,
+    path: PathBuf,
+    persisted: bool,
+}
+
+impl TempFile {
+    fn persist(mut self) -> Result<()> {
+        fs::rename(&self.temp, &self.path).with_context(|| {
+            format!(
+                "renaming {} to {}",
+                self.temp.display(),
+                self.path.display()
+            )
+        })?;
+        self.persisted = true;
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = fs::remove_file(&self.temp);
+        }
+    }
+}
+
+/// A file written through the compression its extension asks for, like
+/// `report.json.gz`. Everything is written to a temporary file next to it,
+/// which only replaces the file when `finish` is called, so a failed or
+/// interrupted run never leaves a truncated file behind.
+pub struct OutputFile {
+    encoder: Encoder,
+    target: TempFile,
+}
+
+impl OutputFile {
+    /// Fails if `path` exists, unless `overwrite` is set
+    pub fn create(path: &str, overwrite: bool) -> Result<OutputFile> {
+        let path = PathBuf::from(path);
+        if !overwrite && path.exists() {
+            bail!(
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            );
+        }
+
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let temp = path.with_file_name(format!(".{name}.{}.tmp", process::id()));
+        let file = File::create(&temp).with_context(|| format!("creating {}", temp.display()))?;
+        let target = TempFile {
+            temp,
+            path,
+            persisted: false,
+        };
+
+        let file = BufWriter::new(file);
+        let encoder = match Compression::from_extension(&target.path.to_string_lossy()) {
+            None => Encoder::Plain(file),
+            Some(Compression::Gzip) => Encoder::Gzip(flate2::write::GzEncoder::new(
+                file,
+                flate2::Compression::default(),
+            )),
+            Some(Compression::Zstd) => Encoder::Zstd(zstd::Encoder::new(file, 0)?),
+            Some(Compression::Bzip2) => Encoder::Bzip2(bzip2::write::BzEncoder::new(
+                file,
+                bzip2::Compression::default(),
+            )),
+            Some(Compression::Xz) => Encoder::Xz(xz2::write::XzEncoder::new(file, 6)),
+        };
+        Ok(OutputFile { encoder, target })
+    }
+
+    /// Writes the end of the compressed stream and moves the file in place
+    pub fn finish(self) -> Result<()> {
+        let OutputFile { encoder, target } = self;
+        let file = match encoder {
+            Encoder::Plain(file) => file,
+            Encoder::Gzip(encoder) => encoder.finish()?,
+            Encoder::Zstd(encoder) => encoder.finish()?,
+            Encoder::Bzip2(encoder) => encoder.finish()?,
+            Encoder::Xz(encoder) => encoder.finish()?,
+        };
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        target.persist()
+    }
+
+    fn writer(&mut self) -> &mut dyn Write {
+        match &mut self.encoder {
+            Encoder::Plain(file) => file,
+            Encoder::Gzip(encoder) => encoder,
+            Encoder::Zstd(encoder) => encoder,
+            Encoder::Bzip2(encoder) => encoder,
+            Encoder::Xz(encoder) => encoder,
+        }
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer().flush()
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::report::{percent, round3};
+use crate::TopInfo;
+use serde::Serialize;
+
+/// How evenly the requests are spread over the clients and paths
+#[derive(Debug, Serialize)]
+pub struct Concentration {
+    pub client_ips: Option<Share>,
+    /// Without query params
+    pub paths: Option<Share>,
+}
+
+/// The share of the requests of the busiest values of a field
+#[derive(Debug, Serialize)]
+pub struct Share {
+    /// The number of distinct values
+    pub values: usize,
+    /// Percentage of the requests from the busiest 1% of the values
+    pub top_1_percent: f64,
+    /// Percentage of the requests from the busiest 10% of the values
+    pub top_10_percent: f64,
+    /// 0 when every value has the same number of requests, close to 1 when
+    /// one value has nearly all of them
+    pub gini: f64,
+}
+
+impl Share {
+    // `None` without any requests
+    fn new<'a, I>(counts: I) -> Option<Share>
+    where
+        I: Iterator<Item = &'a usize>,
+    {
+        let mut counts: Vec<usize> = counts.copied().collect();
+        counts.sort_unstable();
+        let total: usize = counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+
+        // the busiest `share` percent of the values, at least one
+        let n = counts.len();
+        let top = |share: usize| {
+            let k = (n * share).div_ceil(100).max(1);
+            let requests: usize = counts.iter().rev().take(k).sum();
+            percent(requests, total)
+        };
+        let weighted: f64 = counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (i + 1) as f64 * *count as f64)
+            .sum();
+        let gini = 2.0 * weighted / (n as f64 * total as f64) - (n as f64 + 1.0) / n as f64;
+
+        Some(Share {
+            values: n,
+            top_1_percent: top(1),
+            top_10_percent: top(10),
+            gini: round3(gini),
+        })
+    }
+}
+
+impl Concentration {
+    /// Label and display value of each field, in display order
+    pub fn items(&self) -> Vec<(&'static str, String)> {
+        let value = |share: &Option<Share>| match share {
+            Some(share) => format!(
+                "top 1% {}%, top 10% {}%, Gini {} ({} values)",
+                share.top_1_percent, share.top_10_percent, share.gini, share.values
+            ),
+            None => "n/a".into(),
+        };
+        vec![
+            ("Client IPs", value(&self.client_ips)),
+            ("Paths", value(&self.paths)),
+        ]
+    }
+}
+
+impl TopInfo {
+    pub(crate) fn concentration(&self) -> Concentration {
+        Concentration {
+            client_ips: Share::new(self.clients().values()),
+            paths: Share::new(self.requests_no_query.values()),
+        }
+    }
+}
@@ -0,0 +1,268 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::audit::{HostList, IpAudit, IpList};
+use crate::filter::{Filter, StatusFilter, TimeWindow};
+use crate::layout::SectionLayout;
+use crate::section::Section;
+use crate::SortOrder;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A named bundle of analysis settings. Every field is optional, unset fields
+/// fall back to the command line defaults.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Preset {
+    pub sections: Option<Vec<String>>,
+    pub top: Option<usize>,
+    pub min_response_time_threshold: Option<usize>,
+    pub sort: Option<String>,
+    pub status: Option<Vec<String>>,
+    pub methods: Option<Vec<String>>,
+    pub path: Option<String>,
+    pub business_hours: Option<String>,
+    pub exclude_hours: Option<Vec<String>>,
+    pub weekdays_only: Option<bool>,
+    pub utc_offset: Option<String>,
+    pub headline: Option<bool>,
+    pub funnel: Option<Vec<String>>,
+    pub session_timeout: Option<usize>,
+    pub allowlist: Option<PathBuf>,
+    pub denylist: Option<PathBuf>,
+    pub cert_hosts: Option<PathBuf>,
+}
+
+impl Preset {
+    /// Returns a preset with the values from `self`, falling back to `other` for unset values
+    pub fn or(self, other: Preset) -> Preset {
+        Preset {
+            sections: self.sections.or(other.sections),
+            top: self.top.or(other.top),
+            min_response_time_threshold: self
+                .min_response_time_threshold
+                .or(other.min_response_time_threshold),
+            sort: self.sort.or(other.sort),
+            status: self.status.or(other.status),
+            methods: self.methods.or(other.methods),
+            path: self.path.or(other.path),
+            business_hours: self.business_hours.or(other.business_hours),
+            exclude_hours: self.exclude_hours.or(other.exclude_hours),
+            weekdays_only: self.weekdays_only.or(other.weekdays_only),
+            utc_offset: self.utc_offset.or(other.utc_offset),
+            headline: self.headline.or(other.headline),
+            funnel: self.funnel.or(other.funnel),
+            session_timeout: self.session_timeout.or(other.session_timeout),
+            allowlist: self.allowlist.or(other.allowlist),
+            denylist: self.denylist.or(other.denylist),
+            cert_hosts: self.cert_hosts.or(other.cert_hosts),
+        }
+    }
+
+    pub fn sections(&self) -> Result<Vec<Section>> {
+        match &self.sections {
+            Some(sections) => sections
+                .iter()
+                .map(|s| s.parse().map_err(|e: String| anyhow!(e)))
+                .collect(),
+            None => Ok(Section::defaults()),
+        }
+    }
+
+    pub fn sort_order(&self) -> Result<Option<SortOrder>> {
+        self.sort
+            .as_deref()
+            .map(|s| s.parse().map_err(|e: String| anyhow!(e)))
+            .transpose()
+    }
+
+    pub fn filter(&self) -> Result<Filter> {
+        Ok(Filter {
+            statuses: self
+                .status
+                .iter()
+                .flatten()
+                .map(|s| s.parse::<StatusFilter>().map_err(|e| anyhow!(e)))
+                .collect::<Result<_>>()?,
+            methods: self
+                .methods
+                .iter()
+                .flatten()
+                .map(|m| {
+                    m.trim()
+                        .to_uppercase()
+                        .parse()
+                        .with_context(|| format!("invalid method '{m}'"))
+                })
+                .collect::<Result<_>>()?,
+            path_prefix: self.path.clone(),
+            hours: self
+                .business_hours
+                .as_deref()
+                .map(|hours| hours.parse::<TimeWindow>().map_err(|e| anyhow!(e)))
+                .transpose()?,
+            excluded_hours: self
+                .exclude_hours
+                .iter()
+                .flatten()
+                .map(|hours| hours.parse::<TimeWindow>().map_err(|e| anyhow!(e)))
+                .collect::<Result<_>>()?,
+            weekdays_only: self.weekdays_only.unwrap_or(false),
+            utc_offset: self
+                .utc_offset
+                .as_deref()
+                .map(|offset| {
+                    offset
+                        .trim()
+                        .parse()
+                        .with_context(|| format!("invalid UTC offset '{offset}'"))
+                })
+                .transpose()?,
+            ..Filter::default()
+        })
+    }
+
+    pub fn ip_audit(&self) -> Result<IpAudit> {
+        Ok(IpAudit {
+            allow: self.allowlist.as_deref().map(IpList::load).transpose()?,
+            deny: self.denylist.as_deref().map(IpList::load).transpose()?,
+        })
+    }
+
+    pub fn cert_hosts(&self) -> Result<Option<HostList>> {
+        self.cert_hosts.as_deref().map(HostList::load).transpose()
+    }
+
+    fn builtin(name: &str) -> Option<Preset> {
+        let strings = |values: &[&str]| Some(values.iter().map(|v| v.to_string()).collect());
+
+        match name {
+            "cf-incident" => Some(Preset {
+                sections: strings(&[
+                    "response-codes",
+                    "requests-no-query",
+                    "backend-ips",
+                    "hosts",
+                    "app-ids",
+                    "app-indexes",
+                    "response-times",
+                    "gorouter-times",
+                    "x-cf-routererrors",
+                ]),
+                top: Some(20),
+                min_response_time_threshold: Some(10),
+                sort: Some("value".into()),
+                status: strings(&["5xx"]),
+                ..Preset::default()
+            }),
+            "web-traffic" => Some(Preset {
+                sections: strings(&[
+                    "response-codes",
+                    "request-methods",
+                    "requests-no-query",
+                    "requests-query",
+                    "user-agents",
+                    "platforms",
+                    "referrers",
+                    "client-ips",
+                ]),
+                top: Some(25),
+                ..Preset::default()
+            }),
+            "security" => Some(Preset {
+                sections: strings(&[
+                    "response-codes",
+                    "request-methods",
+                    "requests-query",
+                    "user-agents",
+                    "client-ips",
+                    "x-forwarded-fors",
+                ]),
+                top: Some(20),
+                sort: Some("value".into()),
+                status: strings(&["4xx", "5xx"]),
+                ..Preset::default()
+            }),
+            _ => None,
+        }
+    }
+
+    pub const BUILTIN_NAMES: [&'static str; 3] = ["cf-incident", "web-traffic", "security"];
+}
+
+/// The optional top-logs configuration file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub presets: HashMap<String, Preset>,
+    /// Section layouts by section name
+    pub sections: HashMap<String, SectionLayout>,
+}
+
+impl Config {
+    /// Loads the configuration from `path` or, when not given, from the default
+    /// location if a file exists there
+    pub fn load(path: Option<&Path>) -> Result<Config> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Config::default_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("reading config file {}", path.display()))?;
+        toml::from_str(&contents).with_context(|| format!("parsing config file {}", path.display()))
+    }
+
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("top-logs").join("config.toml"))
+    }
+
+    /// The section layouts by section, fails on unknown section or column names
+    pub fn layouts(&self) -> Result<HashMap<Section, SectionLayout>> {
+        self.sections
+            .iter()
+            .map(|(name, layout)| {
+                let section = name.parse().map_err(|e: String| anyhow!(e))?;
+                layout
+                    .validate()
+                    .with_context(|| format!("invalid layout of section '{name}'"))?;
+                Ok((section, layout.clone()))
+            })
+            .collect()
+    }
+
+    /// Looks up a preset by name, user defined presets take precedence over built-in ones
+    pub fn preset(&self, name: &str) -> Result<Preset> {
+        self.presets
+            .get(name)
+            .cloned()
+            .or_else(|| Preset::builtin(name))
+            .ok_or_else(|| {
+                let mut names: Vec<&str> = Preset::BUILTIN_NAMES.to_vec();
+                names.extend(self.presets.keys().map(|k| k.as_str()));
+                names.sort_unstable();
+                names.dedup();
+                anyhow!(
+                    "unknown preset '{}', available presets: {}",
+                    name,
+                    names.join(", ")
+                )
+            })
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A `HashMap` which reads missing keys as the default value and inserts it
+//! when they're written, so counting is `map[key] += 1`.
+//!
+//! It's like the one of the `defaultmap` crate, but always defaults to
+//! `V::default()` instead of boxing a function making the default, so a
+//! `TopInfo` can be counted on one thread and merged on another. It's saved
+//! in the same layout too.
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::collections::hash_map::{Iter, IterMut, Keys, Values};
+use std::collections::{hash_map, HashMap};
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::ops::{Index, IndexMut};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DefaultHashMap<K: Eq + Hash, V> {
+    map: HashMap<K, V>,
+    // returned for missing keys, without inserting them
+    default: V,
+}
+
+impl<K: Eq + Hash, V: Default> DefaultHashMap<K, V> {
+    pub fn new() -> DefaultHashMap<K, V> {
+        DefaultHashMap {
+            map: HashMap::new(),
+            default: V::default(),
+        }
+    }
+
+    /// The value of `key`, inserting the default value if it's missing
+    pub fn get_mut(&mut self, key: K) -> &mut V {
+        self.map.entry(key).or_default()
+    }
+}
+
+impl<K: Eq + Hash, V: Default> Default for DefaultHashMap<K, V> {
+    fn default() -> DefaultHashMap<K, V> {
+        DefaultHashMap::new()
+    }
+}
+
+impl<K: Eq + Hash, V> DefaultHashMap<K, V> {
+    /// The value of `key`, or the default value if it's missing
+    pub fn get<Q, QB: Borrow<Q>>(&self, key: QB) -> &V
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.map.get(key.borrow()).unwrap_or(&self.default)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        self.map.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        self.map.iter_mut()
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        self.map.keys()
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        self.map.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map.insert(key, value)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.map.remove(key)
+    }
+
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&K, &mut V) -> bool,
+    {
+        self.map.retain(f)
+    }
+}
+
+impl<K: Eq + Hash, KB: Borrow<K>, V> Index<KB> for DefaultHashMap<K, V> {
+    type Output = V;
+
+    fn index(&self, key: KB) -> &V {
+        self.get(key)
+    }
+}
+
+impl<K: Eq + Hash, V: Default> IndexMut<K> for DefaultHashMap<K, V> {
+    fn index_mut(&mut self, key: K) -> &mut V {
+        self.get_mut(key)
+    }
+}
+
+impl<K: Eq + Hash, V: Default> FromIterator<(K, V)> for DefaultHashMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> DefaultHashMap<K, V> {
+        DefaultHashMap {
+            map: HashMap::from_iter(iter),
+            default: V::default(),
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> IntoIterator for DefaultHashMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = hash_map::IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+impl<'a, K: Eq + Hash, V> IntoIterator for &'a DefaultHashMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.iter()
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Everything that isn't the report goes through here and ends up on STDERR.
+//!
+//! In structured mode every diagnostic is a single line formatted as
+//! `top-logs: <kind>: <message>`, so it can be told apart from the report and
+//! parsed by other tools.
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static STRUCTURED: AtomicBool = AtomicBool::new(false);
+
+/// The kind of a diagnostic, used as the prefix in structured mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Error,
+    Warning,
+    ParseError,
+    ReadError,
+    Progress,
+}
+
+impl Kind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Error => "error",
+            Kind::Warning => "warning",
+            Kind::ParseError => "parse-error",
+            Kind::ReadError => "read-error",
+            Kind::Progress => "progress",
+        }
+    }
+}
+
+pub fn set_structured(structured: bool) {
+    STRUCTURED.store(structured, Ordering::Relaxed);
+}
+
+pub fn is_structured() -> bool {
+    STRUCTURED.load(Ordering::Relaxed)
+}
+
+/// Writes a diagnostic to STDERR. `message` is shown as is, unless structured
+/// mode is on in which case it is prefixed and collapsed onto one line.
+pub fn emit(kind: Kind, message: &str) {
+    crate::progress::suspend(|| {
+        if is_structured() {
+            eprintln!(
+                "top-logs: {}: {}",
+                kind.name(),
+                message.trim_end().replace('\r', "").replace('\n', "\\n")
+            );
+        } else {
+            eprintln!("{message}");
+        }
+    })
+}
@@ -0,0 +1,310 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::filter::StatusFilter;
+use crate::parallel::add_counts;
+use crate::report::{status_label, top_rows, ReportRow};
+use crate::{path_matches, DefaultHashMap, Entry, Latencies, SortOrder};
+use anyhow::{anyhow, bail, Result};
+use http::StatusCode;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+// the counts of a drill, without its selector, as saved by `TopInfo::state`
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct DrillState {
+    key: String,
+    requests: usize,
+    paths: DefaultHashMap<String, usize>,
+    client_ips: DefaultHashMap<String, usize>,
+    statuses: Vec<(u16, usize)>,
+    user_agents: DefaultHashMap<String, usize>,
+    latencies: Latencies,
+}
+
+/// The value `--drill` and `--timeline` select requests by
+#[derive(Debug, Clone)]
+pub(crate) enum Selector {
+    /// The path without query params, `*` matches any characters
+    Path(String),
+    /// The client IP, or the first X-Forwarded-For address, in a range
+    ClientIp(IpNet),
+    Host(String),
+    /// `*` matches any characters
+    UserAgent(String),
+    Status(StatusFilter),
+}
+
+/// Breaks down the requests with one path, client, host, user agent or
+/// status by the other fields, counted in the same pass as the report
+#[derive(Debug, Clone)]
+pub struct Drill {
+    key: String,
+    selector: Selector,
+    requests: usize,
+    paths: DefaultHashMap<String, usize>,
+    client_ips: DefaultHashMap<String, usize>,
+    statuses: DefaultHashMap<StatusCode, usize>,
+    user_agents: DefaultHashMap<String, usize>,
+    latencies: Latencies,
+}
+
+impl Selector {
+    /// Parses `field=value`, like `path=/api/v1/orders` or `client-ip=10.0.0.0/8`,
+    /// into the selector and its key. `option` names the option in errors.
+    pub(crate) fn parse(s: &str, option: &str) -> Result<(String, Selector)> {
+        let (field, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid {option} '{s}', expected FIELD=VALUE"))?;
+        let value = value.trim();
+        let selector = match field.trim().to_lowercase().replace('_', "-").as_str() {
+            "path" => Selector::Path(value.to_string()),
+            "client-ip" => Selector::ClientIp(match value.parse::<IpAddr>() {
+                Ok(ip) => IpNet::from(ip),
+                Err(_) => value.parse().map_err(|_| {
+                    anyhow!("invalid IP address or range '{value}' in {option} '{s}'")
+                })?,
+            }),
+            "host" => Selector::Host(value.to_lowercase()),
+            "user-agent" => Selector::UserAgent(value.to_string()),
+            "status" => Selector::Status(value.parse().map_err(|e: String| anyhow!(e))?),
+            _ => bail!(
+                "invalid {option} field '{field}', expected path, client-ip, host, user-agent or status"
+            ),
+        };
+        Ok((format!("{}={value}", field.trim()), selector))
+    }
+
+    pub(crate) fn matches(&self, entry: &Entry) -> bool {
+        match self {
+            Selector::Path(pattern) => entry
+                .path
+                .as_deref()
+                .is_some_and(|path| path_matches(pattern, path)),
+            Selector::ClientIp(net) => entry.client().is_some_and(|ip| net.contains(&ip)),
+            Selector::Host(host) => entry
+                .host
+                .as_deref()
+                .is_some_and(|h| h.eq_ignore_ascii_case(host)),
+            Selector::UserAgent(pattern) => entry
+                .user_agent
+                .as_deref()
+                .is_some_and(|ua| path_matches(pattern, ua)),
+            Selector::Status(status) => {
+                StatusCode::from_u16(entry.status).is_ok_and(|code| status.matches(code))
+            }
+        }
+    }
+}
+
+impl FromStr for Drill {
+    type Err = anyhow::Error;
+
+    /// Parses `field=value`, like `path=/api/v1/orders` or `client-ip=10.0.0.0/8`
+    fn from_str(s: &str) -> Result<Self> {
+        let (key, selector) = Selector::parse(s, "drill")?;
+        Ok(Drill {
+            key,
+            selector,
+            requests: 0,
+            paths: DefaultHashMap::new(),
+            client_ips: DefaultHashMap::new(),
+            statuses: DefaultHashMap::new(),
+            user_agents: DefaultHashMap::new(),
+            latencies: Latencies::default(),
+        })
+    }
+}
+
+impl Drill {
+    // the same selection, without any requests
+    pub(crate) fn cleared(&self) -> Drill {
+        Drill {
+            key: self.key.clone(),
+            selector: self.selector.clone(),
+            requests: 0,
+            paths: DefaultHashMap::new(),
+            client_ips: DefaultHashMap::new(),
+            statuses: DefaultHashMap::new(),
+            user_agents: DefaultHashMap::new(),
+            latencies: Latencies::default(),
+        }
+    }
+
+    // adds the requests counted by `other`, a cleared copy of this drill
+    pub(crate) fn merge(&mut self, other: &Drill) {
+        self.requests += other.requests;
+        add_counts(&mut self.paths, &other.paths);
+        add_counts(&mut self.client_ips, &other.client_ips);
+        add_counts(&mut self.statuses, &other.statuses);
+        add_counts(&mut self.user_agents, &other.user_agents);
+        self.latencies.merge(&other.latencies);
+    }
+
+    // the requests counted so far, to be saved with the other counts
+    pub(crate) fn state(&self) -> DrillState {
+        DrillState {
+            key: self.key.clone(),
+            requests: self.requests,
+            paths: self.paths.clone(),
+            client_ips: self.client_ips.clone(),
+            statuses: self
+                .statuses
+                .iter()
+                .map(|(status, count)| (status.as_u16(), *count))
+                .collect(),
+            user_agents: self.user_agents.clone(),
+            latencies: self.latencies.clone(),
+        }
+    }
+
+    // adds the requests of the saved drill with the same key, if there is one
+    pub(crate) fn restore(&mut self, saved: &[DrillState]) {
+        let Some(state) = saved.iter().find(|state| state.key == self.key) else {
+            return;
+        };
+        self.requests += state.requests;
+        add_counts(&mut self.paths, &state.paths);
+        add_counts(&mut self.client_ips, &state.client_ips);
+        for (status, count) in &state.statuses {
+            if let Ok(status) = StatusCode::from_u16(*status) {
+                self.statuses[status] += count;
+            }
+        }
+        add_counts(&mut self.user_agents, &state.user_agents);
+        self.latencies.merge(&state.latencies);
+    }
+
+    pub(crate) fn record(&mut self, entry: &Entry) {
+        if !self.selector.matches(entry) {
+            return;
+        }
+
+        self.requests += 1;
+        if let Some(path) = &entry.path {
+            self.paths[path.clone()] += 1;
+        }
+        if let Some(ip) = entry.client() {
+            self.client_ips[ip.to_string()] += 1;
+        }
+        if let Ok(status) = StatusCode::from_u16(entry.status) {
+            self.statuses[status] += 1;
+        }
+        if let Some(user_agent) = &entry.user_agent {
+            self.user_agents[user_agent.clone()] += 1;
+        }
+        if let Some(response_time) = entry.response_time {
+            self.latencies.record(response_time);
+        }
+    }
+
+    /// The top `max` rows of every field but the one drilled into
+    pub(crate) fn report(&self, max: usize) -> DrillReport {
+        let by_value = &SortOrder::ByValue;
+        let mut tables = Vec::new();
+        let mut add = |title: String, key_label, rows: Vec<ReportRow>| {
+            if !rows.is_empty() {
+                tables.push(DrillTable {
+                    title,
+                    key_label,
+                    rows,
+                })
+            }
+        };
+
+        if !matches!(self.selector, Selector::ClientIp(_)) {
+            add(
+                format!("Top '{max}' Client IPs"),
+                "Client IP",
+                top_rows(self.client_ips.iter(), by_value, max),
+            );
+        }
+        if !matches!(self.selector, Selector::Status(_)) {
+            add(
+                "Response Codes".into(),
+                "Response Code",
+                top_rows(
+                    self.statuses
+                        .iter()
+                        .map(|(code, count)| (status_label(code), count)),
+                    &SortOrder::ByKey,
+                    usize::MAX,
+                ),
+            );
+        }
+        if !matches!(self.selector, Selector::Path(_)) {
+            add(
+                format!("Top '{max}' Paths"),
+                "Path",
+                top_rows(self.paths.iter(), by_value, max),
+            );
+        }
+        if !matches!(self.selector, Selector::UserAgent(_)) {
+            add(
+                format!("Top '{max}' User Agents"),
+                "User Agent",
+                top_rows(self.user_agents.iter(), by_value, max),
+            );
+        }
+
+        DrillReport {
+            key: self.key.clone(),
+            requests: self.requests,
+            p50_response_time: self.latencies.percentile(50.0),
+            p95_response_time: self.latencies.percentile(95.0),
+            p99_response_time: self.latencies.percentile(99.0),
+            tables,
+        }
+    }
+}
+
+/// The summary of the requests selected by a `--drill`
+#[derive(Debug, Serialize)]
+pub struct DrillReport {
+    /// The field and value drilled into, like `path=/api/v1/orders`
+    pub key: String,
+    pub requests: usize,
+    /// In seconds, only known for log formats that record response times
+    pub p50_response_time: Option<f64>,
+    pub p95_response_time: Option<f64>,
+    pub p99_response_time: Option<f64>,
+    pub tables: Vec<DrillTable>,
+}
+
+/// One breakdown of a drill, like the client IPs of a path
+#[derive(Debug, Serialize)]
+pub struct DrillTable {
+    pub title: String,
+    /// Describes the key column, like the section key labels
+    pub key_label: &'static str,
+    pub rows: Vec<ReportRow>,
+}
+
+impl DrillReport {
+    /// Label and display value of each summary item, in display order
+    pub fn items(&self) -> Vec<(&'static str, String)> {
+        let seconds = |value: Option<f64>| {
+            value
+                .map(|t| format!("{t}s"))
+                .unwrap_or_else(|| "n/a".into())
+        };
+        vec![
+            ("Requests", self.requests.to_string()),
+            ("p50 Response Time", seconds(self.p50_response_time)),
+            ("p95 Response Time", seconds(self.p95_response_time)),
+            ("p99 Response Time", seconds(self.p99_response_time)),
+        ]
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::reader::{read_log, ReadOptions};
+use crate::Filter;
+use access_log_parser::{LogEntry, LogType, RequestResult};
+use anyhow::Result;
+use chrono::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::net::IpAddr;
+
+/// A log entry normalized across all of the supported log formats. Fields a
+/// format doesn't record are `None`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    pub timestamp: DateTime<FixedOffset>,
+    pub method: Option<String>,
+    /// The request path without query params, or the raw path if it isn't valid
+    pub path: Option<String>,
+    pub query: Option<String>,
+    pub status: u16,
+    /// In seconds
+    pub response_time: Option<f64>,
+    pub client_ip: Option<IpAddr>,
+    pub x_forwarded_for: Vec<IpAddr>,
+    pub backend_ip: Option<IpAddr>,
+    pub host: Option<String>,
+    pub user_agent: Option<String>,
+    pub referrer: Option<String>,
+    pub bytes_sent: u64,
+    pub bytes_received: Option<u64>,
+}
+
+// method, path and query of a request
+fn request_parts(request: &RequestResult) -> (Option<String>, Option<String>, Option<String>) {
+    match request {
+        RequestResult::Valid(req) => (
+            Some(req.method().to_string()),
+            Some(req.uri().path().to_string()),
+            req.uri().query().map(String::from),
+        ),
+        RequestResult::InvalidPath(path, _err) => (None, Some(path.to_string()), None),
+        RequestResult::InvalidRequest(_) => (None, None, None),
+    }
+}
+
+impl Entry {
+    pub fn new(log_entry: &LogEntry) -> Entry {
+        match log_entry {
+            LogEntry::CommonLog(log) => {
+                let (method, path, query) = request_parts(&log.request);
+                Entry {
+                    timestamp: log.timestamp,
+                    method,
+                    path,
+                    query,
+                    status: log.status_code.as_u16(),
+                    response_time: None,
+                    client_ip: Some(log.ip),
+                    x_forwarded_for: Vec::new(),
+                    backend_ip: None,
+                    host: None,
+                    user_agent: None,
+                    referrer: None,
+                    bytes_sent: log.bytes,
+                    bytes_received: None,
+                }
+            }
+            LogEntry::CombinedLog(log) => {
+                let (method, path, query) = request_parts(&log.request);
+                Entry {
+                    timestamp: log.timestamp,
+                    method,
+                    path,
+                    query,
+                    status: log.status_code.as_u16(),
+                    response_time: None,
+                    client_ip: Some(log.ip),
+                    x_forwarded_for: Vec::new(),
+                    backend_ip: None,
+                    host: None,
+                    user_agent: log.user_agent.map(String::from),
+                    referrer: log.referrer.as_ref().map(|r| r.to_string()),
+                    bytes_sent: log.bytes,
+                    bytes_received: None,
+                }
+            }
+            LogEntry::CloudControllerLog(log) => {
+                let (method, path, query) = request_parts(&log.request);
+                Entry {
+                    timestamp: log.timestamp,
+                    method,
+                    path,
+                    query,
+                    status: log.status_code.as_u16(),
+                    response_time: log.response_time,
+                    client_ip: None,
+                    x_forwarded_for: log.x_forwarded_for.clone(),
+                    backend_ip: None,
+                    host: Some(log.request_host.to_string()),
+                    user_agent: log.user_agent.map(String::from),
+                    referrer: log.referrer.as_ref().map(|r| r.to_string()),
+                    bytes_sent: log.bytes,
+                    bytes_received: None,
+                }
+            }
+            LogEntry::GorouterLog(log) => {
+                let (method, path, query) = request_parts(&log.request);
+                Entry {
+                    timestamp: log.timestamp,
+                    method,
+                    path,
+                    query,
+                    status: log.status_code.as_u16(),
+                    response_time: log.response_time,
+                    client_ip: Some(log.remote_addr),
+                    x_forwarded_for: log.x_forwarded_for.clone(),
+                    backend_ip: log.backend_addr,
+                    host: Some(log.request_host.to_string()),
+                    user_agent: log.user_agent.map(String::from),
+                    referrer: log.referrer.as_ref().map(|r| r.to_string()),
+                    bytes_sent: log.bytes_sent,
+                    bytes_received: Some(log.bytes_received),
+                }
+            }
+        }
+    }
+
+    /// The client IP, or the first X-Forwarded-For address for cloud
+    /// controller logs, which only know the client through it
+    pub fn client(&self) -> Option<IpAddr> {
+        self.client_ip
+            .or_else(|| self.x_forwarded_for.first().copied())
+    }
+}
+
+// an entry with the tags of the file it was read from
+#[derive(Serialize)]
+struct Record<'a> {
+    #[serde(flatten)]
+    entry: Entry,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    tags: &'a BTreeMap<String, String>,
+}
+
+/// Writes every entry of the access log at `path` matching `filter` to `out`
+/// as JSON Lines, one `Entry` per line with `tags`, if any. Returns the number
+/// of lines that couldn't be parsed.
+pub fn write_json_lines<W: Write>(
+    path: &str,
+    log_type: LogType,
+    options: &ReadOptions,
+    filter: &Filter,
+    tags: &BTreeMap<String, String>,
+    out: &mut W,
+) -> Result<usize> {
+    read_log(path, log_type, options, |log, _| {
+        if filter.matches(&log) {
+            let record = Record {
+                entry: Entry::new(&log),
+                tags,
+            };
+            serde_json::to_writer(&mut *out, &record)?;
+            writeln!(out)?;
+        }
+        Ok(())
+    })
+}
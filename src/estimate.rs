@@ -0,0 +1,320 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `--estimate`, how large a run over the access logs would be, from parsing
+//! the start and the end of every file, before committing to a long job.
+use crate::explain::format_name;
+use crate::reader::sample_ranges;
+use crate::{DefaultHashMap, TopInfo};
+use access_log_parser::LogType;
+use anyhow::Result;
+use std::hash::Hash;
+use std::io::Write;
+use std::net::IpAddr;
+use std::time::Instant;
+
+// the bytes read from the start and from the end of every file
+const SAMPLE_BYTES: u64 = 4 * 1024 * 1024;
+
+// the hash map overhead of a counted key, besides the key itself
+const ENTRY_BYTES: u64 = 64;
+
+// the memory of a request kept for the session sections, besides its path
+const VISIT_BYTES: u64 = 96;
+
+// runs longer than this and more memory than this get a suggestion
+const LONG_SECONDS: f64 = 600.0;
+const LARGE_BYTES: u64 = 1024 * 1024 * 1024;
+
+// the width of the item names
+const NAME_WIDTH: usize = 16;
+
+// what's extrapolated from the sample of a file
+#[derive(Default)]
+struct Estimate {
+    lines: u64,
+    counted: u64,
+    seconds: f64,
+    memory: u64,
+}
+
+// the distinct keys and their bytes in a map, without the hash map overhead
+fn keys<K: Eq + Hash, V>(map: &DefaultHashMap<K, V>, len: impl Fn(&K) -> usize) -> (u64, u64) {
+    map.keys().fold((0, 0), |(count, bytes), key| {
+        (count + 1, bytes + len(key) as u64)
+    })
+}
+
+fn ip_len(ip: &IpAddr) -> usize {
+    match ip {
+        IpAddr::V4(_) => 4,
+        IpAddr::V6(_) => 16,
+    }
+}
+
+// `value` scaled by `scale` but no more than `limit`, distinct keys can't
+// grow faster than the lines they're counted from
+fn scaled(value: u64, scale: f64, limit: u64) -> u64 {
+    ((value as f64 * scale).round() as u64)
+        .min(limit)
+        .max(value)
+}
+
+fn bytes_text(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+fn seconds_text(seconds: f64) -> String {
+    let seconds = seconds.ceil() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{seconds}s"),
+        (0, _) => format!("{minutes}m {seconds}s"),
+        _ => format!("{hours}h {minutes}m {seconds}s"),
+    }
+}
+
+impl TopInfo {
+    // the memory of the counts of `self` if every map grew by `scale`, up to
+    // one key for every one of the `counted` requests
+    fn memory(&self, scale: f64, counted: u64) -> u64 {
+        let maps = [
+            keys(&self.requests_no_query, String::len),
+            keys(&self.requests_query, String::len),
+            keys(&self.client_ips, ip_len),
+            keys(&self.client_errors, ip_len),
+            keys(&self.backend_ips, ip_len),
+            keys(&self.user_agents, String::len),
+            keys(&self.x_forwarded_fors, String::len),
+            keys(&self.hosts, String::len),
+            keys(&self.error_paths, String::len),
+            keys(&self.client_error_paths, String::len),
+            keys(&self.pii_paths, String::len),
+            keys(&self.malformed_requests, String::len),
+        ];
+        let mut memory: u64 = maps
+            .iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, bytes)| scaled(*count, scale, counted) * (ENTRY_BYTES + bytes / count))
+            .sum();
+        // the session sections keep every request until the report
+        if self.sessions.is_some() {
+            let (paths, bytes) = keys(&self.requests_no_query, String::len);
+            memory += counted * (VISIT_BYTES + bytes / paths.max(1));
+        }
+        memory
+    }
+
+    // parses the start and the end of the file at `path` with the settings
+    // of `self`, writes what they say about the whole file
+    fn estimate_file<W: Write>(
+        &self,
+        path: &str,
+        log_type: LogType,
+        out: &mut W,
+    ) -> Result<Option<Estimate>> {
+        writeln!(out, "{path} ({} format)", format_name(log_type))?;
+        let Some((len, ranges)) = sample_ranges(path, SAMPLE_BYTES)? else {
+            writeln!(
+                out,
+                "  can't be sampled, only uncompressed local files can be estimated"
+            )?;
+            return Ok(None);
+        };
+
+        let mut sample = self.cleared();
+        let (mut lines, mut errors, mut sampled) = (0, 0, 0);
+        let started = Instant::now();
+        for range in ranges {
+            sampled += range.end - range.start;
+            let (chunk_lines, chunk_errors) = sample.process_chunk(path, range, log_type)?;
+            lines += chunk_lines as u64;
+            errors += chunk_errors;
+        }
+        let elapsed = started.elapsed().as_secs_f64();
+
+        let field = |out: &mut W, name: &str, value: String| -> Result<()> {
+            writeln!(out, "  {name:NAME_WIDTH$}{value}")?;
+            Ok(())
+        };
+        let scale = len as f64 / sampled.max(1) as f64;
+        let estimate = Estimate {
+            lines: (lines as f64 * scale).round() as u64,
+            counted: (sample.total_requests as f64 * scale).round() as u64,
+            seconds: elapsed * scale,
+            memory: sample.memory(scale, (sample.total_requests as f64 * scale).round() as u64),
+        };
+
+        if sampled == len {
+            field(out, "size", format!("{}, read completely", bytes_text(len)))?;
+        } else {
+            field(
+                out,
+                "size",
+                format!(
+                    "{}, sampled {} from the start and the end",
+                    bytes_text(len),
+                    bytes_text(sampled)
+                ),
+            )?;
+        }
+        field(
+            out,
+            "lines",
+            format!(
+                "~{} ({lines} sampled, {errors} didn't parse)",
+                estimate.lines
+            ),
+        )?;
+        let percent = if lines == 0 {
+            0.0
+        } else {
+            (sample.total_requests as f64 * 1000.0 / lines as f64).round() / 10.0
+        };
+        field(
+            out,
+            "counted",
+            format!("~{} ({percent}% pass the filters)", estimate.counted),
+        )?;
+        for (name, unique) in [
+            ("client IPs", &sample.unique_client_ips),
+            ("paths", &sample.unique_paths),
+            ("user agents", &sample.unique_user_agents),
+        ] {
+            let count = unique.count() as u64;
+            let most = scaled(count, scale, estimate.counted);
+            let value = if most == count {
+                count.to_string()
+            } else {
+                format!("{count} to {most}")
+            };
+            field(out, name, value)?;
+        }
+        field(
+            out,
+            "runtime",
+            format!("~{} on one thread", seconds_text(estimate.seconds)),
+        )?;
+        field(
+            out,
+            "memory",
+            format!("up to ~{}", bytes_text(estimate.memory)),
+        )?;
+        Ok(Some(estimate))
+    }
+
+    /// Writes an estimate of reading `files` with the settings of `self` to
+    /// `out`, without reading them completely. The first and last few MB of
+    /// every file are parsed and the lines, distinct client IPs, paths and
+    /// user agents, runtime and memory are extrapolated from them, assuming
+    /// the requests in between look alike. Ends with suggestions for large
+    /// runs when they would take long with `jobs` threads or a lot of memory.
+    pub fn estimate<W: Write>(
+        &self,
+        files: &[(String, LogType)],
+        jobs: usize,
+        out: &mut W,
+    ) -> Result<()> {
+        let mut total = Estimate::default();
+        let mut skipped = 0;
+        for (path, log_type) in files {
+            match self.estimate_file(path, *log_type, out)? {
+                Some(estimate) => {
+                    total.lines += estimate.lines;
+                    total.counted += estimate.counted;
+                    total.seconds += estimate.seconds;
+                    total.memory += estimate.memory;
+                }
+                None => skipped += 1,
+            }
+            writeln!(out)?;
+        }
+
+        // files are read in parallel and split into chunks, so the runtime
+        // shrinks with the threads
+        let seconds = total.seconds / jobs.max(1) as f64;
+        writeln!(out, "Total")?;
+        writeln!(out, "  {:NAME_WIDTH$}~{}", "lines", total.lines)?;
+        writeln!(out, "  {:NAME_WIDTH$}~{}", "counted", total.counted)?;
+        writeln!(
+            out,
+            "  {:NAME_WIDTH$}~{} with {jobs} thread{}",
+            "runtime",
+            seconds_text(seconds),
+            if jobs == 1 { "" } else { "s" }
+        )?;
+        writeln!(
+            out,
+            "  {:NAME_WIDTH$}up to ~{}",
+            "memory",
+            bytes_text(total.memory)
+        )?;
+        if skipped > 0 {
+            writeln!(
+                out,
+                "  {:NAME_WIDTH$}{skipped} file{} couldn't be sampled and aren't included",
+                "note",
+                if skipped == 1 { "" } else { "s" }
+            )?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Plan")?;
+        let mut plan = Vec::new();
+        if seconds > LONG_SECONDS {
+            if jobs < 2 {
+                plan.push("read the files in parallel with --jobs".to_string());
+            }
+            plan.push(
+                "count fewer requests with filters like --status, --path or --business-hours"
+                    .to_string(),
+            );
+        }
+        if total.memory > LARGE_BYTES {
+            plan.push(
+                "leave out the sections with many distinct keys, like requests-query, with --sections"
+                    .to_string(),
+            );
+            if self.sessions.is_some() {
+                plan.push(
+                    "leave out the session sections, they keep every counted request in memory"
+                        .to_string(),
+                );
+            }
+        }
+        if plan.is_empty() {
+            writeln!(out, "  run as is, the job is small enough")?;
+        }
+        for step in plan {
+            writeln!(out, "  - {step}")?;
+        }
+        writeln!(
+            out,
+            "  distinct client IPs, paths and user agents are estimated past {} values, \
+             whatever the size",
+            crate::unique::EXACT_LIMIT
+        )?;
+        Ok(())
+    }
+}
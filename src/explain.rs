@@ -0,0 +1,244 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `--explain`, how the first lines of an access log would be counted, so
+//! the format and options can be checked before a long run.
+use crate::reader::{first_lines, parse_line};
+use crate::{Entry, Filter, ReadOptions, Section};
+use access_log_parser::{AccessLogError, LogEntry, LogType};
+use anyhow::Result;
+use std::io::Write;
+
+// the width of the field names
+const NAME_WIDTH: usize = 16;
+
+// the name of `log_type` as given to --format
+pub(crate) fn format_name(log_type: LogType) -> &'static str {
+    match log_type {
+        LogType::CommonLog => "common",
+        LogType::CombinedLog => "combined",
+        LogType::GorouterLog => "gorouter",
+        LogType::CloudControllerLog => "cloud_controller",
+    }
+}
+
+fn text<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+// whether counting `entry` adds to `section`. Every format counts response
+// codes and requests per minute, the other sections need their field.
+fn feeds(section: Section, entry: &Entry, log: &LogEntry) -> bool {
+    let gorouter = match log {
+        LogEntry::GorouterLog(log) => Some(log),
+        _ => None,
+    };
+    // sessions and the other per client sections fall back to X-Forwarded-For
+    let client = entry.client().is_some();
+    match section {
+        Section::ResponseCodes
+        | Section::TimeSeries
+        | Section::ErrorRates
+        | Section::Bandwidth
+        | Section::Forecast => true,
+        Section::RequestMethods => entry.method.is_some(),
+        // malformed requests are the only ones without a method
+        Section::RequestsNoQuery | Section::RequestsQuery | Section::PathClusters => {
+            entry.method.is_some()
+        }
+        Section::MalformedRequests => entry.method.is_none(),
+        Section::ServerErrorPaths => entry.method.is_some() && (500..600).contains(&entry.status),
+        Section::ClientErrorPaths => entry.method.is_some() && (400..500).contains(&entry.status),
+        Section::Pii => entry.path.is_some(),
+        // gorouter lines without one are counted as `<none>`
+        Section::UserAgents => entry.user_agent.is_some() || gorouter.is_some(),
+        Section::Platforms => entry.user_agent.is_some(),
+        Section::Referrers => entry.referrer.is_some(),
+        Section::ClientIps | Section::ClientRequests => client,
+        Section::ErrorClientIps => entry.client_ip.is_some() && (400..600).contains(&entry.status),
+        Section::BackendIps => entry.backend_ip.is_some(),
+        Section::XForwardedFors => gorouter.is_some() || !entry.x_forwarded_for.is_empty(),
+        Section::Hosts | Section::HostClients | Section::CertCoverage => entry.host.is_some(),
+        Section::AppIds => gorouter.is_some_and(|log| log.app_id.is_some()),
+        Section::AppIndexes => gorouter.is_some_and(|log| log.app_index.is_some()),
+        Section::ResponseTimes => entry.response_time.is_some(),
+        // only requests with a body add to the paths
+        Section::UploadPaths => {
+            entry.method.is_some() && entry.bytes_received.is_some_and(|bytes| bytes > 0)
+        }
+        Section::GorouterTimes
+        | Section::RequestSizes
+        | Section::XCfRouterErrors
+        | Section::ExtraFields => gorouter.is_some(),
+        Section::Funnel
+        | Section::EntryPages
+        | Section::ExitPages
+        | Section::SessionDepths
+        | Section::SessionDurations
+        | Section::IpAudit
+        | Section::StatusTransitions
+        | Section::RetryStorms
+        | Section::SlowScans => client,
+    }
+}
+
+// the fields of a parsed line, whether it's counted and what it's counted in
+fn explain_entry<W: Write>(
+    log: &LogEntry,
+    filter: &Filter,
+    sections: &[Section],
+    out: &mut W,
+) -> Result<()> {
+    let entry = Entry::new(log);
+    let field = |out: &mut W, name: &str, value: String| -> Result<()> {
+        writeln!(out, "  {name:NAME_WIDTH$}{value}")?;
+        Ok(())
+    };
+
+    field(
+        out,
+        "timestamp",
+        format!(
+            "{} (UTC offset {})",
+            entry.timestamp.to_rfc3339(),
+            entry.timestamp.offset()
+        ),
+    )?;
+    field(out, "method", text(entry.method.as_ref()))?;
+    field(out, "path", text(entry.path.as_ref()))?;
+    field(out, "query", text(entry.query.as_ref()))?;
+    field(out, "status", entry.status.to_string())?;
+    field(
+        out,
+        "response time",
+        entry.response_time.map_or_else(
+            || "- (not logged in this format)".to_string(),
+            |seconds| format!("{seconds} seconds"),
+        ),
+    )?;
+    if let LogEntry::GorouterLog(log) = log {
+        field(
+            out,
+            "gorouter time",
+            text(
+                log.gorouter_time
+                    .map(|seconds| format!("{seconds} seconds")),
+            ),
+        )?;
+        field(out, "app id", text(log.app_id))?;
+        field(out, "app index", text(log.app_index))?;
+        field(out, "router error", text(log.x_cf_routererror))?;
+    }
+    field(out, "client IP", text(entry.client_ip))?;
+    let forwarded: Vec<String> = entry
+        .x_forwarded_for
+        .iter()
+        .map(|ip| ip.to_string())
+        .collect();
+    field(
+        out,
+        "X-Forwarded-For",
+        text((!forwarded.is_empty()).then(|| forwarded.join(", "))),
+    )?;
+    field(out, "backend", text(entry.backend_ip))?;
+    field(out, "host", text(entry.host.as_ref()))?;
+    field(out, "user agent", text(entry.user_agent.as_ref()))?;
+    field(out, "referrer", text(entry.referrer.as_ref()))?;
+    field(out, "bytes sent", entry.bytes_sent.to_string())?;
+    field(out, "bytes received", text(entry.bytes_received))?;
+
+    match filter.rejection(log) {
+        Some(option) => {
+            field(out, "filters", format!("skipped, doesn't match {option}"))?;
+            field(out, "sections", "-".to_string())?;
+        }
+        None => {
+            field(out, "filters", "counted".to_string())?;
+            let fed: Vec<&str> = sections
+                .iter()
+                .filter(|section| feeds(**section, &entry, log))
+                .map(|section| section.name())
+                .collect();
+            field(
+                out,
+                "sections",
+                text((!fed.is_empty()).then(|| fed.join(", "))),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes how the first `lines` lines of the access log at `path` are read
+/// to `out`: the fields parsed from every line, whether `filter` lets it
+/// through and which of `sections` it would be counted in. Ends with how
+/// many of the lines parsed and would be counted.
+pub fn explain<W: Write>(
+    path: &str,
+    log_type: LogType,
+    options: &ReadOptions,
+    filter: &Filter,
+    sections: &[Section],
+    lines: usize,
+    out: &mut W,
+) -> Result<()> {
+    // parse errors are shown with their line instead
+    let quiet = ReadOptions {
+        ignore_parse_errors: true,
+        ..options.clone()
+    };
+
+    writeln!(out, "{path} ({} format)", format_name(log_type))?;
+    let sample = first_lines(path, options, lines)?;
+    let (mut parsed, mut counted) = (0, 0);
+    for (i, line) in sample.iter().enumerate() {
+        writeln!(out)?;
+        writeln!(out, "line {}: {line}", i + 1)?;
+        let mut seen = false;
+        let ok = parse_line(line, log_type, &quiet, &mut |log, _| {
+            seen = true;
+            if filter.matches(&log) {
+                counted += 1;
+            }
+            explain_entry(&log, filter, sections, out)
+        })?;
+        if !ok {
+            writeln!(out, "  not parsed")?;
+            // the parser's trace, which points at where it failed
+            if let Err(AccessLogError::ParseError { msg }) =
+                access_log_parser::parse(log_type, line)
+            {
+                for trace in msg.lines().filter(|trace| !trace.trim().is_empty()) {
+                    writeln!(out, "    {trace}")?;
+                }
+            }
+        } else if !seen {
+            writeln!(out, "  skipped, not an access log line of `cf logs`")?;
+        }
+        if ok {
+            parsed += 1;
+        }
+    }
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "{parsed} of {} lines parsed, {counted} would be counted",
+        sample.len()
+    )?;
+    if parsed > 0 && counted == 0 && !filter.is_empty() {
+        writeln!(out, "None of the parsed lines match the filters")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,243 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{TimeHistogram, TimeResolution, TopInfo};
+use anyhow::{Context, Result};
+use chrono::prelude::*;
+use rusqlite::{params, Connection, ToSql, Transaction};
+use std::path::Path;
+
+// every aggregate table has the same layout, the name of the table and its key column
+const COUNT_TABLES: [(&str, &str, &str); 16] = [
+    ("response_codes", "status", "INTEGER"),
+    ("request_methods", "method", "TEXT"),
+    ("requests_no_query", "path", "TEXT"),
+    ("requests_query", "path", "TEXT"),
+    ("client_ips", "ip", "TEXT"),
+    ("referrers", "referrer", "TEXT"),
+    ("user_agents", "user_agent", "TEXT"),
+    ("backend_ips", "ip", "TEXT"),
+    ("x_forwarded_fors", "x_forwarded_for", "TEXT"),
+    ("hosts", "host", "TEXT"),
+    ("app_ids", "app_id", "TEXT"),
+    ("app_indexes", "app_index", "INTEGER"),
+    ("response_times", "seconds", "INTEGER"),
+    ("gorouter_times", "seconds", "INTEGER"),
+    ("x_cf_routererrors", "router_error", "TEXT"),
+    ("requests_per_minute", "minute", "TEXT"),
+];
+
+const RUNS_TABLE: &str = "CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    log_start TEXT NOT NULL,
+    log_end TEXT NOT NULL,
+    total_requests INTEGER NOT NULL,
+    parse_errors INTEGER NOT NULL,
+    filtered INTEGER NOT NULL
+)";
+
+const TAGS_TABLE: &str = "CREATE TABLE IF NOT EXISTS tags (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    key TEXT NOT NULL,
+    value TEXT NOT NULL
+)";
+
+fn insert_counts<'a, K, I>(
+    tx: &Transaction,
+    run_id: i64,
+    table: &str,
+    column: &str,
+    iter: I,
+) -> Result<()>
+where
+    K: ToSql,
+    I: Iterator<Item = (K, &'a usize)>,
+{
+    let mut stmt = tx.prepare(&format!(
+        "INSERT INTO {table} (run_id, {column}, count) VALUES (?1, ?2, ?3)"
+    ))?;
+    for (key, count) in iter {
+        stmt.execute(params![run_id, key, *count as i64])?;
+    }
+    Ok(())
+}
+
+// time buckets start at a whole second, or millisecond with a millisecond time
+// resolution, requests without a time are stored as NULL
+fn time_buckets(times: &TimeHistogram, resolution: TimeResolution) -> Vec<(Option<i64>, usize)> {
+    times
+        .buckets(resolution)
+        .into_iter()
+        .map(|(start, count)| (Some(start as i64), count))
+        .chain((times.untimed() > 0).then_some((None, times.untimed())))
+        .collect()
+}
+
+impl TopInfo {
+    /// Writes every counted value, not just the top entries, to the SQLite
+    /// database at `path`. Each call is recorded as a new row in the `runs`
+    /// table and the aggregate tables reference it through `run_id`, so
+    /// several runs can be collected into the same database. The tags of the
+    /// run are kept in the `tags` table.
+    pub fn export_sqlite(&self, path: &Path) -> Result<()> {
+        let mut conn = Connection::open(path)
+            .with_context(|| format!("opening database {}", path.display()))?;
+
+        let tx = conn.transaction()?;
+        tx.execute(RUNS_TABLE, [])?;
+        tx.execute(TAGS_TABLE, [])?;
+        for (table, column, kind) in COUNT_TABLES {
+            tx.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {table} (
+                        run_id INTEGER NOT NULL REFERENCES runs(id),
+                        {column} {kind},
+                        count INTEGER NOT NULL
+                    )"
+                ),
+                [],
+            )?;
+        }
+
+        tx.execute(
+            "INSERT INTO runs (created_at, log_start, log_end, total_requests, parse_errors, filtered)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                Utc::now().to_rfc3339(),
+                self.duration.start.to_rfc3339(),
+                self.duration.end.to_rfc3339(),
+                self.total_requests as i64,
+                self.errors as i64,
+                self.filtered as i64,
+            ],
+        )?;
+
+        let run_id = tx.last_insert_rowid();
+        for (key, value) in self.tags.values() {
+            tx.execute(
+                "INSERT INTO tags (run_id, key, value) VALUES (?1, ?2, ?3)",
+                params![run_id, key, value],
+            )?;
+        }
+        insert_counts(
+            &tx,
+            run_id,
+            "response_codes",
+            "status",
+            self.response_codes.iter().map(|(k, v)| (k.as_u16(), v)),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "request_methods",
+            "method",
+            self.request_methods.iter().map(|(k, v)| (k.to_string(), v)),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "requests_no_query",
+            "path",
+            self.requests_no_query.iter(),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "requests_query",
+            "path",
+            self.requests_query.iter(),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "client_ips",
+            "ip",
+            self.client_ips.iter().map(|(k, v)| (k.to_string(), v)),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "referrers",
+            "referrer",
+            self.referrers.iter().map(|(k, v)| (k.to_string(), v)),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "user_agents",
+            "user_agent",
+            self.user_agents.iter(),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "backend_ips",
+            "ip",
+            self.backend_ips.iter().map(|(k, v)| (k.to_string(), v)),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "x_forwarded_fors",
+            "x_forwarded_for",
+            self.x_forwarded_fors.iter(),
+        )?;
+        insert_counts(&tx, run_id, "hosts", "host", self.hosts.iter())?;
+        insert_counts(&tx, run_id, "app_ids", "app_id", self.app_ids.iter())?;
+        insert_counts(
+            &tx,
+            run_id,
+            "app_indexes",
+            "app_index",
+            self.app_indexes.iter(),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "response_times",
+            "seconds",
+            time_buckets(&self.response_times, self.time_resolution())
+                .iter()
+                .map(|(start, count)| (*start, count)),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "gorouter_times",
+            "seconds",
+            time_buckets(&self.gorouter_times, self.time_resolution())
+                .iter()
+                .map(|(start, count)| (*start, count)),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "x_cf_routererrors",
+            "router_error",
+            self.x_cf_routererrors.iter(),
+        )?;
+        insert_counts(
+            &tx,
+            run_id,
+            "requests_per_minute",
+            "minute",
+            self.requests_per_minute
+                .iter()
+                .map(|(k, v)| (k.to_rfc3339(), v)),
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::parallel::add_counts;
+use crate::DefaultHashMap;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Counts the values of `name:"value"` fields of gorouter lines, so fields
+/// the parser doesn't know about, like `x_b3_traceid`, can still be reported
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ExtraFields {
+    names: Vec<String>,
+    counts: Vec<DefaultHashMap<String, usize>>,
+}
+
+impl ExtraFields {
+    /// Counts the fields with these names, which are letters, digits, `_` and `-`
+    pub fn new(names: Vec<String>) -> Result<ExtraFields> {
+        let names: Vec<String> = names.iter().map(|n| n.trim().to_string()).collect();
+        for name in &names {
+            if name.is_empty()
+                || !name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                bail!("invalid extra field name '{name}'");
+            }
+        }
+        Ok(ExtraFields {
+            counts: vec![DefaultHashMap::default(); names.len()],
+            names,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The counted values of every field, in the order the fields were given
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DefaultHashMap<String, usize>)> {
+        self.names
+            .iter()
+            .map(|n| n.as_str())
+            .zip(self.counts.iter())
+    }
+
+    // the same fields, without any counts
+    pub(crate) fn cleared(&self) -> ExtraFields {
+        ExtraFields {
+            names: self.names.clone(),
+            counts: vec![DefaultHashMap::default(); self.names.len()],
+        }
+    }
+
+    // adds the values counted by `other` for the fields selected here too
+    pub(crate) fn merge(&mut self, other: &ExtraFields) {
+        for (name, counts) in self.names.iter().zip(&mut self.counts) {
+            if let Some(i) = other.names.iter().position(|n| n == name) {
+                add_counts(counts, &other.counts[i]);
+            }
+        }
+    }
+
+    /// Counts the values of the selected fields in `line`. Fields which are
+    /// missing or `-` aren't counted.
+    pub(crate) fn record(&mut self, line: &str) {
+        for (name, value) in fields(line) {
+            if value.is_empty() || value == "-" {
+                continue;
+            }
+            if let Some(i) = self.names.iter().position(|n| n == name) {
+                self.counts[i][value.to_string()] += 1;
+            }
+        }
+    }
+}
+
+// the `name:value` and `name:"value"` tokens of a line. Quoted strings, like
+// the user agent, are skipped as a whole, so text inside them is never taken
+// for a field.
+fn fields(line: &str) -> impl Iterator<Item = (&str, &str)> {
+    tokens(line).filter_map(|token| {
+        let (name, value) = token.split_once(':')?;
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return None;
+        }
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        Some((name, value))
+    })
+}
+
+// splits on spaces outside of double quotes and brackets
+fn tokens(line: &str) -> impl Iterator<Item = &str> {
+    let mut rest = line;
+    std::iter::from_fn(move || {
+        rest = rest.trim_start_matches(' ');
+        if rest.is_empty() {
+            return None;
+        }
+
+        let mut quoted = false;
+        let mut bracketed = false;
+        let mut escaped = false;
+        let mut end = rest.len();
+        for (i, c) in rest.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if quoted => escaped = true,
+                '"' => quoted = !quoted,
+                '[' if !quoted => bracketed = true,
+                ']' if !quoted => bracketed = false,
+                ' ' if !quoted && !bracketed => {
+                    end = i;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let (token, remaining) = rest.split_at(end);
+        rest = remaining;
+        Some(token)
+    })
+}
@@ -0,0 +1,190 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Counts of the values a regex captures from the paths, like the order IDs
+//! of `/orders/([0-9]+)`, to see the top entities behind the requests without
+//! exporting the entries.
+use crate::parallel::add_counts;
+use crate::report::{percent, top_rows, Cardinality};
+use crate::{DefaultHashMap, Entry, SortOrder, UniqueCount};
+use anyhow::{anyhow, bail, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// the counts of an extract, without its regex, as saved by `TopInfo::state`
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ExtractState {
+    name: String,
+    values: DefaultHashMap<String, usize>,
+    unique: UniqueCount,
+}
+
+/// Counts the values the first group of a regex captures from the paths
+/// without query params, or the whole match if it has no groups
+#[derive(Debug, Clone)]
+pub struct Extract {
+    name: String,
+    regex: Regex,
+    values: DefaultHashMap<String, usize>,
+    unique: UniqueCount,
+}
+
+impl FromStr for Extract {
+    type Err = anyhow::Error;
+
+    /// Parses `name=regex`, like `order_id=/orders/([0-9]+)`. Names are
+    /// letters, digits, `_` and `-`.
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, pattern) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid extract '{s}', expected NAME=REGEX"))?;
+        let name = name.trim();
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            bail!("invalid extract name '{name}' in '{s}'");
+        }
+        let regex = Regex::new(pattern)
+            .map_err(|e| anyhow!("invalid regex '{pattern}' in extract '{s}': {e}"))?;
+        Ok(Extract {
+            name: name.to_string(),
+            regex,
+            values: DefaultHashMap::new(),
+            unique: UniqueCount::default(),
+        })
+    }
+}
+
+impl Extract {
+    // the same regex, without any values
+    pub(crate) fn cleared(&self) -> Extract {
+        Extract {
+            name: self.name.clone(),
+            regex: self.regex.clone(),
+            values: DefaultHashMap::new(),
+            unique: UniqueCount::default(),
+        }
+    }
+
+    // adds the values counted by `other`, a cleared copy of this extract
+    pub(crate) fn merge(&mut self, other: &Extract) {
+        add_counts(&mut self.values, &other.values);
+        self.unique.merge(&other.unique);
+    }
+
+    // the values counted so far, to be saved with the other counts
+    pub(crate) fn state(&self) -> ExtractState {
+        ExtractState {
+            name: self.name.clone(),
+            values: self.values.clone(),
+            unique: self.unique.clone(),
+        }
+    }
+
+    // adds the values of the saved extract with the same name, if there is one
+    pub(crate) fn restore(&mut self, saved: &[ExtractState]) {
+        if let Some(state) = saved.iter().find(|state| state.name == self.name) {
+            add_counts(&mut self.values, &state.values);
+            self.unique.merge(&state.unique);
+        }
+    }
+
+    pub(crate) fn record(&mut self, entry: &Entry) {
+        let Some(captures) = entry
+            .path
+            .as_deref()
+            .and_then(|path| self.regex.captures(path))
+        else {
+            return;
+        };
+        let Some(value) = captures.get(1).or_else(|| captures.get(0)) else {
+            return;
+        };
+        self.values[value.as_str().to_string()] += 1;
+        self.unique.insert(value.as_str());
+    }
+
+    /// The `max` values captured most often
+    pub(crate) fn report(&self, max: usize) -> ExtractReport {
+        let requests: usize = self.values.values().sum();
+        let values = top_rows(self.values.iter(), &SortOrder::ByValue, max)
+            .into_iter()
+            .map(|row| ExtractValue {
+                percent: percent(row.count, requests),
+                value: row.key,
+                requests: row.count,
+            })
+            .collect();
+        ExtractReport {
+            name: self.name.clone(),
+            regex: self.regex.to_string(),
+            max,
+            requests,
+            distinct: (&self.unique).into(),
+            values,
+        }
+    }
+}
+
+/// The top values captured by an `--extract`
+#[derive(Debug, Serialize)]
+pub struct ExtractReport {
+    pub name: String,
+    pub regex: String,
+    #[serde(skip)]
+    pub max: usize,
+    /// The requests with a path the regex matched
+    pub requests: usize,
+    /// The number of different values captured
+    pub distinct: Cardinality,
+    pub values: Vec<ExtractValue>,
+}
+
+/// One captured value
+#[derive(Debug, Serialize)]
+pub struct ExtractValue {
+    pub value: String,
+    pub requests: usize,
+    /// Percentage of the requests the regex matched
+    pub percent: f64,
+}
+
+impl ExtractReport {
+    /// The column headers of `ExtractValue::cells`
+    pub const HEADERS: [&'static str; 3] = ["Value", "Requests", "% of Matched"];
+
+    pub fn title(&self) -> String {
+        format!(
+            "Top '{}' Values of {} ({}{} distinct in {} requests):",
+            self.max,
+            self.name,
+            if self.distinct.estimated { "~" } else { "" },
+            self.distinct.count,
+            self.requests
+        )
+    }
+}
+
+impl ExtractValue {
+    pub fn cells(&self) -> Vec<String> {
+        vec![
+            self.value.clone(),
+            self.requests.to_string(),
+            format!("{}%", self.percent),
+        ]
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use access_log_parser::{LogEntry, RequestResult};
+use chrono::{DateTime, Datelike, FixedOffset, NaiveTime, Weekday};
+use http::{Method, StatusCode};
+use std::fmt;
+use std::str::FromStr;
+
+/// Matches a single status code (`404`) or a whole class of them (`5xx`)
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatusFilter {
+    Code(StatusCode),
+    Class(u16),
+}
+
+impl StatusFilter {
+    pub fn matches(&self, status: StatusCode) -> bool {
+        match self {
+            StatusFilter::Code(code) => *code == status,
+            StatusFilter::Class(class) => status.as_u16() / 100 == *class,
+        }
+    }
+}
+
+impl fmt::Display for StatusFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatusFilter::Code(code) => write!(f, "{}", code.as_u16()),
+            StatusFilter::Class(class) => write!(f, "{class}xx"),
+        }
+    }
+}
+
+impl FromStr for StatusFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        match s.strip_suffix("xx") {
+            Some(class) => match class.parse::<u16>() {
+                Ok(class) if (1..=9).contains(&class) => Ok(StatusFilter::Class(class)),
+                _ => Err(format!("invalid status class '{s}'")),
+            },
+            None => s
+                .parse::<StatusCode>()
+                .map(StatusFilter::Code)
+                .map_err(|_| format!("invalid status code '{s}'")),
+        }
+    }
+}
+
+/// A recurring time of day, like `09:00-17:00`, including the start and
+/// excluding the end. A window which ends before it starts spans midnight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+impl FromStr for TimeWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let time = |t: &str| {
+            NaiveTime::parse_from_str(t.trim(), "%H:%M")
+                .map_err(|_| format!("invalid time '{t}' in '{s}', expected HH:MM"))
+        };
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("invalid time window '{s}', expected HH:MM-HH:MM"))?;
+        Ok(TimeWindow {
+            start: time(start)?,
+            end: time(end)?,
+        })
+    }
+}
+
+/// Restricts which log entries are counted. An empty filter matches everything.
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    pub statuses: Vec<StatusFilter>,
+    pub methods: Vec<Method>,
+    pub path_prefix: Option<String>,
+    /// Only entries at or after this time
+    pub since: Option<DateTime<FixedOffset>>,
+    /// Only entries before this time
+    pub until: Option<DateTime<FixedOffset>>,
+    /// Only entries logged during this time of day, every day
+    pub hours: Option<TimeWindow>,
+    /// No entries logged during these times of day
+    pub excluded_hours: Vec<TimeWindow>,
+    /// Only entries logged from Monday to Friday
+    pub weekdays_only: bool,
+    /// The time zone of `hours`, `excluded_hours` and `weekdays_only`. By
+    /// default the offset every entry's timestamp was logged with is used.
+    pub utc_offset: Option<FixedOffset>,
+}
+
+impl Filter {
+    pub fn is_empty(&self) -> bool {
+        self.statuses.is_empty()
+            && self.methods.is_empty()
+            && self.path_prefix.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+            && self.hours.is_none()
+            && self.excluded_hours.is_empty()
+            && !self.weekdays_only
+    }
+
+    /// What the filter lets through, one condition per item
+    pub fn describe(&self) -> Vec<String> {
+        let join = |values: Vec<String>| values.join(" or ");
+        let mut conditions = Vec::new();
+        if !self.statuses.is_empty() {
+            conditions.push(format!(
+                "status {}",
+                join(self.statuses.iter().map(|s| s.to_string()).collect())
+            ));
+        }
+        if !self.methods.is_empty() {
+            conditions.push(format!(
+                "method {}",
+                join(self.methods.iter().map(|m| m.to_string()).collect())
+            ));
+        }
+        if let Some(prefix) = &self.path_prefix {
+            conditions.push(format!("path starting with {prefix}"));
+        }
+        if let Some(since) = self.since {
+            conditions.push(format!("logged at or after {}", since.to_rfc3339()));
+        }
+        if let Some(until) = self.until {
+            conditions.push(format!("logged before {}", until.to_rfc3339()));
+        }
+        if let Some(hours) = &self.hours {
+            conditions.push(format!("logged during {hours}"));
+        }
+        for hours in &self.excluded_hours {
+            conditions.push(format!("not logged during {hours}"));
+        }
+        if self.weekdays_only {
+            conditions.push("logged from Monday to Friday".to_string());
+        }
+        if let Some(offset) = self.utc_offset {
+            conditions.push(format!("times of day at UTC offset {offset}"));
+        }
+        conditions
+    }
+
+    // whether `timestamp` is within the recurring time windows
+    fn in_windows(&self, timestamp: DateTime<FixedOffset>) -> bool {
+        let local = match self.utc_offset {
+            Some(offset) => timestamp.with_timezone(&offset),
+            None => timestamp,
+        };
+        let time = local.time();
+        if self
+            .hours
+            .as_ref()
+            .is_some_and(|hours| !hours.contains(time))
+            || self.excluded_hours.iter().any(|hours| hours.contains(time))
+        {
+            return false;
+        }
+        !(self.weekdays_only && matches!(local.weekday(), Weekday::Sat | Weekday::Sun))
+    }
+
+    pub fn matches(&self, log_entry: &LogEntry) -> bool {
+        self.rejection(log_entry).is_none()
+    }
+
+    /// The option whose filter doesn't match `log_entry`, or `None` if the
+    /// entry matches the whole filter
+    pub fn rejection(&self, log_entry: &LogEntry) -> Option<&'static str> {
+        let (timestamp, status, request) = match log_entry {
+            LogEntry::CommonLog(log) => (log.timestamp, log.status_code, &log.request),
+            LogEntry::CombinedLog(log) => (log.timestamp, log.status_code, &log.request),
+            LogEntry::GorouterLog(log) => (log.timestamp, log.status_code, &log.request),
+            LogEntry::CloudControllerLog(log) => (log.timestamp, log.status_code, &log.request),
+        };
+
+        if self.since.is_some_and(|since| timestamp < since)
+            || self.until.is_some_and(|until| timestamp >= until)
+        {
+            return Some("the time range");
+        }
+        if !self.in_windows(timestamp) {
+            return Some("--business-hours, --exclude-hours or --weekdays-only");
+        }
+
+        if !self.statuses.is_empty() && !self.statuses.iter().any(|s| s.matches(status)) {
+            return Some("--status");
+        }
+
+        if !self.methods.is_empty() {
+            match request {
+                RequestResult::Valid(req) if self.methods.contains(req.method()) => {}
+                _ => return Some("--method"),
+            }
+        }
+
+        if let Some(prefix) = &self.path_prefix {
+            let path = match request {
+                RequestResult::Valid(req) => req.uri().path(),
+                RequestResult::InvalidPath(path, _err) => path,
+                RequestResult::InvalidRequest(path) => path,
+            };
+            if !path.starts_with(prefix.as_str()) {
+                return Some("--path");
+            }
+        }
+
+        None
+    }
+}
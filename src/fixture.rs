@@ -0,0 +1,319 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `--record-fixture`, which turns the access logs of a run into regression
+//! fixtures: an anonymized slice of every access log next to the JSON report
+//! of that slice. A fixture passes while
+//! `top-logs -f <format> <args> -o json <log>` still writes its report, with
+//! the format and the read options of the run listed in `fixtures.json`.
+//!
+//! IPv4 and IPv6 addresses, including the ones in X-Forwarded-For chains, and
+//! UUIDs are replaced by made up ones, the same value by the same replacement
+//! across all of the access logs of a run, so the counts keep their shape.
+//! Emails, SSNs and card numbers are replaced by ones which still look like
+//! PII, so the `pii` section has something to find.
+//!
+//! Nothing else is. Host names, paths, query strings with any tokens or keys
+//! in them, referrers, user agents and the other fields of gorouter logs, like
+//! app names, are kept as they were logged.
+use crate::explain::format_name;
+use crate::pii::luhn;
+use crate::reader::{first_lines, request_span, ReadOptions};
+use crate::{write_report, OutputFormat, ReportOptions, TopInfo};
+use access_log_parser::LogType;
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+
+// the number of lines of every access log kept in its fixture
+const FIXTURE_LINES: usize = 1000;
+
+// the file listing the fixtures of a directory, new fixtures are added to it
+const MANIFEST: &str = "fixtures.json";
+
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    /// The anonymized slice, relative to the fixture directory
+    log: String,
+    /// The JSON report of the slice
+    report: String,
+    /// The format of the log and the ones tried on lines it doesn't parse
+    format: String,
+    /// The options the lines are read with, like `--cf-logs`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    args: Vec<String>,
+    lines: usize,
+}
+
+// the options which change how the lines of a log are read, as given on the
+// command line
+fn read_args(options: &ReadOptions) -> Vec<String> {
+    let mut args = Vec::new();
+    if options.cf_logs {
+        args.push("--cf-logs".to_string());
+    }
+    if let Some(pattern) = &options.strip_prefix {
+        // without the anchor `--strip-prefix` adds to the pattern
+        let pattern = pattern.as_str();
+        args.push("--strip-prefix".to_string());
+        args.push(
+            pattern
+                .strip_prefix("^(?:")
+                .and_then(|pattern| pattern.strip_suffix(')'))
+                .unwrap_or(pattern)
+                .to_string(),
+        );
+    }
+    if let Some(pattern) = &options.join_continuations {
+        args.push("--join-continuations".to_string());
+        args.push(pattern.to_string());
+    }
+    args
+}
+
+/// Writes fixtures into a directory, call `finish` to update its manifest
+pub struct FixtureRecorder {
+    dir: PathBuf,
+    fixtures: Vec<Fixture>,
+    anonymizer: Anonymizer,
+}
+
+impl FixtureRecorder {
+    /// Creates `dir` if it doesn't exist yet, fixtures already in it are kept
+    pub fn open(dir: &Path) -> Result<FixtureRecorder> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("creating fixture directory {}", dir.display()))?;
+        let manifest = dir.join(MANIFEST);
+        let fixtures = if manifest.is_file() {
+            let contents = fs::read_to_string(&manifest)
+                .with_context(|| format!("reading {}", manifest.display()))?;
+            serde_json::from_str(&contents)
+                .with_context(|| format!("parsing {}", manifest.display()))?
+        } else {
+            Vec::new()
+        };
+
+        Ok(FixtureRecorder {
+            dir: dir.to_path_buf(),
+            fixtures,
+            anonymizer: Anonymizer::new(),
+        })
+    }
+
+    /// Writes the first `FIXTURE_LINES` lines of the access log at `path`,
+    /// anonymized, and the JSON report of them with the default settings.
+    /// Lines are counted with `options`, like the run that recorded them.
+    pub fn record(&mut self, path: &str, log_type: LogType, options: &ReadOptions) -> Result<()> {
+        let name = Path::new(path.split(['?', '#']).next().unwrap_or(path))
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "stdin".to_string());
+        let stem = format!("{:03}-{name}", self.fixtures.len() + 1);
+        let log = format!("{stem}.log");
+        let report = format!("{stem}.json");
+
+        let lines = first_lines(path, options, FIXTURE_LINES)?;
+        let log_path = self.dir.join(&log);
+        let mut out = BufWriter::new(
+            fs::File::create(&log_path)
+                .with_context(|| format!("creating {}", log_path.display()))?,
+        );
+        for line in &lines {
+            writeln!(out, "{}", self.anonymizer.anonymize(line))?;
+        }
+        out.flush()?;
+
+        // the slice is a plain file, whatever the access log was packed in
+        let mut ti = TopInfo::new(10, options.ignore_parse_errors);
+        ti.read_options = ReadOptions {
+            tar_members: None,
+            mmap: false,
+            ..options.clone()
+        };
+        ti.process_file(&log_path.to_string_lossy(), log_type)?;
+        let report_path = self.dir.join(&report);
+        let mut out = BufWriter::new(
+            fs::File::create(&report_path)
+                .with_context(|| format!("creating {}", report_path.display()))?,
+        );
+        write_report(
+            &ti.report(&ReportOptions::default()),
+            OutputFormat::Json,
+            &mut out,
+        )?;
+        out.flush()?;
+
+        self.fixtures.push(Fixture {
+            log,
+            report,
+            format: std::iter::once(log_type)
+                .chain(options.fallback_formats.iter().copied())
+                .map(format_name)
+                .collect::<Vec<_>>()
+                .join(","),
+            args: read_args(options),
+            lines: lines.len(),
+        });
+        Ok(())
+    }
+
+    /// Writes the manifest, with the fixtures recorded before and now
+    pub fn finish(self) -> Result<()> {
+        let manifest = self.dir.join(MANIFEST);
+        let mut out = BufWriter::new(
+            fs::File::create(&manifest)
+                .with_context(|| format!("creating {}", manifest.display()))?,
+        );
+        serde_json::to_writer_pretty(&mut out, &self.fixtures)?;
+        writeln!(out)?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+// replaces the identifying parts of lines, the same value by the same
+// replacement every time
+struct Anonymizer {
+    ipv4: Regex,
+    ipv6: Regex,
+    uuid: Regex,
+    email: Regex,
+    ssn: Regex,
+    card: Regex,
+    names: Names,
+}
+
+#[derive(Default)]
+struct Names {
+    ips: HashMap<String, String>,
+    ipv6s: HashMap<Ipv6Addr, String>,
+    uuids: HashMap<String, String>,
+    emails: HashMap<String, String>,
+}
+
+impl Anonymizer {
+    fn new() -> Anonymizer {
+        let regex = |pattern| Regex::new(pattern).expect("valid pattern");
+        Anonymizer {
+            // versions like `Chrome/120.0.0.0` look the same, they're told
+            // apart by the character in front
+            ipv4: regex(r"(^|[^0-9./])(\d{1,3}\.\d{1,3}\.\d{1,3}\.\d{1,3})\b"),
+            // anything with two colons, times like `10:00:01` don't parse as
+            // an address and are kept
+            ipv6: regex(r"(^|[^0-9A-Za-z:.])([0-9A-Fa-f.]*:[0-9A-Fa-f.]*:[0-9A-Fa-f:.]*)"),
+            uuid: regex(
+                r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b",
+            ),
+            email: regex(r"[A-Za-z0-9._+-]+(@|%40)[A-Za-z0-9.-]+\.[A-Za-z]{2,}"),
+            ssn: regex(r"\b\d{3}-\d{2}-\d{4}\b"),
+            card: regex(r"\b\d(?:(?:[ +-]|%20)?\d){12,18}\b"),
+            names: Names::default(),
+        }
+    }
+
+    fn anonymize(&mut self, line: &str) -> String {
+        // card numbers and SSNs are only looked for in the request, like the
+        // `pii` section does, elsewhere they'd be sizes or times
+        let line = match request_span(line) {
+            Some(span) => format!(
+                "{}{}{}",
+                &line[..span.start],
+                self.anonymize_request(&line[span.clone()]),
+                &line[span.end..]
+            ),
+            None => line.to_string(),
+        };
+
+        let names = &mut self.names;
+        // before IPv4, which would replace the end of `::ffff:192.0.2.1`
+        let line =
+            self.ipv6
+                .replace_all(&line, |caps: &Captures| match caps[2].parse::<Ipv6Addr>() {
+                    Ok(ip) => format!("{}{}", &caps[1], names.ipv6(ip)),
+                    Err(_) => caps[0].to_string(),
+                });
+        let line =
+            self.ipv4
+                .replace_all(&line, |caps: &Captures| match caps[2].parse::<Ipv4Addr>() {
+                    Ok(_) => format!("{}{}", &caps[1], names.ip(&caps[2])),
+                    Err(_) => caps[0].to_string(),
+                });
+        let line = self
+            .uuid
+            .replace_all(&line, |caps: &Captures| names.uuid(&caps[0]));
+        let line = self.email.replace_all(&line, |caps: &Captures| {
+            format!("{}{}example.com", names.email(&caps[0]), &caps[1])
+        });
+        line.into_owned()
+    }
+
+    fn anonymize_request(&self, request: &str) -> String {
+        let request = self.ssn.replace_all(request, "123-45-6789");
+        // all zeros pass the Luhn check, so it's still a card number
+        let request = self.card.replace_all(&request, |caps: &Captures| {
+            let digits: Vec<u32> = caps[0].chars().filter_map(|c| c.to_digit(10)).collect();
+            if luhn(&digits) {
+                caps[0].replace(|c: char| c.is_ascii_digit(), "0")
+            } else {
+                caps[0].to_string()
+            }
+        });
+        request.into_owned()
+    }
+}
+
+impl Names {
+    // addresses in 10.0.0.0/8, numbered in the order they're first seen
+    fn ip(&mut self, ip: &str) -> String {
+        let next = self.ips.len() as u32 + 1;
+        self.ips
+            .entry(ip.to_string())
+            .or_insert_with(|| {
+                let [_, b, c, d] = next.to_be_bytes();
+                Ipv4Addr::new(10, b, c, d).to_string()
+            })
+            .clone()
+    }
+
+    // addresses in fd00::/8, numbered in the order they're first seen
+    fn ipv6(&mut self, ip: Ipv6Addr) -> String {
+        let next = self.ipv6s.len() as u128 + 1;
+        self.ipv6s
+            .entry(ip)
+            .or_insert_with(|| Ipv6Addr::from(0xfd00_u128 << 112 | next).to_string())
+            .clone()
+    }
+
+    fn uuid(&mut self, uuid: &str) -> String {
+        let next = self.uuids.len() + 1;
+        self.uuids
+            .entry(uuid.to_lowercase())
+            .or_insert_with(|| format!("00000000-0000-4000-8000-{next:012x}"))
+            .clone()
+    }
+
+    // the user part of an email, the domain is always example.com
+    fn email(&mut self, email: &str) -> String {
+        let next = self.emails.len() + 1;
+        self.emails
+            .entry(email.replace("%40", "@").to_lowercase())
+            .or_insert_with(|| format!("user{next}"))
+            .clone()
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::DefaultHashMap;
+use chrono::{DateTime, Duration, FixedOffset, Timelike};
+use std::collections::BTreeMap;
+
+// logs spanning at least two days are forecast by hour with a daily season,
+// shorter logs by minute without one
+const SEASON_HOURS: usize = 24;
+const FORECAST_MINUTES: usize = 60;
+// fewer intervals don't give a meaningful fit
+const MIN_INTERVALS: usize = 10;
+// the smoothing factors tried for level, trend and season
+const FACTORS: [f64; 5] = [0.1, 0.3, 0.5, 0.7, 0.9];
+// the z-score of a 95% confidence band
+const Z_95: f64 = 1.96;
+
+/// The expected requests of an interval after the end of the logs, with a
+/// 95% confidence band
+#[derive(Debug, Clone)]
+pub struct Forecast {
+    pub start: DateTime<FixedOffset>,
+    pub requests: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+// the state after smoothing a series, and the squared error of the one step
+// ahead predictions made along the way
+struct Fit {
+    level: f64,
+    trend: f64,
+    seasonal: Vec<f64>,
+    sse: f64,
+    predictions: usize,
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+// additive Holt-Winters smoothing, or Holt's linear trend without a `season`
+fn fit(series: &[f64], season: usize, alpha: f64, beta: f64, gamma: f64) -> Fit {
+    let (mut level, mut trend, mut seasonal, start) = if season > 0 {
+        let first = mean(&series[..season]);
+        let second = mean(&series[season..2 * season]);
+        let seasonal = series[..season].iter().map(|y| y - first).collect();
+        (first, (second - first) / season as f64, seasonal, season)
+    } else {
+        (series[0], series[1] - series[0], Vec::new(), 1)
+    };
+
+    let mut sse = 0.0;
+    for (t, y) in series.iter().enumerate().skip(start) {
+        let s = if season > 0 {
+            seasonal[t % season]
+        } else {
+            0.0
+        };
+        sse += (y - (level + trend + s)).powi(2);
+
+        let last_level = level;
+        level = alpha * (y - s) + (1.0 - alpha) * (level + trend);
+        trend = beta * (level - last_level) + (1.0 - beta) * trend;
+        if season > 0 {
+            seasonal[t % season] = gamma * (y - level) + (1.0 - gamma) * s;
+        }
+    }
+
+    Fit {
+        level,
+        trend,
+        seasonal,
+        sse,
+        predictions: series.len() - start,
+    }
+}
+
+// the requests of every interval from the first to the last one, including
+// those without requests
+fn series(
+    per_minute: &DefaultHashMap<DateTime<FixedOffset>, usize>,
+    interval: Duration,
+) -> Vec<(DateTime<FixedOffset>, f64)> {
+    let mut counts: BTreeMap<DateTime<FixedOffset>, usize> = BTreeMap::new();
+    for (minute, count) in per_minute.iter() {
+        let start = if interval == Duration::hours(1) {
+            minute.with_minute(0).unwrap_or(*minute)
+        } else {
+            *minute
+        };
+        *counts.entry(start).or_insert(0) += count;
+    }
+
+    let (Some(first), Some(last)) = (counts.keys().next(), counts.keys().next_back()) else {
+        return Vec::new();
+    };
+    let mut series = Vec::new();
+    let mut start = *first;
+    while start <= *last {
+        series.push((start, counts.get(&start).copied().unwrap_or(0) as f64));
+        start += interval;
+    }
+    series
+}
+
+/// Forecasts the requests per hour of the day after the logs, when they span
+/// at least two days, or else the requests per minute of the hour after them.
+/// The first and last interval are left out of the fit, as the logs usually
+/// start and end part way through them, and the forecast starts after the last one. Returns nothing for short logs.
+pub fn forecast(per_minute: &DefaultHashMap<DateTime<FixedOffset>, usize>) -> Vec<Forecast> {
+    let hourly = series(per_minute, Duration::hours(1));
+    let (mut series, interval, season, horizon) = if hourly.len() >= 2 * SEASON_HOURS + 2 {
+        (hourly, Duration::hours(1), SEASON_HOURS, SEASON_HOURS)
+    } else {
+        (
+            series(per_minute, Duration::minutes(1)),
+            Duration::minutes(1),
+            0,
+            FORECAST_MINUTES,
+        )
+    };
+    if series.len() < MIN_INTERVALS + 2 {
+        return Vec::new();
+    }
+    let last = series.pop().map(|(start, _)| start).unwrap_or_default();
+    series.remove(0);
+    let values: Vec<f64> = series.iter().map(|(_, count)| *count).collect();
+
+    // the smoothing factors that predicted the logs best
+    let gammas: &[f64] = if season > 0 { &FACTORS } else { &[0.0] };
+    let mut best: Option<Fit> = None;
+    for alpha in FACTORS {
+        for beta in FACTORS {
+            for gamma in gammas {
+                let fit = fit(&values, season, alpha, beta, *gamma);
+                if best.as_ref().is_none_or(|best| fit.sse < best.sse) {
+                    best = Some(fit);
+                }
+            }
+        }
+    }
+    let Some(fit) = best else {
+        return Vec::new();
+    };
+
+    // the band widens with the distance from the end of the fit, which is
+    // one interval before the end of the logs
+    let sigma = (fit.sse / fit.predictions.max(1) as f64).sqrt();
+    (1..=horizon)
+        .map(|i| {
+            let h = i + 1;
+            let s = if season > 0 {
+                fit.seasonal[(values.len() + h - 1) % season]
+            } else {
+                0.0
+            };
+            let requests = (fit.level + h as f64 * fit.trend + s).max(0.0);
+            let band = Z_95 * sigma * (h as f64).sqrt();
+            Forecast {
+                start: last + interval * i as i32,
+                requests: requests.round(),
+                low: (requests - band).max(0.0).round(),
+                high: (requests + band).round(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,145 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Response and gorouter times recorded in an HDR histogram, which keeps
+//! them to three significant digits in a fixed amount of memory, so they can
+//! be bucketed by any resolution when the report is rendered.
+use crate::TimeResolution;
+use hdrhistogram::Histogram;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const SIGNIFICANT_DIGITS: u8 = 3;
+
+// grows the histogram to fit `micros`, saturating only when it can't grow further
+fn record_n(histogram: &mut Histogram<u64>, micros: u64, count: u64) {
+    if histogram.record_n(micros, count).is_err() {
+        histogram.saturating_record_n(micros, count);
+    }
+}
+
+/// The times of the requests, in microseconds, with the requests that don't
+/// have a time counted on their own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "Recorded", into = "Recorded")]
+pub struct TimeHistogram {
+    micros: Histogram<u64>,
+    untimed: usize,
+}
+
+// the histogram as its recorded values and their counts, `Histogram` isn't serializable
+#[derive(Serialize, Deserialize)]
+struct Recorded {
+    micros: Vec<(u64, u64)>,
+    untimed: usize,
+}
+
+impl From<TimeHistogram> for Recorded {
+    fn from(histogram: TimeHistogram) -> Self {
+        Recorded {
+            micros: histogram
+                .micros
+                .iter_recorded()
+                .map(|value| (value.value_iterated_to(), value.count_at_value()))
+                .collect(),
+            untimed: histogram.untimed,
+        }
+    }
+}
+
+impl From<Recorded> for TimeHistogram {
+    fn from(recorded: Recorded) -> Self {
+        let mut histogram = TimeHistogram {
+            untimed: recorded.untimed,
+            ..TimeHistogram::default()
+        };
+        for (micros, count) in recorded.micros {
+            record_n(&mut histogram.micros, micros, count);
+        }
+        histogram
+    }
+}
+
+impl Default for TimeHistogram {
+    fn default() -> Self {
+        TimeHistogram {
+            // resizes to fit any time
+            micros: Histogram::new(SIGNIFICANT_DIGITS).expect("valid significant digits"),
+            untimed: 0,
+        }
+    }
+}
+
+impl TimeHistogram {
+    /// Records a request taking `seconds`, or one without a time
+    pub fn record(&mut self, seconds: Option<f64>) {
+        match seconds {
+            Some(seconds) => record_n(
+                &mut self.micros,
+                (seconds.max(0.0) * 1_000_000.0).round() as u64,
+                1,
+            ),
+            None => self.untimed += 1,
+        }
+    }
+
+    /// Adds the requests recorded by `other`
+    pub fn merge(&mut self, other: &TimeHistogram) {
+        for value in other.micros.iter_recorded() {
+            record_n(
+                &mut self.micros,
+                value.value_iterated_to(),
+                value.count_at_value(),
+            );
+        }
+        self.untimed += other.untimed;
+    }
+
+    /// Every request, with or without a time
+    pub fn count(&self) -> usize {
+        self.micros.len() as usize + self.untimed
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count() == 0
+    }
+
+    /// Requests without a time
+    pub fn untimed(&self) -> usize {
+        self.untimed
+    }
+
+    /// The time in seconds `percentile` percent of the requests with a time
+    /// took at most, rounded to milliseconds like the percentiles of
+    /// `Latencies`. None without any.
+    pub fn percentile(&self, percentile: f64) -> Option<f64> {
+        (!self.micros.is_empty()).then(|| {
+            let micros = self
+                .micros
+                .median_equivalent(self.micros.value_at_percentile(percentile));
+            (micros as f64 / 1000.0).round() / 1000.0
+        })
+    }
+
+    /// The requests with a time by the start of their bucket at `resolution`
+    pub fn buckets(&self, resolution: TimeResolution) -> BTreeMap<usize, usize> {
+        let mut buckets = BTreeMap::new();
+        for value in self.micros.iter_recorded() {
+            let micros = self.micros.median_equivalent(value.value_iterated_to());
+            let bucket = resolution.bucket(micros as f64 / 1_000_000.0);
+            *buckets.entry(bucket).or_insert(0) += value.count_at_value() as usize;
+        }
+        buckets
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::diagnostics;
+use crate::TopInfo;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+/// When to print an interim summary while access logs are read
+#[derive(Debug, Clone, Default)]
+pub struct Interim {
+    /// After every this many log entries
+    pub lines: Option<usize>,
+    /// After this much time since the last summary
+    pub every: Option<Duration>,
+}
+
+// the time is only looked at every this many entries
+const CLOCK_LINES: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub(crate) struct InterimState {
+    interim: Interim,
+    started: Instant,
+    last_at: Instant,
+    last_lines: usize,
+}
+
+impl TopInfo {
+    /// Print a summary of the counts so far to STDERR as set by `interim`,
+    /// so long runs show progress. When files are read on several threads
+    /// the summary is printed as files or chunks are merged.
+    pub fn set_interim(&mut self, interim: Interim) {
+        let now = Instant::now();
+        self.interim =
+            (interim.lines.is_some() || interim.every.is_some()).then_some(InterimState {
+                interim,
+                started: now,
+                last_at: now,
+                last_lines: 0,
+            });
+    }
+
+    // prints the interim summary when it's due. The time is only looked at
+    // every `CLOCK_LINES` entries, unless `clock` is set.
+    pub(crate) fn check_interim(&mut self, clock: bool) {
+        let lines = self.total_requests + self.filtered;
+        let Some(state) = &mut self.interim else {
+            return;
+        };
+        let by_lines = state
+            .interim
+            .lines
+            .is_some_and(|every| lines - state.last_lines >= every);
+        let by_time = state.interim.every.is_some_and(|every| {
+            (clock || lines.is_multiple_of(CLOCK_LINES)) && state.last_at.elapsed() >= every
+        });
+        if !(by_lines || by_time) {
+            return;
+        }
+        state.last_at = Instant::now();
+        state.last_lines = lines;
+        let elapsed = state.started.elapsed().as_secs();
+
+        let mut summary = format!("Interim summary after {lines} entries ({elapsed}s):\n");
+        let _ = writeln!(summary, "  Total Requests    : {}", self.total_requests);
+        let _ = writeln!(summary, "  Total Filtered    : {}", self.filtered);
+        for (label, value) in self.headline().items() {
+            let _ = writeln!(summary, "  {label:17} : {value}");
+        }
+        diagnostics::emit(diagnostics::Kind::Progress, &summary);
+    }
+}
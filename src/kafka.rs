@@ -0,0 +1,92 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::diagnostics;
+use crate::reader::{parse_line, ReadOptions};
+use access_log_parser::{LogEntry, LogType};
+use anyhow::{Context, Result};
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::message::Message;
+use std::time::Duration;
+
+// the most messages read in one go, so a busy topic doesn't hold up the report
+const MAX_MESSAGES_PER_READ: usize = 100_000;
+
+/// Consumes access log lines from a Kafka topic as they're published, the
+/// counterpart of `LogFollower` for logs shipped through Kafka
+pub struct KafkaFollower {
+    consumer: BaseConsumer,
+    topic: String,
+}
+
+impl KafkaFollower {
+    /// Joins the consumer `group` on `topic`. A group that hasn't consumed
+    /// the topic before starts with the messages published from now on.
+    pub fn connect(brokers: &str, topic: &str, group: &str) -> Result<KafkaFollower> {
+        let consumer: BaseConsumer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("group.id", group)
+            .set("enable.auto.commit", "true")
+            .set("auto.offset.reset", "latest")
+            .create()
+            .with_context(|| format!("connecting to Kafka at {brokers}"))?;
+        consumer
+            .subscribe(&[topic])
+            .with_context(|| format!("subscribing to {topic}"))?;
+        Ok(KafkaFollower {
+            consumer,
+            topic: topic.to_string(),
+        })
+    }
+
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Calls `f` with every entry, and its line, parsed from the messages
+    /// published since the last call and returns the number of lines that
+    /// couldn't be parsed. A message may hold several lines.
+    pub fn read_new<F>(
+        &mut self,
+        log_type: LogType,
+        options: &ReadOptions,
+        mut f: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(LogEntry, &str) -> Result<()>,
+    {
+        let mut errors = 0;
+        for _ in 0..MAX_MESSAGES_PER_READ {
+            let message = match self.consumer.poll(Duration::ZERO) {
+                Some(Ok(message)) => message,
+                // the client reconnects by itself, like when a broker restarts
+                Some(Err(err)) => {
+                    diagnostics::emit(
+                        diagnostics::Kind::ReadError,
+                        &format!("consuming {}: {err}", self.topic),
+                    );
+                    break;
+                }
+                None => break,
+            };
+            let payload = String::from_utf8_lossy(message.payload().unwrap_or_default());
+            for line in payload.lines().filter(|line| !line.trim().is_empty()) {
+                if !parse_line(line, log_type, options, &mut f)? {
+                    errors += 1;
+                }
+            }
+        }
+        Ok(errors)
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Records durations with millisecond precision, so percentiles can be
+/// calculated without keeping every value around. Used for the percentiles
+/// by minute, path and so on, where most keys see a handful of distinct
+/// times and the buckets of a `TimeHistogram` would take far more memory.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Latencies {
+    millis: BTreeMap<u64, usize>,
+    count: usize,
+}
+
+impl Latencies {
+    pub fn record(&mut self, seconds: f64) {
+        let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+        *self.millis.entry(millis).or_insert(0) += 1;
+        self.count += 1;
+    }
+
+    /// Adds the durations recorded by `other`
+    pub fn merge(&mut self, other: &Latencies) {
+        for (millis, count) in &other.millis {
+            *self.millis.entry(*millis).or_insert(0) += count;
+        }
+        self.count += other.count;
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The nearest-rank percentile in seconds, `percentile` goes from 0 to 100
+    pub fn percentile(&self, percentile: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((percentile / 100.0) * self.count as f64).ceil().max(1.0) as usize;
+        let mut seen = 0;
+        for (millis, count) in &self.millis {
+            seen += count;
+            if seen >= rank {
+                return Some(*millis as f64 / 1000.0);
+            }
+        }
+        self.millis
+            .keys()
+            .last()
+            .map(|millis| *millis as f64 / 1000.0)
+    }
+}
+
+/// The count, mean, spread and range of durations, exact rather than
+/// rounded to milliseconds like `Latencies`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Stats {
+    count: usize,
+    mean: f64,
+    // the sum of squared differences from the mean, updated as in Welford's algorithm
+    squares: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Stats {
+    pub fn record(&mut self, seconds: f64) {
+        if self.count == 0 || seconds < self.min {
+            self.min = seconds;
+        }
+        if self.count == 0 || seconds > self.max {
+            self.max = seconds;
+        }
+        self.count += 1;
+        let delta = seconds - self.mean;
+        self.mean += delta / self.count as f64;
+        self.squares += delta * (seconds - self.mean);
+    }
+
+    /// Adds the durations recorded by `other`
+    pub fn merge(&mut self, other: &Stats) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = other.clone();
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.squares +=
+            other.squares + delta * delta * (self.count as f64 * other.count as f64) / count as f64;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.count = count;
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// In seconds, none of these are known without durations
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// The population standard deviation
+    pub fn stddev(&self) -> Option<f64> {
+        (self.count > 0).then(|| (self.squares / self.count as f64).sqrt())
+    }
+}
+
+/// The width of the response and gorouter time buckets, a number of seconds
+/// or milliseconds, or buckets that double in width
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeResolution {
+    Seconds(u32),
+    Milliseconds(u32),
+    /// Buckets in milliseconds that double in width, 1 to 2, 2 to 4, 4 to 8
+    /// and so on, after a bucket for anything under a millisecond
+    Log2,
+}
+
+impl Default for TimeResolution {
+    fn default() -> Self {
+        TimeResolution::Seconds(1)
+    }
+}
+
+impl TimeResolution {
+    /// A fixed bucket width, `s` or `ms` for one second or millisecond, or a
+    /// number of them like `2s` or `50ms`
+    pub fn width(s: &str) -> Result<TimeResolution, String> {
+        let value = s.trim().to_lowercase();
+        let (number, unit): (&str, fn(u32) -> TimeResolution) =
+            match value.strip_suffix("ms") {
+                Some(number) => (number, TimeResolution::Milliseconds),
+                None => match value.strip_suffix('s') {
+                    Some(number) => (number, TimeResolution::Seconds),
+                    None => {
+                        return Err(format!(
+                            "invalid bucket width '{s}', expected a number of seconds or milliseconds like '2s' or '50ms'"
+                        ))
+                    }
+                },
+            };
+        match number.trim() {
+            "" => Ok(unit(1)),
+            number => match number.parse::<u32>() {
+                Ok(width) if width > 0 => Ok(unit(width)),
+                _ => Err(format!(
+                    "invalid bucket width '{s}', expected a number of seconds or milliseconds like '2s' or '50ms'"
+                )),
+            },
+        }
+    }
+
+    /// The start of the bucket of `seconds`, in the unit of the resolution
+    pub fn bucket(&self, seconds: f64) -> usize {
+        match self {
+            TimeResolution::Seconds(width) => {
+                let width = *width as usize;
+                seconds.floor() as usize / width * width
+            }
+            // rounded to microseconds first, so 0.29 isn't 289.99999... milliseconds
+            TimeResolution::Milliseconds(width) => {
+                let micros = (seconds.max(0.0) * 1_000_000.0).round() as usize;
+                let width = *width as usize;
+                micros / (1000 * width) * width
+            }
+            TimeResolution::Log2 => {
+                let millis = (seconds.max(0.0) * 1_000_000.0).round() as usize / 1000;
+                match millis {
+                    0 => 0,
+                    millis => 1 << millis.ilog2(),
+                }
+            }
+        }
+    }
+
+    /// The end of the bucket starting at `start`, which isn't part of it
+    pub fn end(&self, start: usize) -> usize {
+        match self {
+            TimeResolution::Seconds(width) | TimeResolution::Milliseconds(width) => {
+                start + *width as usize
+            }
+            TimeResolution::Log2 => (start * 2).max(1),
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            TimeResolution::Seconds(_) => "s",
+            TimeResolution::Milliseconds(_) | TimeResolution::Log2 => "ms",
+        }
+    }
+}
+
+impl fmt::Display for TimeResolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TimeResolution::Seconds(1) => write!(f, "s"),
+            TimeResolution::Seconds(width) => write!(f, "{width}s"),
+            TimeResolution::Milliseconds(1) => write!(f, "ms"),
+            TimeResolution::Milliseconds(width) => write!(f, "{width}ms"),
+            TimeResolution::Log2 => write!(f, "log2"),
+        }
+    }
+}
+
+impl FromStr for TimeResolution {
+    type Err = String;
+
+    /// `log2` for buckets that double in width, otherwise a bucket width,
+    /// see `TimeResolution::width`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "log2" => Ok(TimeResolution::Log2),
+            _ => TimeResolution::width(s).map_err(|_| {
+                format!("invalid time resolution '{s}', expected 's', 'ms', a number of seconds or milliseconds like '2s' or '10ms', or 'log2'")
+            }),
+        }
+    }
+}
@@ -0,0 +1,296 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::report::ReportRow;
+use crate::section::Section;
+use crate::TimeResolution;
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A column of a section in the table, Markdown and HTML output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Key,
+    Count,
+    ServerErrors,
+    ClientErrors,
+    Megabytes,
+    P50,
+    P95,
+    Percent,
+    Low,
+    High,
+}
+
+impl Field {
+    /// Every column, in the default order
+    pub const ALL: [Field; 10] = [
+        Field::Key,
+        Field::Count,
+        Field::ServerErrors,
+        Field::ClientErrors,
+        Field::Megabytes,
+        Field::P50,
+        Field::P95,
+        Field::Percent,
+        Field::Low,
+        Field::High,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::Key => "key",
+            Field::Count => "count",
+            Field::ServerErrors => "server_errors",
+            Field::ClientErrors => "client_errors",
+            Field::Megabytes => "megabytes",
+            Field::P50 => "p50",
+            Field::P95 => "p95",
+            Field::Percent => "percent",
+            Field::Low => "low",
+            Field::High => "high",
+        }
+    }
+
+    // the header of the column unless the layout sets one, time buckets are in
+    // the unit of `resolution`
+    fn label(&self, section: Section, resolution: TimeResolution) -> String {
+        match self {
+            Field::Key => match section {
+                Section::ResponseTimes | Section::GorouterTimes => {
+                    return format!("{} ({})", section.key_label(), resolution.unit());
+                }
+                _ => section.key_label(),
+            },
+            Field::Count => match section {
+                Section::ErrorRates | Section::Bandwidth => "Requests",
+                Section::UploadPaths => "Bytes Received",
+                _ => "Count",
+            },
+            Field::ServerErrors => "5xx",
+            Field::ClientErrors => "4xx",
+            Field::Megabytes => "MB Sent",
+            Field::P50 => "p50 (s)",
+            Field::P95 => "p95 (s)",
+            Field::Percent => match section {
+                Section::ErrorClientIps => "% of Requests",
+                Section::ErrorRates => "% 5xx",
+                _ => "% of Previous",
+            },
+            Field::Low => "Low (95%)",
+            Field::High => "High (95%)",
+        }
+        .to_string()
+    }
+
+    // the value of the numeric columns beyond key and count
+    fn value(&self, row: &ReportRow) -> Option<f64> {
+        match self {
+            Field::Key | Field::Count => None,
+            Field::ServerErrors => row.server_errors.map(|count| count as f64),
+            Field::ClientErrors => row.client_errors.map(|count| count as f64),
+            Field::Megabytes => row.megabytes,
+            Field::P50 => row.p50,
+            Field::P95 => row.p95,
+            Field::Percent => row.percent,
+            Field::Low => row.low,
+            Field::High => row.high,
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        Field::ALL
+            .iter()
+            .find(|field| field.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = Field::ALL.iter().map(|f| f.name()).collect();
+                format!(
+                    "invalid column '{s}', available columns: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// How a section is rendered in the table, Markdown and HTML output, set
+/// per section name in the `sections` table of the configuration file
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SectionLayout {
+    /// Replaces the title of the section
+    pub title: Option<String>,
+    /// Names of the columns to show, in order. Columns without values in a
+    /// section, like `p50` outside the time series, are left out.
+    pub columns: Option<Vec<String>>,
+    /// Column headers by column name
+    pub labels: HashMap<String, String>,
+    /// Decimal places of numeric columns by column name
+    pub decimals: HashMap<String, usize>,
+}
+
+impl SectionLayout {
+    /// Fails on column names which don't exist
+    pub fn validate(&self) -> Result<()> {
+        self.columns
+            .iter()
+            .flatten()
+            .chain(self.labels.keys())
+            .chain(self.decimals.keys())
+            .try_for_each(|name| name.parse::<Field>().map(|_| ()).map_err(|e| anyhow!(e)))
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        match &self.columns {
+            Some(columns) => columns.iter().filter_map(|c| c.parse().ok()).collect(),
+            None => Field::ALL.to_vec(),
+        }
+    }
+}
+
+/// How the percentiles, percentages and forecast bands are written, for
+/// spreadsheets which expect the decimal separator of their locale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    /// Decimal places of the numeric columns, a section layout can override
+    /// it. By default values are written with as many places as they need.
+    pub decimals: Option<usize>,
+    /// Only used in the table, Markdown and HTML output, JSON and YAML
+    /// numbers always have a `.`
+    pub separator: char,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            decimals: None,
+            separator: '.',
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Rounds the numeric columns of `row` to the decimal places of this
+    /// format. Columns `layout` sets decimal places for are left alone, those
+    /// only round the table, Markdown and HTML output.
+    pub(crate) fn round(&self, row: &mut ReportRow, layout: Option<&SectionLayout>) {
+        for (field, value) in [
+            (Field::Megabytes, &mut row.megabytes),
+            (Field::P50, &mut row.p50),
+            (Field::P95, &mut row.p95),
+            (Field::Percent, &mut row.percent),
+            (Field::Low, &mut row.low),
+            (Field::High, &mut row.high),
+        ] {
+            if layout.is_some_and(|layout| setting(&layout.decimals, field).is_some()) {
+                continue;
+            }
+            if let (Some(decimals), Some(v)) = (self.decimals, value.as_mut()) {
+                let scale = 10f64.powi(decimals as i32);
+                *v = (*v * scale).round() / scale;
+            }
+        }
+    }
+}
+
+/// A column of a section as it's rendered
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub field: Field,
+    pub title: String,
+    decimals: Option<usize>,
+    separator: char,
+}
+
+impl Column {
+    /// The text of the column for `row`, empty when the row has no value
+    pub fn text(&self, row: &ReportRow) -> String {
+        match self.field {
+            Field::Key => row.key.clone(),
+            Field::Count => row.count.to_string(),
+            Field::ServerErrors | Field::ClientErrors => self
+                .field
+                .value(row)
+                .map_or_else(String::new, |count| count.to_string()),
+            field => {
+                let text = match (field.value(row), self.decimals) {
+                    (Some(value), Some(decimals)) => format!("{value:.decimals$}"),
+                    (Some(value), None) => value.to_string(),
+                    (None, _) => return String::new(),
+                };
+                match self.separator {
+                    '.' => text,
+                    separator => text.replace('.', &separator.to_string()),
+                }
+            }
+        }
+    }
+
+    /// Numbers are right aligned
+    pub fn is_numeric(&self) -> bool {
+        self.field != Field::Key
+    }
+}
+
+// the value set for `field`, column names are case insensitive
+fn setting<T>(values: &HashMap<String, T>, field: Field) -> Option<&T> {
+    values
+        .iter()
+        .find(|(name, _)| name.parse() == Ok(field))
+        .map(|(_, value)| value)
+}
+
+// the columns of a section with `rows`, key and count are always kept
+// unless the layout leaves them out
+pub(crate) fn columns(
+    section: Section,
+    layout: Option<&SectionLayout>,
+    numbers: &NumberFormat,
+    resolution: TimeResolution,
+    rows: &[ReportRow],
+) -> Vec<Column> {
+    let default = SectionLayout::default();
+    let layout = layout.unwrap_or(&default);
+    layout
+        .fields()
+        .into_iter()
+        .filter(|field| {
+            matches!(field, Field::Key | Field::Count)
+                || rows.iter().any(|row| field.value(row).is_some())
+        })
+        .map(|field| Column {
+            field,
+            title: setting(&layout.labels, field)
+                .cloned()
+                .unwrap_or_else(|| field.label(section, resolution)),
+            decimals: setting(&layout.decimals, field)
+                .copied()
+                .or(numbers.decimals),
+            separator: numbers.separator,
+        })
+        .collect()
+}
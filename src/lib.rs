@@ -13,37 +13,225 @@
 // limitations under the License.
 use anyhow::Result;
 use chrono::prelude::*;
-use defaultmap::DefaultHashMap;
 use http::{Method, StatusCode};
-use prettytable::{cell, Row, Table};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::fs;
+use std::collections::HashMap;
 use std::io;
-use std::io::prelude::*;
 use std::net::IpAddr;
-
+use std::str::FromStr;
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::Arc;
+
+mod audit;
+mod breakdown;
+mod checkpoint;
+mod cloud;
+mod cluster;
+mod compression;
+mod concentration;
+mod config;
+mod default_map;
+pub mod diagnostics;
+mod drill;
+mod entry;
+mod estimate;
+mod explain;
+mod export;
+mod extra;
+mod extract;
+mod filter;
+mod fixture;
+mod forecast;
+mod histogram;
+mod interim;
+#[cfg(feature = "kafka")]
+mod kafka;
+mod latency;
+mod layout;
+mod manifest;
+mod outlier;
+mod output;
+mod parallel;
+mod pii;
+mod platform;
+mod profile;
+pub mod progress;
+mod reader;
+mod report;
+mod section;
+mod segment;
+mod selftest;
+mod series;
+mod session;
+mod slices;
+mod state;
+mod syslog;
+mod tags;
+mod timeline;
+mod top;
+mod trend;
+mod unique;
+
+pub use audit::{HostList, IpAudit, IpList};
+pub use breakdown::{Breakdown, BreakdownKey, BreakdownReport, BreakdownRow};
+pub use compression::OutputFile;
+pub use concentration::{Concentration, Share};
+pub use config::{Config, Preset};
+pub use default_map::DefaultHashMap;
+pub use drill::{Drill, DrillReport, DrillTable};
+pub use entry::{write_json_lines, Entry};
+pub use explain::explain;
+pub use extra::ExtraFields;
+pub use extract::{Extract, ExtractReport, ExtractValue};
+pub use filter::{Filter, StatusFilter, TimeWindow};
+pub use fixture::FixtureRecorder;
+pub use histogram::TimeHistogram;
+pub use interim::Interim;
+#[cfg(feature = "kafka")]
+pub use kafka::KafkaFollower;
+pub use latency::{Latencies, Stats, TimeResolution};
+pub use layout::{Column, Field, NumberFormat, SectionLayout};
+pub use manifest::{Manifest, ManifestFile, ManifestInput};
+pub use outlier::{Outlier, OutlierReport, Outliers, SlowestReport};
+pub use output::{write_report, write_template, OutputFormat};
+pub use pii::Pii;
+pub use platform::{platform, Device};
+pub use profile::{Deviation, KeyDeviation, Profile};
+pub use reader::{
+    detect_format, expand_paths, read_log, FormatMap, LogFollower, ReadOptions, StdinFollower,
+};
+pub use report::{
+    Cardinality, Coverage, Headline, Report, ReportRow, ReportSection, Statistics, TimeStatistics,
+    Unique,
+};
+pub use section::Section;
+pub use segment::{Segment, SegmentReport, SegmentSide};
+pub use selftest::selftest;
+pub use series::{Interval, SeriesPoint};
+pub use session::{path_matches, Session, Sessions, Visit};
+pub use slices::{SliceReport, SliceSummary, Slices};
+pub use syslog::{strip_envelope, SyslogListener};
+pub use tags::Tags;
+pub use timeline::{Timeline, TimelinePoint, TimelineReport};
+pub use top::TopRows;
+pub use trend::{load_trends, write_trends, TrendWeek};
+pub use unique::UniqueCount;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SortOrder {
     ByValue,
     ByKey,
 }
 
 impl SortOrder {
+    /// Largest value first, ties are broken by key so the order never depends on hashing
     pub fn sort_by_val<K, V>(a: &(K, V), b: &(K, V)) -> Ordering
     where
+        K: ToString,
         V: Ord,
     {
         b.1.cmp(&a.1)
+            .then_with(|| a.0.to_string().cmp(&b.0.to_string()))
     }
 
+    /// Smallest key first, keys with the same text are broken by largest value
     pub fn sort_by_key<K, V>(a: &(K, V), b: &(K, V)) -> Ordering
     where
         K: ToString,
+        V: Ord,
     {
-        a.0.to_string().cmp(&b.0.to_string())
+        a.0.to_string()
+            .cmp(&b.0.to_string())
+            .then_with(|| b.1.cmp(&a.1))
     }
 }
 
-#[derive(Debug)]
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "value" | "count" => Ok(SortOrder::ByValue),
+            "key" => Ok(SortOrder::ByKey),
+            _ => Err(format!("invalid sort order '{s}'")),
+        }
+    }
+}
+
+/// Controls what `TopInfo::print_summary` displays
+#[derive(Debug, Clone)]
+pub struct ReportOptions {
+    pub min_response_time_threshold: usize,
+    pub sections: Vec<Section>,
+    /// Overrides the sort order of every table, by default each table picks its own
+    pub sort: Option<SortOrder>,
+    /// Show the headline summary ahead of the tables
+    pub headline: bool,
+    /// Show a sparkline of the requests over the log duration with the totals
+    pub sparkline: bool,
+    /// Path patterns of the funnel steps, needs session tracking
+    pub funnel: Vec<String>,
+    /// Client IP allow and deny lists for the IP audit section
+    pub ip_audit: IpAudit,
+    /// The hostnames the TLS certificates cover, for the cert coverage section
+    pub cert_hosts: Option<HostList>,
+    /// Show the number of distinct client IPs, paths and user agents
+    pub unique: bool,
+    /// Show how much of the traffic comes from the busiest clients and paths
+    pub concentration: bool,
+    /// List the slowest requests beyond the 99.9th percentile response time
+    pub outliers: bool,
+    /// Compare the count sections with this expected profile
+    pub profile: Option<Profile>,
+    /// How sections are rendered in the table, Markdown and HTML output
+    pub layouts: HashMap<Section, SectionLayout>,
+    /// Decimal places and separator of the numeric columns
+    pub numbers: NumberFormat,
+    /// Draw a bar next to the counts of the table output
+    pub bars: bool,
+}
+
+impl ReportOptions {
+    fn shows(&self, section: Section) -> bool {
+        self.sections.contains(&section)
+    }
+
+    /// True if the selected sections need `TopInfo::track_sessions`
+    pub fn needs_sessions(&self) -> bool {
+        self.sections
+            .iter()
+            .any(|s| s.needs_sessions() && (*s != Section::Funnel || !self.funnel.is_empty()))
+    }
+
+    fn sort_or<'a>(&'a self, default: &'a SortOrder) -> &'a SortOrder {
+        self.sort.as_ref().unwrap_or(default)
+    }
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        ReportOptions {
+            min_response_time_threshold: 100,
+            sections: Section::defaults(),
+            sort: None,
+            headline: true,
+            sparkline: true,
+            funnel: Vec::new(),
+            ip_audit: IpAudit::default(),
+            cert_hosts: None,
+            unique: false,
+            concentration: false,
+            outliers: false,
+            layouts: HashMap::new(),
+            numbers: NumberFormat::default(),
+            bars: false,
+            profile: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogDuration {
     pub start: DateTime<FixedOffset>,
     pub end: DateTime<FixedOffset>,
@@ -52,12 +240,18 @@ pub struct LogDuration {
 #[derive(Debug)]
 pub struct TopInfo {
     max_results: usize,
-    ignore_parse_errors: bool,
+    read_options: ReadOptions,
+    filter: Filter,
     pub duration: LogDuration,
     pub total_requests: usize,
     pub errors: usize,
+    pub filtered: usize,
     pub response_codes: DefaultHashMap<StatusCode, usize>,
+    /// Requests with a missing or invalid status code
+    pub invalid_statuses: usize,
     pub request_methods: DefaultHashMap<Method, usize>,
+    /// Requests without a valid method
+    pub invalid_methods: usize,
     pub requests_no_query: DefaultHashMap<String, usize>,
     pub requests_query: DefaultHashMap<String, usize>,
     pub client_ips: DefaultHashMap<IpAddr, usize>,
@@ -66,26 +260,103 @@ pub struct TopInfo {
     pub backend_ips: DefaultHashMap<IpAddr, usize>,
     pub x_forwarded_fors: DefaultHashMap<String, usize>,
     pub hosts: DefaultHashMap<String, usize>,
+    /// Distinct client IPs by destination host
+    pub host_clients: DefaultHashMap<String, UniqueCount>,
     pub app_ids: DefaultHashMap<String, usize>,
     pub app_indexes: DefaultHashMap<u16, usize>,
-    pub response_times: DefaultHashMap<usize, usize>,
-    pub gorouter_times: DefaultHashMap<usize, usize>,
+    /// Requests by their time, bucketed by `set_time_resolution` in the report
+    pub response_times: TimeHistogram,
+    pub gorouter_times: TimeHistogram,
+    time_resolution: TimeResolution,
     pub x_cf_routererrors: DefaultHashMap<String, usize>,
+    pub requests_per_minute: DefaultHashMap<DateTime<FixedOffset>, usize>,
+    pub latencies_per_minute: DefaultHashMap<DateTime<FixedOffset>, Latencies>,
+    /// Only counted for intervals under a minute, see `set_interval`
+    pub requests_per_second: DefaultHashMap<DateTime<FixedOffset>, usize>,
+    pub latencies_per_second: DefaultHashMap<DateTime<FixedOffset>, Latencies>,
+    /// Requests with a 5xx and with a 4xx response, by the same times as the
+    /// requests
+    pub server_errors_per_minute: DefaultHashMap<DateTime<FixedOffset>, usize>,
+    pub client_errors_per_minute: DefaultHashMap<DateTime<FixedOffset>, usize>,
+    pub server_errors_per_second: DefaultHashMap<DateTime<FixedOffset>, usize>,
+    pub client_errors_per_second: DefaultHashMap<DateTime<FixedOffset>, usize>,
+    /// Bytes of the response bodies, by the same times as the requests
+    pub bytes_sent_per_minute: DefaultHashMap<DateTime<FixedOffset>, usize>,
+    pub bytes_sent_per_second: DefaultHashMap<DateTime<FixedOffset>, usize>,
+    interval: Interval,
+    /// Requests with a 5xx response, by path without query params
+    pub error_paths: DefaultHashMap<String, usize>,
+    /// Requests with a 4xx response, by path without query params
+    pub client_error_paths: DefaultHashMap<String, usize>,
+    /// Requests with a 4xx or 5xx response, by client IP
+    pub client_errors: DefaultHashMap<IpAddr, usize>,
+    /// Requests with likely PII in the URL, by path and kind of PII
+    pub pii_paths: DefaultHashMap<String, usize>,
+    /// Requests the parser couldn't make sense of, by kind and request line as
+    /// logged, like `invalid path: GET /a b HTTP/1.1`
+    pub malformed_requests: DefaultHashMap<String, usize>,
+    /// Values of the gorouter fields selected with `set_extra_fields`
+    pub extra_fields: ExtraFields,
+    /// Labels of the report, with the file tags of the files read so far
+    pub tags: Tags,
+    /// Distinct client IPs, paths without query params and user agents,
+    /// estimated for large inputs
+    pub unique_client_ips: UniqueCount,
+    pub unique_paths: UniqueCount,
+    pub unique_user_agents: UniqueCount,
+    /// Exact statistics of the response and gorouter times
+    pub response_time_stats: Stats,
+    pub gorouter_time_stats: Stats,
+    /// Bytes of the response bodies
+    pub bytes_sent: u64,
+    /// Bytes of the request bodies, only known for gorouter logs
+    pub bytes_received: Option<u64>,
+    /// Requests by the lower bound of the `report::REQUEST_SIZES` bucket
+    /// their body falls into, for gorouter logs
+    pub request_sizes: DefaultHashMap<usize, usize>,
+    /// Bytes of the request bodies by path without query params, for gorouter logs
+    pub bytes_received_by_path: DefaultHashMap<String, usize>,
+    /// Only counted when asked for with `set_segment`
+    pub segment: Option<Segment>,
+    /// The slowest requests, as many as the top entries of the tables
+    pub outliers: Outliers,
+    /// Only kept when asked for with `set_slowest`
+    pub slowest: Option<Outliers>,
+    pub sessions: Option<Sessions>,
+    /// Only counted when asked for with `set_slices`
+    pub slices: Option<Slices>,
+    pub drills: Vec<Drill>,
+    pub timelines: Vec<Timeline>,
+    pub extracts: Vec<Extract>,
+    pub breakdowns: Vec<Breakdown>,
+    interim: Option<interim::InterimState>,
+    checkpoint: Option<checkpoint::Checkpoint>,
+    // invalid values are only reported once, shared with the copies counting
+    // on other threads
+    warned_invalid_status: Arc<AtomicBool>,
+    warned_invalid_method: Arc<AtomicBool>,
 }
 
 impl TopInfo {
     pub fn new(max_results: usize, ignore_parse_errors: bool) -> TopInfo {
         TopInfo {
             max_results,
-            ignore_parse_errors,
+            read_options: ReadOptions {
+                ignore_parse_errors,
+                ..ReadOptions::default()
+            },
+            filter: Filter::default(),
             duration: LogDuration {
                 start: DateTime::default(),
                 end: DateTime::default(),
             },
             total_requests: 0,
             errors: 0,
+            filtered: 0,
             response_codes: DefaultHashMap::new(),
+            invalid_statuses: 0,
             request_methods: DefaultHashMap::new(),
+            invalid_methods: 0,
             requests_no_query: DefaultHashMap::new(),
             requests_query: DefaultHashMap::new(),
             client_ips: DefaultHashMap::new(),
@@ -94,124 +365,460 @@ impl TopInfo {
             backend_ips: DefaultHashMap::new(),
             x_forwarded_fors: DefaultHashMap::new(),
             hosts: DefaultHashMap::new(),
+            host_clients: DefaultHashMap::new(),
             app_ids: DefaultHashMap::new(),
             app_indexes: DefaultHashMap::new(),
-            response_times: DefaultHashMap::new(),
-            gorouter_times: DefaultHashMap::new(),
+            response_times: TimeHistogram::default(),
+            gorouter_times: TimeHistogram::default(),
+            time_resolution: TimeResolution::default(),
             x_cf_routererrors: DefaultHashMap::new(),
+            requests_per_minute: DefaultHashMap::new(),
+            latencies_per_minute: DefaultHashMap::new(),
+            requests_per_second: DefaultHashMap::new(),
+            latencies_per_second: DefaultHashMap::new(),
+            server_errors_per_minute: DefaultHashMap::new(),
+            client_errors_per_minute: DefaultHashMap::new(),
+            server_errors_per_second: DefaultHashMap::new(),
+            client_errors_per_second: DefaultHashMap::new(),
+            bytes_sent_per_minute: DefaultHashMap::new(),
+            bytes_sent_per_second: DefaultHashMap::new(),
+            interval: Interval::default(),
+            error_paths: DefaultHashMap::new(),
+            client_error_paths: DefaultHashMap::new(),
+            client_errors: DefaultHashMap::new(),
+            pii_paths: DefaultHashMap::new(),
+            malformed_requests: DefaultHashMap::new(),
+            extra_fields: ExtraFields::default(),
+            tags: Tags::default(),
+            unique_client_ips: UniqueCount::default(),
+            unique_paths: UniqueCount::default(),
+            unique_user_agents: UniqueCount::default(),
+            response_time_stats: Stats::default(),
+            gorouter_time_stats: Stats::default(),
+            bytes_sent: 0,
+            bytes_received: None,
+            request_sizes: DefaultHashMap::new(),
+            bytes_received_by_path: DefaultHashMap::new(),
+            segment: None,
+            outliers: Outliers::new(max_results),
+            slowest: None,
+            sessions: None,
+            slices: None,
+            drills: Vec::new(),
+            timelines: Vec::new(),
+            extracts: Vec::new(),
+            breakdowns: Vec::new(),
+            interim: None,
+            checkpoint: None,
+            warned_invalid_status: Arc::new(AtomicBool::new(false)),
+            warned_invalid_method: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Only count log entries matching `filter`
+    pub fn set_filter(&mut self, filter: Filter) {
+        self.filter = filter;
+    }
+
+    /// Count the requests matching `segment` apart from all the others
+    pub fn set_segment(&mut self, segment: Segment) {
+        self.segment = Some(segment);
+    }
+
+    /// Only read the members of tar archives whose path matches `pattern`
+    pub fn set_tar_members(&mut self, pattern: &str) {
+        self.read_options.tar_members = Some(pattern.to_string());
+    }
+
+    /// Try these formats, in order, on lines that don't parse in the format
+    /// of their file before counting them as parse errors
+    pub fn set_fallback_formats(&mut self, formats: Vec<access_log_parser::LogType>) {
+        self.read_options.fallback_formats = formats;
+    }
+
+    /// Join the lines matching `pattern` to the line before them before parsing
+    pub fn set_join_continuations(&mut self, pattern: regex::Regex) {
+        self.read_options.join_continuations = Some(pattern);
+    }
+
+    /// Remove the prefix matching `pattern` from every line before parsing
+    pub fn set_strip_prefix(&mut self, pattern: regex::Regex) {
+        self.read_options.strip_prefix = Some(pattern);
+    }
+
+    /// Read the output of `cf logs`, see `ReadOptions::cf_logs`
+    pub fn set_cf_logs(&mut self, cf_logs: bool) {
+        self.read_options.cf_logs = cf_logs;
+    }
+
+    /// Count the values of these `name:"value"` fields of gorouter lines
+    pub fn set_extra_fields(&mut self, extra_fields: ExtraFields) {
+        self.extra_fields = extra_fields;
+    }
+
+    /// Bucket the response and gorouter times by `resolution` instead of whole
+    /// seconds. The times are recorded as they are, so it can be changed at
+    /// any time before the report is rendered.
+    pub fn set_time_resolution(&mut self, resolution: TimeResolution) {
+        self.time_resolution = resolution;
+    }
+
+    pub fn time_resolution(&self) -> TimeResolution {
+        self.time_resolution
+    }
+
+    /// Show the time series in intervals of `interval` instead of minutes.
+    /// Intervals under a minute count the requests per second from then on,
+    /// set it before reading any logs.
+    pub fn set_interval(&mut self, interval: Interval) {
+        self.interval = interval;
+    }
+
+    pub fn interval(&self) -> Interval {
+        self.interval
+    }
+
+    /// Label the report with `tags`, file tags are added as matching files are read
+    pub fn set_tags(&mut self, tags: Tags) {
+        self.tags = tags;
+    }
+
+    /// Break down the requests selected by `drill` in a summary of their own
+    pub fn add_drill(&mut self, drill: Drill) {
+        self.drills.push(drill);
+    }
+
+    /// Count the requests selected by `timeline` per interval of `set_interval`
+    pub fn add_timeline(&mut self, timeline: Timeline) {
+        self.timelines.push(timeline);
+    }
+
+    /// Count the values `extract` captures from the paths
+    pub fn add_extract(&mut self, extract: Extract) {
+        self.extracts.push(extract);
+    }
+
+    /// Count the requests and response times by the value of `key`, like every path
+    pub fn add_breakdown(&mut self, key: BreakdownKey) {
+        if !self
+            .breakdowns
+            .iter()
+            .any(|breakdown| breakdown.key() == key)
+        {
+            self.breakdowns.push(Breakdown::new(key));
         }
     }
 
+    /// Keep the `count` slowest requests with their raw lines
+    pub fn set_slowest(&mut self, count: usize) {
+        self.slowest = Some(Outliers::with_lines(count));
+    }
+
+    /// Summarize the requests of every `interval` of the logs, like every hour
+    pub fn set_slices(&mut self, interval: Interval) {
+        self.slices = Some(Slices::new(interval));
+    }
+
+    /// Group requests into sessions, which uses memory for every request.
+    /// A new session starts after `timeout` of inactivity.
+    pub fn track_sessions(&mut self, timeout: chrono::Duration) {
+        self.sessions = Some(Sessions::new(timeout));
+    }
+
     pub fn process_file(&mut self, path: &str, log_type: access_log_parser::LogType) -> Result<()> {
-        let tmp = io::stdin();
-        let reader: io::BufReader<Box<dyn io::Read>> = if path.trim() == "-" {
-            io::BufReader::new(Box::new(tmp.lock()))
-        } else {
-            io::BufReader::new(Box::new(fs::File::open(path)?))
-        };
+        self.tags.add_file(path);
+        let options = self.read_options.clone();
+        let errors = read_log(path, log_type, &options, |log, line| {
+            self.process_entry(log, line);
+            Ok(())
+        })?;
+        self.errors += errors;
+        Ok(())
+    }
 
-        reader
-            .lines()
-            .filter_map(|line| match line {
-                Ok(line) => Some(line),
-                Err(msg) => {
-                    eprintln!("Read failed: {msg:#?}",);
-                    None
-                }
-            })
-            .for_each(|line| match access_log_parser::parse(log_type, &line) {
-                Ok(log) => {
-                    self.calc_stats(log);
-                }
-                Err(err) => {
-                    self.errors += 1;
-                    if !self.ignore_parse_errors {
-                        eprintln!("Parse error: {err:#?} with line '{line}'");
-                    }
-                }
-            });
+    /// Counts the lines added to a followed access log since the last call
+    pub fn process_new_lines(
+        &mut self,
+        follower: &mut LogFollower,
+        log_type: access_log_parser::LogType,
+    ) -> Result<()> {
+        self.tags.add_file(follower.path());
+        let options = self.read_options.clone();
+        let errors = follower.read_new(log_type, &options, |log, line| {
+            self.process_entry(log, line);
+            Ok(())
+        })?;
+        self.errors += errors;
         Ok(())
     }
 
-    fn calc_stats(&mut self, log_entry: access_log_parser::LogEntry) {
+    /// Counts the lines piped into STDIN since the last call
+    pub fn process_stdin(
+        &mut self,
+        follower: &mut StdinFollower,
+        log_type: access_log_parser::LogType,
+    ) -> Result<()> {
+        let options = self.read_options.clone();
+        let errors = follower.read_new(log_type, &options, |log, line| {
+            self.process_entry(log, line);
+            Ok(())
+        })?;
+        self.errors += errors;
+        Ok(())
+    }
+
+    /// Counts the messages published to a followed Kafka topic since the last call
+    #[cfg(feature = "kafka")]
+    pub fn process_new_messages(
+        &mut self,
+        follower: &mut KafkaFollower,
+        log_type: access_log_parser::LogType,
+    ) -> Result<()> {
+        let options = self.read_options.clone();
+        let errors = follower.read_new(log_type, &options, |log, line| {
+            self.process_entry(log, line);
+            Ok(())
+        })?;
+        self.errors += errors;
+        Ok(())
+    }
+
+    /// Counts the syslog messages received since the last call
+    pub fn process_syslog(
+        &mut self,
+        listener: &mut SyslogListener,
+        log_type: access_log_parser::LogType,
+    ) -> Result<()> {
+        let options = self.read_options.clone();
+        let errors = listener.read_new(log_type, &options, |log, line| {
+            self.process_entry(log, line);
+            Ok(())
+        })?;
+        self.errors += errors;
+        Ok(())
+    }
+
+    fn process_entry(&mut self, log_entry: access_log_parser::LogEntry, line: &str) {
+        if self.filter.matches(&log_entry) {
+            if !self.extra_fields.is_empty()
+                && matches!(log_entry, access_log_parser::LogEntry::GorouterLog(_))
+            {
+                self.extra_fields.record(line);
+            }
+            self.calc_stats(log_entry, line);
+        } else {
+            self.filtered += 1;
+        }
+        self.check_interim(false);
+    }
+
+    fn calc_stats(&mut self, log_entry: access_log_parser::LogEntry, line: &str) {
+        if let Some(segment) = &mut self.segment {
+            segment.record(&log_entry);
+        }
+        if self.sessions.is_some()
+            || !self.drills.is_empty()
+            || !self.timelines.is_empty()
+            || !self.extracts.is_empty()
+            || !self.breakdowns.is_empty()
+        {
+            let entry = Entry::new(&log_entry);
+            if let Some(sessions) = &mut self.sessions {
+                sessions.record(&entry);
+            }
+            for drill in &mut self.drills {
+                drill.record(&entry);
+            }
+            for timeline in &mut self.timelines {
+                timeline.record(&entry);
+            }
+            for extract in &mut self.extracts {
+                extract.record(&entry);
+            }
+            for breakdown in &mut self.breakdowns {
+                breakdown.record(&entry);
+            }
+        }
+
+        self.outliers.record(&log_entry, line);
+        if let Some(slowest) = &mut self.slowest {
+            slowest.record(&log_entry, line);
+        }
+
         match log_entry {
-            access_log_parser::LogEntry::CommonLog(log) => self.calc_common_log(log),
-            access_log_parser::LogEntry::CombinedLog(log) => self.calc_combined_log(log),
-            access_log_parser::LogEntry::GorouterLog(log) => self.calc_gorouter_log(log),
+            access_log_parser::LogEntry::CommonLog(log) => self.calc_common_log(log, line),
+            access_log_parser::LogEntry::CombinedLog(log) => self.calc_combined_log(log, line),
+            access_log_parser::LogEntry::GorouterLog(log) => self.calc_gorouter_log(log, line),
             access_log_parser::LogEntry::CloudControllerLog(log) => {
-                self.calc_cloud_controller_log(log)
+                self.calc_cloud_controller_log(log, line)
             }
         }
     }
 
-    fn calc_common_log(&mut self, log_entry: access_log_parser::CommonLogEntry) {
+    // counts everything every log format has in common
+    fn calc_request(
+        &mut self,
+        timestamp: DateTime<FixedOffset>,
+        status_code: StatusCode,
+        request: &access_log_parser::RequestResult,
+        response_time: Option<f64>,
+        bytes_sent: u64,
+        line: &str,
+    ) {
         // count total requests
         self.total_requests += 1;
+        self.bytes_sent += bytes_sent;
 
-        // pick out oldest & newest log entries
-        if log_entry.timestamp < self.duration.start {
-            self.duration.start = log_entry.timestamp;
+        // pick out oldest & newest log entries, the first one replaces the placeholder
+        if self.total_requests == 1 || timestamp < self.duration.start {
+            self.duration.start = timestamp;
+        }
+        if self.total_requests == 1 || timestamp > self.duration.end {
+            self.duration.end = timestamp;
         }
-        if log_entry.timestamp > self.duration.end {
-            self.duration.end = log_entry.timestamp;
+        if let Some(response_time) = response_time {
+            self.response_time_stats.record(response_time);
+        }
+        if let Some(minute) = timestamp.with_second(0).and_then(|t| t.with_nanosecond(0)) {
+            self.requests_per_minute[minute] += 1;
+            if let Some(response_time) = response_time {
+                self.latencies_per_minute[minute].record(response_time);
+            }
+            self.bytes_sent_per_minute[minute] += bytes_sent as usize;
+            if status_code.is_server_error() {
+                self.server_errors_per_minute[minute] += 1;
+            } else if status_code.is_client_error() {
+                self.client_errors_per_minute[minute] += 1;
+            }
+        }
+        if self.interval.is_sub_minute() {
+            if let Some(second) = timestamp.with_nanosecond(0) {
+                self.requests_per_second[second] += 1;
+                if let Some(response_time) = response_time {
+                    self.latencies_per_second[second].record(response_time);
+                }
+                self.bytes_sent_per_second[second] += bytes_sent as usize;
+                if status_code.is_server_error() {
+                    self.server_errors_per_second[second] += 1;
+                } else if status_code.is_client_error() {
+                    self.client_errors_per_second[second] += 1;
+                }
+            }
         }
 
-        // count individual resources
-        self.response_codes[log_entry.status_code] += 1;
-        if let access_log_parser::RequestResult::Valid(ref req) = log_entry.request {
-            self.request_methods[req.method().clone()] += 1;
+        // count individual resources, the parser reads a missing status code
+        // as 418, lines with an invalid one are passed on the same way
+        let status_field = reader::status_field(line);
+        if status_code != StatusCode::IM_A_TEAPOT || status_field == Some("418") {
+            self.response_codes[status_code] += 1;
+        } else {
+            self.invalid_statuses += 1;
+            if !self
+                .warned_invalid_status
+                .swap(true, atomic::Ordering::Relaxed)
+            {
+                diagnostics::emit(
+                    diagnostics::Kind::Warning,
+                    &format!(
+                        "Missing or invalid status code {} in line '{line}', such requests are counted as 'invalid'",
+                        status_field.unwrap_or("-")
+                    ),
+                );
+            }
+        }
+        match request_method(request, line) {
+            Some(method) => self.request_methods[method] += 1,
+            None => {
+                self.invalid_methods += 1;
+                if !self
+                    .warned_invalid_method
+                    .swap(true, atomic::Ordering::Relaxed)
+                {
+                    diagnostics::emit(
+                        diagnostics::Kind::Warning,
+                        &format!(
+                            "Invalid request '{}' in line '{line}', requests without a valid method are counted as 'invalid'",
+                            reader::request_field(line).unwrap_or_default()
+                        ),
+                    );
+                }
+            }
         }
-        self.client_ips[log_entry.ip] += 1;
 
-        // count query path hits
-        let (path, path_no_query) = match log_entry.request {
-            access_log_parser::RequestResult::Valid(ref req) => (
+        // count query path hits, malformed requests have no path to count
+        let (path, path_no_query) = match request {
+            access_log_parser::RequestResult::Valid(req) => (
                 req.uri()
                     .path_and_query()
                     .map(|p| p.as_str())
                     .unwrap_or("<none>"),
                 req.uri().path(),
             ),
-            access_log_parser::RequestResult::InvalidPath(path, _err) => (path, ""),
-            access_log_parser::RequestResult::InvalidRequest(path) => (path, ""),
+            access_log_parser::RequestResult::InvalidPath(path, _err) => {
+                self.malformed_requests[malformed_key("invalid path", path, line)] += 1;
+                (*path, "")
+            }
+            access_log_parser::RequestResult::InvalidRequest(path) => {
+                self.malformed_requests[malformed_key("invalid request", path, line)] += 1;
+                (*path, "")
+            }
         };
-        self.requests_no_query[path_no_query.to_string()] += 1;
-        self.requests_query[path.to_string()] += 1;
-    }
-
-    fn calc_combined_log(&mut self, log_entry: access_log_parser::CombinedLogEntry) {
-        // count total requests
-        self.total_requests += 1;
-
-        // pick out oldest & newest log entries
-        if log_entry.timestamp < self.duration.start {
-            self.duration.start = log_entry.timestamp;
+        if let Some(slices) = &mut self.slices {
+            let path = (!path_no_query.is_empty()).then_some(path_no_query);
+            slices.record(timestamp, status_code, response_time, path);
+        }
+        if !path_no_query.is_empty() {
+            self.requests_no_query[path_no_query.to_string()] += 1;
+            self.unique_paths.insert(path_no_query);
+            self.requests_query[path.to_string()] += 1;
+            if status_code.is_server_error() {
+                self.error_paths[path_no_query.to_string()] += 1;
+            } else if status_code.is_client_error() {
+                self.client_error_paths[path_no_query.to_string()] += 1;
+            }
         }
-        if log_entry.timestamp > self.duration.end {
-            self.duration.end = log_entry.timestamp;
+
+        // the query often holds the PII, so the whole request is scanned
+        for pii in pii::scan(path) {
+            let path = if path_no_query.is_empty() {
+                path
+            } else {
+                path_no_query
+            };
+            self.pii_paths[format!("{path} ({pii})")] += 1;
         }
+    }
 
-        // count individual resources
-        self.response_codes[log_entry.status_code] += 1;
-        if let access_log_parser::RequestResult::Valid(ref req) = log_entry.request {
-            self.request_methods[req.method().clone()] += 1;
+    fn calc_client(&mut self, ip: IpAddr, status_code: StatusCode) {
+        self.client_ips[ip] += 1;
+        self.unique_client_ips.insert(&ip);
+        if status_code.is_client_error() || status_code.is_server_error() {
+            self.client_errors[ip] += 1;
         }
-        self.client_ips[log_entry.ip] += 1;
+    }
 
-        // count query path hits
-        let (path, path_no_query) = match log_entry.request {
-            access_log_parser::RequestResult::Valid(ref req) => (
-                req.uri()
-                    .path_and_query()
-                    .map(|p| p.as_str())
-                    .unwrap_or("<none>"),
-                req.uri().path(),
-            ),
-            access_log_parser::RequestResult::InvalidPath(path, _err) => (path, ""),
-            access_log_parser::RequestResult::InvalidRequest(path) => (path, ""),
-        };
-        self.requests_no_query[path_no_query.to_string()] += 1;
-        self.requests_query[path.to_string()] += 1;
+    fn calc_common_log(&mut self, log_entry: access_log_parser::CommonLogEntry, line: &str) {
+        self.calc_request(
+            log_entry.timestamp,
+            log_entry.status_code,
+            &log_entry.request,
+            None,
+            log_entry.bytes,
+            line,
+        );
+        self.calc_client(log_entry.ip, log_entry.status_code);
+    }
+
+    fn calc_combined_log(&mut self, log_entry: access_log_parser::CombinedLogEntry, line: &str) {
+        self.calc_request(
+            log_entry.timestamp,
+            log_entry.status_code,
+            &log_entry.request,
+            None,
+            log_entry.bytes,
+            line,
+        );
+        self.calc_client(log_entry.ip, log_entry.status_code);
 
         // count referrer hits
         if let Some(referrer) = log_entry.referrer {
@@ -219,41 +826,24 @@ impl TopInfo {
         }
 
         // count user agent hits
-        self.user_agents[log_entry.user_agent.unwrap_or("<none>").to_string()] += 1;
+        let user_agent = log_entry.user_agent.unwrap_or("<none>");
+        self.user_agents[user_agent.to_string()] += 1;
+        self.unique_user_agents.insert(user_agent);
     }
 
-    fn calc_cloud_controller_log(&mut self, log_entry: access_log_parser::CloudControllerLogEntry) {
-        // count total requests
-        self.total_requests += 1;
-
-        // pick out oldest & newest log entries
-        if log_entry.timestamp < self.duration.start {
-            self.duration.start = log_entry.timestamp;
-        }
-        if log_entry.timestamp > self.duration.end {
-            self.duration.end = log_entry.timestamp;
-        }
-
-        // count individual resources
-        self.response_codes[log_entry.status_code] += 1;
-        if let access_log_parser::RequestResult::Valid(ref req) = log_entry.request {
-            self.request_methods[req.method().clone()] += 1;
-        }
-
-        // count query path hits
-        let (path, path_no_query) = match log_entry.request {
-            access_log_parser::RequestResult::Valid(ref req) => (
-                req.uri()
-                    .path_and_query()
-                    .map(|p| p.as_str())
-                    .unwrap_or("<none>"),
-                req.uri().path(),
-            ),
-            access_log_parser::RequestResult::InvalidPath(path, _err) => (path, ""),
-            access_log_parser::RequestResult::InvalidRequest(path) => (path, ""),
-        };
-        self.requests_no_query[path_no_query.to_string()] += 1;
-        self.requests_query[path.to_string()] += 1;
+    fn calc_cloud_controller_log(
+        &mut self,
+        log_entry: access_log_parser::CloudControllerLogEntry,
+        line: &str,
+    ) {
+        self.calc_request(
+            log_entry.timestamp,
+            log_entry.status_code,
+            &log_entry.request,
+            log_entry.response_time,
+            log_entry.bytes,
+            line,
+        );
 
         // count referrer hits
         if let Some(referrer) = log_entry.referrer {
@@ -261,7 +851,9 @@ impl TopInfo {
         }
 
         // count user agent hits
-        self.user_agents[log_entry.user_agent.unwrap_or("<none>").to_string()] += 1;
+        let user_agent = log_entry.user_agent.unwrap_or("<none>");
+        self.user_agents[user_agent.to_string()] += 1;
+        self.unique_user_agents.insert(user_agent);
 
         // count cloud controller specific hits
         self.x_forwarded_fors[log_entry
@@ -271,47 +863,33 @@ impl TopInfo {
             .collect::<Vec<String>>()
             .join(", ")] += 1;
         self.hosts[log_entry.request_host.into()] += 1;
+        // the client is the first hop of X-Forwarded-For
+        if let Some(ip) = log_entry.x_forwarded_for.first() {
+            self.unique_client_ips.insert(ip);
+        }
 
-        // bucket response times
-        self.response_times[log_entry
-            .response_time
-            .map(|t| t.floor() as usize)
-            .unwrap_or(usize::MAX)] += 1;
+        // record response times
+        self.response_times.record(log_entry.response_time);
     }
 
-    fn calc_gorouter_log(&mut self, log_entry: access_log_parser::GorouterLogEntry) {
-        // count total requests
-        self.total_requests += 1;
-
-        // pick out oldest & newest log entries
-        if log_entry.timestamp < self.duration.start {
-            self.duration.start = log_entry.timestamp;
+    fn calc_gorouter_log(&mut self, log_entry: access_log_parser::GorouterLogEntry, line: &str) {
+        self.calc_request(
+            log_entry.timestamp,
+            log_entry.status_code,
+            &log_entry.request,
+            log_entry.response_time,
+            log_entry.bytes_sent,
+            line,
+        );
+        *self.bytes_received.get_or_insert(0) += log_entry.bytes_received;
+        let size = log_entry.bytes_received as usize;
+        if let Some(bound) = report::REQUEST_SIZES.iter().rev().find(|b| **b <= size) {
+            self.request_sizes[*bound] += 1;
         }
-        if log_entry.timestamp > self.duration.end {
-            self.duration.end = log_entry.timestamp;
+        if let (access_log_parser::RequestResult::Valid(req), 1..) = (&log_entry.request, size) {
+            self.bytes_received_by_path[req.uri().path().to_string()] += size;
         }
-
-        // count individual resources
-        self.response_codes[log_entry.status_code] += 1;
-        if let access_log_parser::RequestResult::Valid(ref req) = log_entry.request {
-            self.request_methods[req.method().clone()] += 1;
-        }
-        self.client_ips[log_entry.remote_addr] += 1;
-
-        // count query path hits
-        let (path, path_no_query) = match log_entry.request {
-            access_log_parser::RequestResult::Valid(ref req) => (
-                req.uri()
-                    .path_and_query()
-                    .map(|p| p.as_str())
-                    .unwrap_or("<none>"),
-                req.uri().path(),
-            ),
-            access_log_parser::RequestResult::InvalidPath(path, _err) => (path, ""),
-            access_log_parser::RequestResult::InvalidRequest(path) => (path, ""),
-        };
-        self.requests_no_query[path_no_query.to_string()] += 1;
-        self.requests_query[path.to_string()] += 1;
+        self.calc_client(log_entry.remote_addr, log_entry.status_code);
 
         // count referrer hits
         if let Some(referrer) = log_entry.referrer {
@@ -319,7 +897,9 @@ impl TopInfo {
         }
 
         // count user agent hits
-        self.user_agents[log_entry.user_agent.unwrap_or("<none>").to_string()] += 1;
+        let user_agent = log_entry.user_agent.unwrap_or("<none>");
+        self.user_agents[user_agent.to_string()] += 1;
+        self.unique_user_agents.insert(user_agent);
 
         // count gorouter specific hits
         if let Some(ip) = log_entry.backend_addr {
@@ -332,6 +912,7 @@ impl TopInfo {
             .collect::<Vec<String>>()
             .join(", ")] += 1;
         self.hosts[log_entry.request_host.into()] += 1;
+        self.host_clients[log_entry.request_host.into()].insert(&log_entry.remote_addr);
         if let Some(app_id) = log_entry.app_id {
             self.app_ids[app_id.into()] += 1;
         }
@@ -339,279 +920,61 @@ impl TopInfo {
             self.app_indexes[app_index] += 1;
         }
 
-        // bucket response times
-        self.response_times[log_entry
-            .response_time
-            .map(|t| t.floor() as usize)
-            .unwrap_or(usize::MAX)] += 1;
+        // record response times
+        self.response_times.record(log_entry.response_time);
 
-        // bucket gorouter times
-        self.gorouter_times[log_entry
-            .gorouter_time
-            .map(|t| t.floor() as usize)
-            .unwrap_or(usize::MAX)] += 1;
+        // record gorouter times
+        if let Some(gorouter_time) = log_entry.gorouter_time {
+            self.gorouter_time_stats.record(gorouter_time);
+        }
+        self.gorouter_times.record(log_entry.gorouter_time);
 
         // count x_cf_routererror hits
         self.x_cf_routererrors[log_entry.x_cf_routererror.unwrap_or("<none>").to_string()] += 1;
     }
 
-    fn print_map<I, K, V>(iter: I, sort_order: &SortOrder, max: usize)
-    where
-        K: ToString,
-        V: Ord + ToString,
-        I: Iterator<Item = (K, V)>,
-    {
-        let mut data: Vec<(K, V)> = iter.collect();
-
-        match sort_order {
-            SortOrder::ByKey => data.sort_by(SortOrder::sort_by_key),
-            SortOrder::ByValue => data.sort_by(SortOrder::sort_by_val),
-        };
-
-        println!();
-
-        let mut table = Table::new();
-        table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
-        for (key, val) in data.iter().take(max) {
-            table.add_row(Row::new(vec![cell!(key), cell!(val)]));
-        }
-        table.printstd();
-
-        println!();
+    /// Prints the summary as tables to STDOUT
+    pub fn print_summary(&self, options: &ReportOptions) -> Result<()> {
+        output::write_report(
+            &self.report(options),
+            OutputFormat::Table,
+            &mut io::stdout().lock(),
+        )
     }
+}
 
-    pub fn print_summary(&self, min_response_time_threshold: usize) {
-        println!();
-        println!("Duration: {} to {}", self.duration.start, self.duration.end);
-        println!();
-
-        println!();
-        println!("Total Requests: {}", self.total_requests);
-        println!("Total Errors  : {}", self.errors);
-        println!();
-
-        println!("Response Codes:");
-        TopInfo::print_map(self.response_codes.iter(), &SortOrder::ByKey, usize::MAX);
-
-        println!("Request Methods:");
-        TopInfo::print_map(self.request_methods.iter(), &SortOrder::ByValue, usize::MAX);
-
-        println!("Top '{}' Requests (no query params)", self.max_results);
-        TopInfo::print_map(
-            self.requests_no_query.iter(),
-            &SortOrder::ByValue,
-            self.max_results,
-        );
-
-        println!("Top '{}' Requests (with query params)", self.max_results);
-        TopInfo::print_map(
-            self.requests_query.iter(),
-            &SortOrder::ByValue,
-            self.max_results,
-        );
-
-        if !self.user_agents.is_empty() {
-            println!("Top '{}' User Agents", self.max_results);
-            TopInfo::print_map(
-                self.user_agents.iter(),
-                &SortOrder::ByValue,
-                self.max_results,
-            );
-        }
-
-        if !self.referrers.is_empty() {
-            println!("Top '{}' Referrers", self.max_results);
-            TopInfo::print_map(self.referrers.iter(), &SortOrder::ByValue, self.max_results);
-        }
-
-        if !self.client_ips.is_empty() {
-            println!("Top '{}' Client IPs", self.max_results);
-            TopInfo::print_map(
-                self.client_ips.iter(),
-                &SortOrder::ByValue,
-                self.max_results,
-            );
-        }
-
-        if !self.backend_ips.is_empty() {
-            println!(
-                "Top '{}' Backend Address (Cells & Platform VMs)",
-                self.max_results
-            );
-            TopInfo::print_map(
-                self.backend_ips.iter(),
-                &SortOrder::ByValue,
-                self.max_results,
-            );
-        }
-
-        if !self.x_forwarded_fors.is_empty() {
-            println!("Top '{}' X-Forwarded-For Ips", self.max_results);
-            TopInfo::print_map(
-                self.x_forwarded_fors.iter(),
-                &SortOrder::ByValue,
-                self.max_results,
-            );
-        }
-
-        if !self.hosts.is_empty() {
-            println!("Top '{}' Destination Hosts", self.max_results);
-            TopInfo::print_map(self.hosts.iter(), &SortOrder::ByValue, self.max_results);
-        }
-
-        if !self.app_ids.is_empty() {
-            println!("Top '{}' Application UUIDs", self.max_results);
-            TopInfo::print_map(self.app_ids.iter(), &SortOrder::ByValue, self.max_results);
-        }
-
-        if !self.app_indexes.is_empty() {
-            println!("Top '{}' Application Indexes", self.max_results);
-            TopInfo::print_map(
-                self.app_indexes.iter(),
-                &SortOrder::ByValue,
-                self.max_results,
-            );
-        }
-
-        if !self.response_times.is_empty() {
-            println!("Top Response Times");
-            let mut keys: Vec<&usize> = self
-                .response_times
-                .keys()
-                .filter(|&k| *k < usize::MAX)
-                .collect();
-            keys.sort();
-
-            let max_key = **keys.iter().max().unwrap_or(&&0);
-            let max_width = format!("{max_key}").len();
-
-            println!();
-
-            let mut table = Table::new();
-            table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
-
-            let mut bucket_val: usize = 0;
-            let mut bucket_start: usize = 0;
-
-            for key in keys {
-                if bucket_start == 0 {
-                    bucket_start = *key;
-                }
-
-                bucket_val += self.response_times[key];
-
-                if bucket_val >= min_response_time_threshold {
-                    table.add_row(Row::new(vec![
-                        cell!(format!(
-                            "{:width$} to {:width$}",
-                            bucket_start,
-                            key + 1,
-                            width = max_width
-                        )),
-                        cell!(bucket_val),
-                    ]));
-                    bucket_start = 0;
-                    bucket_val = 0;
-                }
-            }
-
-            if bucket_val > 0 {
-                table.add_row(Row::new(vec![
-                    cell!(format!(
-                        "{:width$} to {:width$}",
-                        bucket_start,
-                        max_key + 1,
-                        width = max_width
-                    )),
-                    cell!(bucket_val),
-                ]));
-            }
-
-            if self.response_times.contains_key(&usize::MAX) {
-                table.add_row(Row::new(vec![
-                    cell!("<none>"),
-                    cell!(self.response_times.get(usize::MAX)),
-                ]));
-            }
-
-            table.printstd();
-
-            println!();
-        }
-
-        if !self.gorouter_times.is_empty() {
-            println!("Top Gorouter Times");
-            let mut keys: Vec<&usize> = self
-                .gorouter_times
-                .keys()
-                .filter(|&k| *k < usize::MAX)
-                .collect();
-            keys.sort();
-
-            let max_key = **keys.iter().max().unwrap_or(&&0);
-            let max_width = format!("{max_key}").len();
-
-            println!();
-
-            let mut table = Table::new();
-            table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
-
-            let mut bucket_val: usize = 0;
-            let mut bucket_start: usize = 0;
-
-            for key in keys {
-                if bucket_start == 0 {
-                    bucket_start = *key;
-                }
-
-                bucket_val += self.gorouter_times[key];
-
-                if bucket_val >= min_response_time_threshold {
-                    table.add_row(Row::new(vec![
-                        cell!(format!(
-                            "{:width$} to {:width$}",
-                            bucket_start,
-                            key + 1,
-                            width = max_width
-                        )),
-                        cell!(bucket_val),
-                    ]));
-                    bucket_start = 0;
-                    bucket_val = 0;
-                }
-            }
-
-            if bucket_val > 0 {
-                table.add_row(Row::new(vec![
-                    cell!(format!(
-                        "{:width$} to {:width$}",
-                        bucket_start,
-                        max_key + 1,
-                        width = max_width
-                    )),
-                    cell!(bucket_val),
-                ]));
-            }
-
-            if self.gorouter_times.contains_key(&usize::MAX) {
-                table.add_row(Row::new(vec![
-                    cell!("<none>"),
-                    cell!(self.gorouter_times.get(usize::MAX)),
-                ]));
+// the key of a malformed request, its kind and the request line as logged.
+// Control characters are escaped, they'd mess up the report otherwise.
+fn malformed_key(kind: &str, parsed: &str, line: &str) -> String {
+    let request = reader::request_field(line).unwrap_or(parsed);
+    if request.is_empty() || request == "-" {
+        return format!("{kind}: <none>");
+    }
+    let request: String = request
+        .chars()
+        .map(|c| {
+            if c.is_control() {
+                c.escape_default().to_string()
+            } else {
+                c.to_string()
             }
+        })
+        .collect();
+    format!("{kind}: {request}")
+}
 
-            table.printstd();
-
-            println!();
-        }
-
-        if !self.x_cf_routererrors.is_empty() {
-            println!("Top '{}' CF Router Errors", self.max_results);
-            TopInfo::print_map(
-                self.x_cf_routererrors.iter(),
-                &SortOrder::ByValue,
-                self.max_results,
-            );
-        }
+// the method of a request. Requests the parser couldn't make sense of, like
+// `GET /a b HTTP/2`, still count with a method of upper case letters, `-` and `_`.
+fn request_method(request: &access_log_parser::RequestResult, line: &str) -> Option<Method> {
+    if let access_log_parser::RequestResult::Valid(req) = request {
+        return Some(req.method().clone());
     }
+    let method = reader::request_field(line)?.split(' ').next()?;
+    let plausible = method.starts_with(|c: char| c.is_ascii_uppercase())
+        && method
+            .bytes()
+            .all(|b| b.is_ascii_uppercase() || b == b'-' || b == b'_');
+    plausible
+        .then(|| Method::from_bytes(method.as_bytes()).ok())
+        .flatten()
 }
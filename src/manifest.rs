@@ -0,0 +1,180 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `--manifest`, a record of the evidence a report was made from: the hashes
+//! of the access logs read and of the reports and counts written, with the
+//! filters and sections in effect, so a report can later be shown to belong
+//! to specific files.
+use crate::cloud::is_object_url;
+use crate::explain::format_name;
+use crate::reader::is_url;
+use crate::{Filter, OutputFile, Section};
+use access_log_parser::LogType;
+use anyhow::{bail, Context, Result};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The access logs a report was made from and the files it was written to
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub tool: String,
+    pub version: String,
+    pub created: DateTime<Utc>,
+    /// What the filters let through, like `--explain` lists them
+    pub filters: Vec<String>,
+    pub sections: Vec<String>,
+    pub inputs: Vec<ManifestInput>,
+    /// The reports and saved counts
+    pub outputs: Vec<ManifestFile>,
+}
+
+/// An access log that was read
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestInput {
+    pub path: String,
+    pub format: String,
+    /// The bytes that were read, from the start to the end of the file
+    pub range: [u64; 2],
+    pub sha256: String,
+}
+
+/// A file that was written
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub path: String,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+// the length and SHA-256 of the file at `path`
+fn hash_file(path: &str) -> Result<(u64, String)> {
+    let mut file = fs::File::open(path).with_context(|| format!("opening {path}"))?;
+    let mut hasher = Sha256::new();
+    let bytes = io::copy(&mut file, &mut hasher).with_context(|| format!("reading {path}"))?;
+    Ok((bytes, format!("{:x}", hasher.finalize())))
+}
+
+impl Manifest {
+    /// Hashes the access logs in `files` before they're read. Only local
+    /// files can be hashed, not STDIN or URLs.
+    pub fn new(
+        files: &[(String, LogType)],
+        filter: &Filter,
+        sections: &[Section],
+    ) -> Result<Manifest> {
+        let inputs = files
+            .iter()
+            .map(|(path, log_type)| {
+                if path.trim() == "-" || is_url(path) || is_object_url(path) {
+                    bail!("--manifest can only hash local files, not '{path}'");
+                }
+                let (bytes, sha256) = hash_file(path)?;
+                Ok(ManifestInput {
+                    path: path.clone(),
+                    format: format_name(*log_type).to_string(),
+                    range: [0, bytes],
+                    sha256,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Manifest {
+            tool: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            created: Utc::now(),
+            filters: filter.describe(),
+            sections: sections.iter().map(|s| s.name().to_string()).collect(),
+            inputs,
+            outputs: Vec::new(),
+        })
+    }
+
+    /// Fails if an access log grew or shrank since it was hashed, then the
+    /// report may have counted lines the hash doesn't cover
+    pub fn check_inputs(&self) -> Result<()> {
+        for input in &self.inputs {
+            let len = fs::metadata(&input.path)
+                .with_context(|| format!("reading {}", input.path))?
+                .len();
+            if len != input.range[1] {
+                bail!(
+                    "{} changed while it was read, from {} to {len} bytes, the manifest wouldn't match the report",
+                    input.path,
+                    input.range[1]
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Hashes the written report or counts at `path`
+    pub fn add_output(&mut self, path: &str) -> Result<()> {
+        let (bytes, sha256) = hash_file(path)?;
+        self.outputs.push(ManifestFile {
+            path: path.to_string(),
+            bytes,
+            sha256,
+        });
+        Ok(())
+    }
+
+    /// Writes the manifest to `path` through a temporary file, like the
+    /// report. Fails if `path` exists, unless `overwrite` is set.
+    pub fn save(&self, path: &Path, overwrite: bool) -> Result<()> {
+        let mut out = OutputFile::create(&path.to_string_lossy(), overwrite)?;
+        serde_json::to_writer_pretty(&mut out, self)?;
+        writeln!(out)?;
+        out.finish()
+            .with_context(|| format!("writing manifest {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Manifest> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("reading manifest {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("parsing manifest {}", path.display()))
+    }
+
+    /// Hashes every file of the manifest again and writes whether it's
+    /// unchanged to `out`. Returns the number of files which changed or are
+    /// missing.
+    pub fn verify<W: Write>(&self, out: &mut W) -> Result<usize> {
+        let files = self
+            .inputs
+            .iter()
+            .map(|input| (&input.path, input.range[1], &input.sha256))
+            .chain(
+                self.outputs
+                    .iter()
+                    .map(|output| (&output.path, output.bytes, &output.sha256)),
+            );
+
+        let mut mismatches = 0;
+        for (path, bytes, sha256) in files {
+            let status = match hash_file(path) {
+                Err(_) => "missing",
+                Ok((_, hash)) if hash == *sha256 => "ok",
+                Ok((len, _)) if len != bytes => "changed (size differs)",
+                Ok(_) => "changed (sha256 differs)",
+            };
+            if status != "ok" {
+                mismatches += 1;
+            }
+            writeln!(out, "{path}: {status}")?;
+        }
+        Ok(mismatches)
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The slowest requests, kept with the fields needed to find them in the
+//! logs, so the response time outliers and the `--slowest` requests can be
+//! listed in the report.
+use crate::TimeHistogram;
+use access_log_parser::{LogEntry, RequestResult};
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+// requests beyond this percentile of the response times are outliers
+const PERCENTILE: f64 = 99.9;
+
+/// One of the slowest requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Outlier {
+    pub timestamp: DateTime<FixedOffset>,
+    /// In seconds
+    pub response_time: f64,
+    pub method: Option<String>,
+    /// Without query params
+    pub path: Option<String>,
+    pub status: u16,
+    /// The backend address, with its port, only known for gorouter logs
+    pub backend: Option<String>,
+    /// Only known for gorouter logs
+    pub app_index: Option<u16>,
+    /// The raw log line, only kept for `--slowest`
+    pub line: Option<String>,
+}
+
+// slowest first, ties go to the earlier request so the order never depends
+// on the order the requests were counted in
+fn slower(a: &Outlier, b: &Outlier) -> Ordering {
+    a.response_time
+        .total_cmp(&b.response_time)
+        .then_with(|| b.timestamp.cmp(&a.timestamp))
+        .then_with(|| b.path.cmp(&a.path))
+}
+
+// response times are compared at the millisecond precision of the percentiles
+fn millis(seconds: f64) -> u64 {
+    (seconds.max(0.0) * 1000.0).round() as u64
+}
+
+/// The slowest requests seen so far, up to a fixed number of them
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Outliers {
+    capacity: usize,
+    // whether the raw lines of the requests are kept too
+    lines: bool,
+    requests: Vec<Outlier>,
+    // the fastest kept response time once full, faster requests aren't kept
+    floor: Option<f64>,
+}
+
+impl Outliers {
+    pub fn new(capacity: usize) -> Outliers {
+        Outliers {
+            capacity,
+            lines: false,
+            requests: Vec::new(),
+            floor: None,
+        }
+    }
+
+    /// Keeps the raw log lines of the requests too, for `--slowest`
+    pub fn with_lines(capacity: usize) -> Outliers {
+        Outliers {
+            lines: true,
+            ..Outliers::new(capacity)
+        }
+    }
+
+    // the same capacity, without any requests
+    pub(crate) fn cleared(&self) -> Outliers {
+        Outliers {
+            lines: self.lines,
+            ..Outliers::new(self.capacity)
+        }
+    }
+
+    // the kept request a slower one replaces once the outliers are full
+    fn fastest(&self) -> Option<(usize, &Outlier)> {
+        self.requests
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| slower(a, b))
+    }
+
+    // whether a request taking `response_time` might be kept, checked before
+    // copying its fields
+    fn admits(&self, response_time: f64) -> bool {
+        self.requests.len() < self.capacity
+            || self.floor.is_some_and(|floor| response_time >= floor)
+    }
+
+    fn insert(&mut self, outlier: Outlier) {
+        if self.requests.len() < self.capacity {
+            self.requests.push(outlier);
+        } else if let Some((i, fastest)) = self.fastest() {
+            if slower(&outlier, fastest) == Ordering::Greater {
+                self.requests[i] = outlier;
+            }
+        }
+        if self.requests.len() == self.capacity {
+            self.floor = self.fastest().map(|(_, fastest)| fastest.response_time);
+        }
+    }
+
+    /// Keeps `log_entry`, read from `line`, if it's one of the slowest
+    /// requests so far. Only the gorouter and cloud controller formats record
+    /// response times.
+    pub fn record(&mut self, log_entry: &LogEntry, line: &str) {
+        let (timestamp, request, status, response_time, backend, app_index) = match log_entry {
+            LogEntry::GorouterLog(log) => (
+                log.timestamp,
+                &log.request,
+                log.status_code,
+                log.response_time,
+                log.backend_addr.map(|addr| match log.backend_port {
+                    Some(port) => std::net::SocketAddr::new(addr, port).to_string(),
+                    None => addr.to_string(),
+                }),
+                log.app_index,
+            ),
+            LogEntry::CloudControllerLog(log) => (
+                log.timestamp,
+                &log.request,
+                log.status_code,
+                log.response_time,
+                None,
+                None,
+            ),
+            _ => return,
+        };
+        let Some(response_time) = response_time.filter(|t| self.admits(*t)) else {
+            return;
+        };
+
+        let (method, path) = match request {
+            RequestResult::Valid(req) => (
+                Some(req.method().to_string()),
+                Some(req.uri().path().to_string()),
+            ),
+            RequestResult::InvalidPath(path, _err) => (None, Some(path.to_string())),
+            RequestResult::InvalidRequest(_) => (None, None),
+        };
+        self.insert(Outlier {
+            timestamp,
+            response_time,
+            method,
+            path,
+            status: status.as_u16(),
+            backend,
+            app_index,
+            line: self.lines.then(|| line.to_string()),
+        });
+    }
+
+    /// Keeps the slowest of the requests kept here and by `other`
+    pub fn merge(&mut self, other: &Outliers) {
+        for outlier in &other.requests {
+            if self.admits(outlier.response_time) {
+                self.insert(outlier.clone());
+            }
+        }
+    }
+
+    /// The kept requests beyond the 99.9th percentile of `times`, the
+    /// response times of every request, slowest first. None without
+    /// response times.
+    pub fn report(&self, times: &TimeHistogram, max: usize) -> Option<OutlierReport> {
+        let threshold = times.percentile(PERCENTILE)?;
+        let mut requests: Vec<Outlier> = self
+            .requests
+            .iter()
+            .filter(|outlier| millis(outlier.response_time) > millis(threshold))
+            .cloned()
+            .collect();
+        requests.sort_by(|a, b| slower(b, a));
+        requests.truncate(max);
+        Some(OutlierReport {
+            threshold,
+            requests,
+        })
+    }
+
+    /// Every kept request, slowest first. None without response times.
+    pub fn slowest(&self) -> Option<SlowestReport> {
+        if self.requests.is_empty() {
+            return None;
+        }
+        let mut requests = self.requests.clone();
+        requests.sort_by(|a, b| slower(b, a));
+        Some(SlowestReport {
+            max: self.capacity,
+            requests,
+        })
+    }
+}
+
+/// The slowest requests, beyond the 99.9th percentile response time
+#[derive(Debug, Serialize)]
+pub struct OutlierReport {
+    /// The 99.9th percentile response time in seconds
+    pub threshold: f64,
+    pub requests: Vec<Outlier>,
+}
+
+impl OutlierReport {
+    /// The column headers of `Outlier::cells`
+    pub const HEADERS: [&'static str; 7] = [
+        "Timestamp",
+        "Response Time (s)",
+        "Method",
+        "Path",
+        "Status",
+        "Backend",
+        "App Index",
+    ];
+
+    pub fn title(&self) -> String {
+        format!(
+            "Response Time Outliers (beyond the p99.9 of {}s):",
+            self.threshold
+        )
+    }
+}
+
+impl Outlier {
+    /// The display value of each column, unknown values are `-`
+    pub fn cells(&self) -> Vec<String> {
+        let text = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        vec![
+            self.timestamp.to_string(),
+            self.response_time.to_string(),
+            text(self.method.clone()),
+            text(self.path.clone()),
+            self.status.to_string(),
+            text(self.backend.clone()),
+            text(self.app_index.map(|index| index.to_string())),
+        ]
+    }
+}
+
+/// The `--slowest` requests, whatever their percentile
+#[derive(Debug, Serialize)]
+pub struct SlowestReport {
+    #[serde(skip)]
+    pub max: usize,
+    pub requests: Vec<Outlier>,
+}
+
+impl SlowestReport {
+    /// The column headers of `Outlier::slowest_cells`
+    pub const HEADERS: [&'static str; 6] = [
+        "Timestamp",
+        "Response Time (s)",
+        "Path",
+        "Status",
+        "Backend",
+        "Line",
+    ];
+
+    pub fn title(&self) -> String {
+        format!("The '{}' Slowest Requests:", self.max)
+    }
+}
+
+impl Outlier {
+    /// The display value of each `SlowestReport` column, unknown values are `-`
+    pub fn slowest_cells(&self) -> Vec<String> {
+        let text = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+        vec![
+            self.timestamp.to_string(),
+            self.response_time.to_string(),
+            text(self.path.clone()),
+            self.status.to_string(),
+            text(self.backend.clone()),
+            text(self.line.clone()),
+        ]
+    }
+}
@@ -0,0 +1,530 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::extract::ExtractReport;
+use crate::outlier::{OutlierReport, SlowestReport};
+use crate::report::{Report, ReportSection};
+use crate::section::Section;
+use crate::slices::SliceReport;
+use crate::timeline::TimelineReport;
+use std::io::{self, Write};
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: left; }
+td.count { text-align: right; font-variant-numeric: tabular-nums; }
+td.sparkline { font-family: monospace; white-space: pre; }
+th { background: #f0f0f0; }
+svg text { font-size: 12px; font-family: sans-serif; }
+svg rect { fill: #4a7ebb; }";
+
+// the anchors of the matching and the other requests of a segment
+const SEGMENT_ANCHORS: [&str; 2] = ["segment-with", "segment-without"];
+
+const CHART_LABEL_WIDTH: usize = 280;
+const CHART_BAR_WIDTH: usize = 400;
+const CHART_ROW_HEIGHT: usize = 20;
+const CHART_MAX_LABEL: usize = 40;
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// only sections where relative size is the interesting part get a chart
+fn has_chart(section: Section) -> bool {
+    matches!(
+        section,
+        Section::ResponseCodes
+            | Section::RequestsNoQuery
+            | Section::ResponseTimes
+            | Section::GorouterTimes
+    )
+}
+
+fn write_chart<W: Write>(section: &ReportSection, out: &mut W) -> io::Result<()> {
+    let max = section.rows.iter().map(|row| row.count).max().unwrap_or(0);
+    if max == 0 {
+        return Ok(());
+    }
+
+    let height = section.rows.len() * CHART_ROW_HEIGHT;
+    writeln!(
+        out,
+        "<svg width=\"{}\" height=\"{}\" role=\"img\">",
+        CHART_LABEL_WIDTH + CHART_BAR_WIDTH + 80,
+        height
+    )?;
+    for (i, row) in section.rows.iter().enumerate() {
+        let y = i * CHART_ROW_HEIGHT;
+        let width = (row.count * CHART_BAR_WIDTH).div_ceil(max);
+        let label: String = if row.key.chars().count() > CHART_MAX_LABEL {
+            row.key
+                .chars()
+                .take(CHART_MAX_LABEL - 1)
+                .chain(std::iter::once('…'))
+                .collect()
+        } else {
+            row.key.clone()
+        };
+        writeln!(
+            out,
+            "<text x=\"0\" y=\"{}\">{}</text><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/><text x=\"{}\" y=\"{}\">{}</text>",
+            y + 14,
+            escape(&label),
+            CHART_LABEL_WIDTH,
+            y + 3,
+            width,
+            CHART_ROW_HEIGHT - 6,
+            CHART_LABEL_WIDTH + width + 5,
+            y + 14,
+            row.count
+        )?;
+    }
+    writeln!(out, "</svg>")
+}
+
+pub(super) fn write<W: Write>(report: &Report, out: &mut W) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Access Log Summary</title>")?;
+    writeln!(out, "<style>\n{STYLE}\n</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>Access Log Summary</h1>")?;
+
+    writeln!(out, "<table>")?;
+    writeln!(
+        out,
+        "<tr><th>Duration</th><td>{}</td></tr>",
+        escape(&report.duration_label())
+    )?;
+    if let Some(sparkline) = &report.sparkline {
+        writeln!(
+            out,
+            "<tr><th>Traffic</th><td class=\"sparkline\">{sparkline}</td></tr>"
+        )?;
+    }
+    writeln!(
+        out,
+        "<tr><th>Total Requests</th><td class=\"count\">{}</td></tr>",
+        report.total_requests
+    )?;
+    writeln!(
+        out,
+        "<tr><th>Total Errors</th><td class=\"count\">{}</td></tr>",
+        report.errors
+    )?;
+    if let Some(filtered) = report.filtered {
+        writeln!(
+            out,
+            "<tr><th>Total Filtered</th><td class=\"count\">{filtered}</td></tr>"
+        )?;
+    }
+    if let Some(coverage) = &report.coverage {
+        for (label, value) in coverage.items() {
+            writeln!(
+                out,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape(label),
+                escape(&value)
+            )?;
+        }
+    }
+    if let Some(unique) = &report.unique {
+        for (label, value) in unique.items() {
+            writeln!(
+                out,
+                "<tr><th>Unique {label}</th><td class=\"count\">{value}</td></tr>"
+            )?;
+        }
+    }
+    if let Some(concentration) = &report.concentration {
+        for (label, value) in concentration.items() {
+            writeln!(
+                out,
+                "<tr><th>Concentration of {label}</th><td>{}</td></tr>",
+                escape(&value)
+            )?;
+        }
+    }
+    if let Some(statistics) = &report.statistics {
+        for (label, value) in statistics.items() {
+            writeln!(out, "<tr><th>{label}</th><td>{}</td></tr>", escape(&value))?;
+        }
+    }
+    writeln!(out, "</table>")?;
+
+    writeln!(out, "<nav>")?;
+    writeln!(out, "<ul>")?;
+    if report.headline.is_some() {
+        writeln!(out, "<li><a href=\"#headline\">Headline</a></li>")?;
+    }
+    if let Some(segment) = &report.segment {
+        for ((title, _), anchor) in segment.tables().iter().zip(SEGMENT_ANCHORS) {
+            writeln!(
+                out,
+                "<li><a href=\"#{anchor}\">{}</a></li>",
+                escape(title.trim_end_matches(':'))
+            )?;
+        }
+    }
+    for section in &report.sections {
+        writeln!(
+            out,
+            "<li><a href=\"#{}\">{}</a></li>",
+            section.section,
+            escape(section.title.trim_end_matches(':'))
+        )?;
+    }
+    if report.outliers.is_some() {
+        writeln!(
+            out,
+            "<li><a href=\"#outliers\">Response Time Outliers</a></li>"
+        )?;
+    }
+    if let Some(slowest) = &report.slowest {
+        writeln!(
+            out,
+            "<li><a href=\"#slowest\">{}</a></li>",
+            escape(slowest.title().trim_end_matches(':'))
+        )?;
+    }
+    if let Some(slices) = &report.slices {
+        writeln!(
+            out,
+            "<li><a href=\"#slices\">{}</a></li>",
+            escape(slices.title().trim_end_matches(':'))
+        )?;
+    }
+    for (i, timeline) in report.timelines.iter().enumerate() {
+        writeln!(
+            out,
+            "<li><a href=\"#timeline-{}\">{}</a></li>",
+            i + 1,
+            escape(timeline.title().trim_end_matches(':'))
+        )?;
+    }
+    for (i, extract) in report.extracts.iter().enumerate() {
+        writeln!(
+            out,
+            "<li><a href=\"#extract-{}\">{}</a></li>",
+            i + 1,
+            escape(extract.title().trim_end_matches(':'))
+        )?;
+    }
+    for breakdown in &report.breakdowns {
+        writeln!(
+            out,
+            "<li><a href=\"#latency-by-{}\">{}</a></li>",
+            breakdown.key,
+            escape(breakdown.title().trim_end_matches(':'))
+        )?;
+    }
+    for deviation in &report.deviations {
+        writeln!(
+            out,
+            "<li><a href=\"#deviation-{}\">Deviation of {}</a></li>",
+            deviation.section, deviation.section
+        )?;
+    }
+    for (i, drill) in report.drills.iter().enumerate() {
+        writeln!(
+            out,
+            "<li><a href=\"#drill-{}\">Drill: {}</a></li>",
+            i + 1,
+            escape(&drill.key)
+        )?;
+    }
+    writeln!(out, "</ul>")?;
+    writeln!(out, "</nav>")?;
+
+    if let Some(headline) = &report.headline {
+        writeln!(out, "<h2 id=\"headline\">Headline</h2>")?;
+        writeln!(out, "<table>")?;
+        for (label, value) in headline.items() {
+            writeln!(
+                out,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape(label),
+                escape(&value)
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    if let Some(segment) = &report.segment {
+        for ((title, side), anchor) in segment.tables().iter().zip(SEGMENT_ANCHORS) {
+            writeln!(
+                out,
+                "<h2 id=\"{anchor}\">{}</h2>",
+                escape(title.trim_end_matches(':'))
+            )?;
+            writeln!(out, "<table>")?;
+            writeln!(out, "<tr><th>Path</th><th>Count</th></tr>")?;
+            for row in &side.paths {
+                writeln!(
+                    out,
+                    "<tr><td>{}</td><td class=\"count\">{}</td></tr>",
+                    escape(&row.key),
+                    row.count
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+    }
+
+    for section in &report.sections {
+        writeln!(
+            out,
+            "<h2 id=\"{}\">{}</h2>",
+            section.section,
+            escape(section.title.trim_end_matches(':'))
+        )?;
+
+        if has_chart(section.section) {
+            write_chart(section, out)?;
+        }
+
+        let columns = section.columns();
+        writeln!(out, "<table>")?;
+        write!(out, "<tr>")?;
+        for column in &columns {
+            write!(out, "<th>{}</th>", escape(&column.title))?;
+        }
+        writeln!(out, "</tr>")?;
+        for row in &section.rows {
+            write!(out, "<tr>")?;
+            for column in &columns {
+                let class = if column.is_numeric() {
+                    " class=\"count\""
+                } else {
+                    ""
+                };
+                write!(out, "<td{class}>{}</td>", escape(&column.text(row)))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    if let Some(outliers) = &report.outliers {
+        writeln!(
+            out,
+            "<h2 id=\"outliers\">{}</h2>",
+            escape(outliers.title().trim_end_matches(':'))
+        )?;
+        writeln!(out, "<table>")?;
+        write!(out, "<tr>")?;
+        for header in OutlierReport::HEADERS {
+            write!(out, "<th>{}</th>", escape(header))?;
+        }
+        writeln!(out, "</tr>")?;
+        for outlier in &outliers.requests {
+            write!(out, "<tr>")?;
+            for cell in outlier.cells() {
+                write!(out, "<td>{}</td>", escape(&cell))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    if let Some(slowest) = &report.slowest {
+        writeln!(
+            out,
+            "<h2 id=\"slowest\">{}</h2>",
+            escape(slowest.title().trim_end_matches(':'))
+        )?;
+        writeln!(out, "<table>")?;
+        write!(out, "<tr>")?;
+        for header in SlowestReport::HEADERS {
+            write!(out, "<th>{}</th>", escape(header))?;
+        }
+        writeln!(out, "</tr>")?;
+        for request in &slowest.requests {
+            write!(out, "<tr>")?;
+            for cell in request.slowest_cells() {
+                write!(out, "<td>{}</td>", escape(&cell))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    if let Some(slices) = &report.slices {
+        writeln!(
+            out,
+            "<h2 id=\"slices\">{}</h2>",
+            escape(slices.title().trim_end_matches(':'))
+        )?;
+        writeln!(out, "<table>")?;
+        write!(out, "<tr>")?;
+        for header in SliceReport::HEADERS {
+            write!(out, "<th>{}</th>", escape(header))?;
+        }
+        writeln!(out, "</tr>")?;
+        for slice in &slices.slices {
+            let cells = slice.cells();
+            write!(out, "<tr><td>{}</td>", escape(&cells[0]))?;
+            for cell in &cells[1..4] {
+                write!(out, "<td class=\"count\">{}</td>", escape(cell))?;
+            }
+            writeln!(out, "<td>{}</td></tr>", escape(&cells[4]))?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    for (i, timeline) in report.timelines.iter().enumerate() {
+        writeln!(
+            out,
+            "<h2 id=\"timeline-{}\">{}</h2>",
+            i + 1,
+            escape(timeline.title().trim_end_matches(':'))
+        )?;
+        writeln!(out, "<table>")?;
+        write!(out, "<tr>")?;
+        for header in TimelineReport::HEADERS {
+            write!(out, "<th>{}</th>", escape(header))?;
+        }
+        writeln!(out, "</tr>")?;
+        for point in &timeline.points {
+            let cells = point.cells();
+            write!(out, "<tr><td>{}</td>", escape(&cells[0]))?;
+            for cell in &cells[1..] {
+                write!(out, "<td class=\"count\">{}</td>", escape(cell))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    for (i, extract) in report.extracts.iter().enumerate() {
+        writeln!(
+            out,
+            "<h2 id=\"extract-{}\">{}</h2>",
+            i + 1,
+            escape(extract.title().trim_end_matches(':'))
+        )?;
+        writeln!(out, "<table>")?;
+        write!(out, "<tr>")?;
+        for header in ExtractReport::HEADERS {
+            write!(out, "<th>{}</th>", escape(header))?;
+        }
+        writeln!(out, "</tr>")?;
+        for value in &extract.values {
+            let cells = value.cells();
+            write!(out, "<tr><td>{}</td>", escape(&cells[0]))?;
+            for cell in &cells[1..] {
+                write!(out, "<td class=\"count\">{}</td>", escape(cell))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    for breakdown in &report.breakdowns {
+        writeln!(
+            out,
+            "<h2 id=\"latency-by-{}\">{}</h2>",
+            breakdown.key,
+            escape(breakdown.title().trim_end_matches(':'))
+        )?;
+        writeln!(out, "<table>")?;
+        write!(out, "<tr>")?;
+        for header in breakdown.headers() {
+            write!(out, "<th>{}</th>", escape(header))?;
+        }
+        writeln!(out, "</tr>")?;
+        for row in &breakdown.rows {
+            let cells = row.cells();
+            write!(out, "<tr><td>{}</td>", escape(&cells[0]))?;
+            for cell in &cells[1..] {
+                write!(out, "<td class=\"count\">{}</td>", escape(cell))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    for deviation in &report.deviations {
+        writeln!(
+            out,
+            "<h2 id=\"deviation-{}\">{}</h2>",
+            deviation.section,
+            escape(deviation.title().trim_end_matches(':'))
+        )?;
+        writeln!(out, "<table>")?;
+        write!(out, "<tr>")?;
+        for header in deviation.headers() {
+            write!(out, "<th>{}</th>", escape(header))?;
+        }
+        writeln!(out, "</tr>")?;
+        for key in &deviation.keys {
+            let cells = key.cells();
+            write!(out, "<tr><td>{}</td>", escape(&cells[0]))?;
+            for cell in &cells[1..] {
+                write!(out, "<td class=\"count\">{}</td>", escape(cell))?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    for (i, drill) in report.drills.iter().enumerate() {
+        writeln!(
+            out,
+            "<h2 id=\"drill-{}\">Drill: {}</h2>",
+            i + 1,
+            escape(&drill.key)
+        )?;
+        writeln!(out, "<table>")?;
+        for (label, value) in drill.items() {
+            writeln!(
+                out,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape(label),
+                escape(&value)
+            )?;
+        }
+        writeln!(out, "</table>")?;
+
+        for table in &drill.tables {
+            writeln!(out, "<h3>{}</h3>", escape(&table.title))?;
+            writeln!(out, "<table>")?;
+            writeln!(
+                out,
+                "<tr><th>{}</th><th>Count</th></tr>",
+                escape(table.key_label)
+            )?;
+            for row in &table.rows {
+                writeln!(
+                    out,
+                    "<tr><td>{}</td><td class=\"count\">{}</td></tr>",
+                    escape(&row.key),
+                    row.count
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+    }
+
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
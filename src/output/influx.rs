@@ -0,0 +1,169 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::report::{Report, ReportSection};
+use std::io::{self, Write};
+
+// tag values can't be empty or contain new lines, commas, equal signs and
+// spaces have to be escaped
+fn escape(value: &str) -> String {
+    if value.is_empty() {
+        return "<empty>".into();
+    }
+
+    value
+        .replace('\\', "\\\\")
+        .replace(['\r', '\n'], " ")
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+// the tags of the report as `,key=value` pairs, leaving out those named like a
+// tag of the measurement
+fn tag_set(report: &Report, reserved: &[&str]) -> String {
+    report
+        .tags
+        .iter()
+        .filter(|(key, _)| !reserved.contains(&key.as_str()))
+        .map(|(key, value)| format!(",{key}={}", escape(value)))
+        .collect()
+}
+
+fn write_section<W: Write>(
+    section: &ReportSection,
+    report_tags: &str,
+    end: &str,
+    out: &mut W,
+) -> io::Result<()> {
+    for row in &section.rows {
+        let tags = match (row.from, row.to) {
+            (Some(from), Some(to)) => format!("from={from},to={to}"),
+            _ => format!("key={}", escape(&row.key)),
+        };
+        write!(
+            out,
+            "top_logs_section,section={}{report_tags},{tags} count={}i",
+            section.section, row.count
+        )?;
+        if let Some(p50) = row.p50 {
+            write!(out, ",p50={p50}")?;
+        }
+        if let Some(p95) = row.p95 {
+            write!(out, ",p95={p95}")?;
+        }
+        if let Some(percent) = row.percent {
+            write!(out, ",percent={percent}")?;
+        }
+        if let Some(low) = row.low {
+            write!(out, ",low={low}")?;
+        }
+        if let Some(high) = row.high {
+            write!(out, ",high={high}")?;
+        }
+        if let Some(server_errors) = row.server_errors {
+            write!(out, ",server_errors={server_errors}i")?;
+        }
+        if let Some(client_errors) = row.client_errors {
+            write!(out, ",client_errors={client_errors}i")?;
+        }
+        if let Some(megabytes) = row.megabytes {
+            write!(out, ",megabytes={megabytes}")?;
+        }
+        writeln!(out, "{end}")?;
+    }
+    Ok(())
+}
+
+pub(super) fn write<W: Write>(report: &Report, out: &mut W) -> io::Result<()> {
+    // every point is stamped with the newest log entry, the range is kept in
+    // fields. Without any requests there's no range, points get the time they're written.
+    let end = match &report.duration {
+        Some(duration) => format!(
+            ",log_start={}i,log_end={}i {}",
+            duration.start.timestamp(),
+            duration.end.timestamp(),
+            duration.end.timestamp_nanos_opt().unwrap_or_default()
+        ),
+        None => String::new(),
+    };
+
+    let tags = tag_set(report, &[]);
+    write!(
+        out,
+        "top_logs{tags} total_requests={}i,errors={}i",
+        report.total_requests, report.errors
+    )?;
+    if let Some(filtered) = report.filtered {
+        write!(out, ",filtered={filtered}i")?;
+    }
+    if let Some(unique) = &report.unique {
+        write!(
+            out,
+            ",unique_client_ips={}i,unique_paths={}i,unique_user_agents={}i",
+            unique.client_ips.count, unique.paths.count, unique.user_agents.count
+        )?;
+    }
+    if let Some(concentration) = &report.concentration {
+        let fields = [
+            ("client_ips", &concentration.client_ips),
+            ("paths", &concentration.paths),
+        ];
+        for (field, share) in fields {
+            if let Some(share) = share {
+                write!(
+                    out,
+                    ",{field}_top_1_percent={},{field}_top_10_percent={},{field}_gini={}",
+                    share.top_1_percent, share.top_10_percent, share.gini
+                )?;
+            }
+        }
+    }
+    if let Some(statistics) = &report.statistics {
+        for (field, stats) in statistics.times() {
+            write!(
+                out,
+                ",{field}_mean={},{field}_min={},{field}_max={},{field}_stddev={}",
+                stats.mean, stats.min, stats.max, stats.stddev
+            )?;
+        }
+        write!(out, ",bytes_sent={}i", statistics.bytes_sent)?;
+        if let Some(bytes) = statistics.bytes_received {
+            write!(out, ",bytes_received={bytes}i")?;
+        }
+    }
+    writeln!(out, "{end}")?;
+
+    if let Some(headline) = &report.headline {
+        write!(
+            out,
+            "top_logs_headline{tags} error_rate={},server_errors={}i",
+            headline.error_rate, headline.server_errors
+        )?;
+        if let Some(p95) = headline.p95_response_time {
+            write!(out, ",p95_response_time={p95}")?;
+        }
+        writeln!(out, "{end}")?;
+    }
+
+    for section in &report.sections {
+        write_section(
+            section,
+            &tag_set(report, &["section", "key", "from", "to"]),
+            &end,
+            out,
+        )?;
+    }
+
+    Ok(())
+}
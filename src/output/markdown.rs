@@ -0,0 +1,320 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::extract::ExtractReport;
+use crate::outlier::{OutlierReport, SlowestReport};
+use crate::report::Report;
+use crate::slices::SliceReport;
+use crate::timeline::TimelineReport;
+use std::io::{self, Write};
+
+// the anchors of the matching and the other requests of a segment
+const SEGMENT_ANCHORS: [&str; 2] = ["segment-with", "segment-without"];
+
+// keeps user controlled values, like paths and user agents, from breaking the table
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace('\n', " ")
+}
+
+pub(super) fn write<W: Write>(report: &Report, out: &mut W) -> io::Result<()> {
+    writeln!(out, "# Access Log Summary")?;
+    writeln!(out)?;
+    writeln!(out, "- Duration: {}", report.duration_label())?;
+    if let Some(sparkline) = &report.sparkline {
+        writeln!(out, "- Traffic: `{sparkline}`")?;
+    }
+    writeln!(out, "- Total Requests: {}", report.total_requests)?;
+    writeln!(out, "- Total Errors: {}", report.errors)?;
+    if let Some(filtered) = report.filtered {
+        writeln!(out, "- Total Filtered: {filtered}")?;
+    }
+    if let Some(coverage) = &report.coverage {
+        for (label, value) in coverage.items() {
+            writeln!(out, "- {label}: {value}")?;
+        }
+    }
+    if let Some(unique) = &report.unique {
+        for (label, value) in unique.items() {
+            writeln!(out, "- Unique {label}: {value}")?;
+        }
+    }
+    if let Some(concentration) = &report.concentration {
+        for (label, value) in concentration.items() {
+            writeln!(out, "- Concentration of {label}: {value}")?;
+        }
+    }
+    if let Some(statistics) = &report.statistics {
+        for (label, value) in statistics.items() {
+            writeln!(out, "- {label}: {value}")?;
+        }
+    }
+
+    writeln!(out)?;
+    writeln!(out, "## Contents")?;
+    writeln!(out)?;
+    if report.headline.is_some() {
+        writeln!(out, "- [Headline](#headline)")?;
+    }
+    if let Some(segment) = &report.segment {
+        for ((title, _), anchor) in segment.tables().iter().zip(SEGMENT_ANCHORS) {
+            writeln!(
+                out,
+                "- [{}](#{anchor})",
+                escape(title.trim_end_matches(':'))
+            )?;
+        }
+    }
+    for section in &report.sections {
+        writeln!(
+            out,
+            "- [{}](#{})",
+            section.title.trim_end_matches(':'),
+            section.section
+        )?;
+    }
+    if report.outliers.is_some() {
+        writeln!(out, "- [Response Time Outliers](#outliers)")?;
+    }
+    if let Some(slowest) = &report.slowest {
+        writeln!(
+            out,
+            "- [{}](#slowest)",
+            slowest.title().trim_end_matches(':')
+        )?;
+    }
+    if let Some(slices) = &report.slices {
+        writeln!(out, "- [{}](#slices)", slices.title().trim_end_matches(':'))?;
+    }
+    for (i, timeline) in report.timelines.iter().enumerate() {
+        writeln!(
+            out,
+            "- [{}](#timeline-{})",
+            escape(timeline.title().trim_end_matches(':')),
+            i + 1
+        )?;
+    }
+    for (i, extract) in report.extracts.iter().enumerate() {
+        writeln!(
+            out,
+            "- [{}](#extract-{})",
+            escape(extract.title().trim_end_matches(':')),
+            i + 1
+        )?;
+    }
+    for breakdown in &report.breakdowns {
+        writeln!(
+            out,
+            "- [{}](#latency-by-{})",
+            escape(breakdown.title().trim_end_matches(':')),
+            breakdown.key
+        )?;
+    }
+    for deviation in &report.deviations {
+        writeln!(
+            out,
+            "- [Deviation of {}](#deviation-{})",
+            deviation.section, deviation.section
+        )?;
+    }
+    for (i, drill) in report.drills.iter().enumerate() {
+        writeln!(out, "- [Drill: {}](#drill-{})", escape(&drill.key), i + 1)?;
+    }
+
+    // explicit anchors, the generated ones would change with the titles
+    if let Some(headline) = &report.headline {
+        writeln!(out)?;
+        writeln!(out, "<a id=\"headline\"></a>")?;
+        writeln!(out)?;
+        writeln!(out, "## Headline")?;
+        writeln!(out)?;
+        for (label, value) in headline.items() {
+            writeln!(out, "- **{label}**: {}", escape(&value))?;
+        }
+    }
+
+    if let Some(segment) = &report.segment {
+        for ((title, side), anchor) in segment.tables().iter().zip(SEGMENT_ANCHORS) {
+            writeln!(out)?;
+            writeln!(out, "<a id=\"{anchor}\"></a>")?;
+            writeln!(out)?;
+            writeln!(out, "## {}", escape(title.trim_end_matches(':')))?;
+            writeln!(out)?;
+            writeln!(out, "| Path | Count |")?;
+            writeln!(out, "| --- | ---: |")?;
+            for row in &side.paths {
+                writeln!(out, "| {} | {} |", escape(&row.key), row.count)?;
+            }
+        }
+    }
+
+    for section in &report.sections {
+        writeln!(out)?;
+        writeln!(out, "<a id=\"{}\"></a>", section.section)?;
+        writeln!(out)?;
+        writeln!(out, "## {}", section.title.trim_end_matches(':'))?;
+        writeln!(out)?;
+        let columns = section.columns();
+        write!(out, "|")?;
+        for column in &columns {
+            write!(out, " {} |", escape(&column.title))?;
+        }
+        writeln!(out)?;
+        write!(out, "|")?;
+        for column in &columns {
+            write!(
+                out,
+                "{}",
+                if column.is_numeric() {
+                    " ---: |"
+                } else {
+                    " --- |"
+                }
+            )?;
+        }
+        writeln!(out)?;
+        for row in &section.rows {
+            write!(out, "|")?;
+            for column in &columns {
+                write!(out, " {} |", escape(&column.text(row)))?;
+            }
+            writeln!(out)?;
+        }
+    }
+
+    if let Some(outliers) = &report.outliers {
+        writeln!(out)?;
+        writeln!(out, "<a id=\"outliers\"></a>")?;
+        writeln!(out)?;
+        writeln!(out, "## {}", outliers.title().trim_end_matches(':'))?;
+        writeln!(out)?;
+        writeln!(out, "| {} |", OutlierReport::HEADERS.join(" | "))?;
+        writeln!(out, "| --- | ---: | --- | --- | ---: | --- | ---: |")?;
+        for outlier in &outliers.requests {
+            let cells: Vec<String> = outlier.cells().iter().map(|c| escape(c)).collect();
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+    }
+
+    if let Some(slowest) = &report.slowest {
+        writeln!(out)?;
+        writeln!(out, "<a id=\"slowest\"></a>")?;
+        writeln!(out)?;
+        writeln!(out, "## {}", slowest.title().trim_end_matches(':'))?;
+        writeln!(out)?;
+        writeln!(out, "| {} |", SlowestReport::HEADERS.join(" | "))?;
+        writeln!(out, "| --- | ---: | --- | ---: | --- | --- |")?;
+        for request in &slowest.requests {
+            let cells: Vec<String> = request.slowest_cells().iter().map(|c| escape(c)).collect();
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+    }
+
+    if let Some(slices) = &report.slices {
+        writeln!(out)?;
+        writeln!(out, "<a id=\"slices\"></a>")?;
+        writeln!(out)?;
+        writeln!(out, "## {}", slices.title().trim_end_matches(':'))?;
+        writeln!(out)?;
+        writeln!(out, "| {} |", SliceReport::HEADERS.join(" | "))?;
+        writeln!(out, "| --- | ---: | ---: | ---: | --- |")?;
+        for slice in &slices.slices {
+            let cells: Vec<String> = slice.cells().iter().map(|c| escape(c)).collect();
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+    }
+
+    for (i, timeline) in report.timelines.iter().enumerate() {
+        writeln!(out)?;
+        writeln!(out, "<a id=\"timeline-{}\"></a>", i + 1)?;
+        writeln!(out)?;
+        writeln!(out, "## {}", escape(timeline.title().trim_end_matches(':')))?;
+        writeln!(out)?;
+        writeln!(out, "| {} |", TimelineReport::HEADERS.join(" | "))?;
+        writeln!(out, "| --- | ---: | ---: |")?;
+        for point in &timeline.points {
+            writeln!(out, "| {} |", point.cells().join(" | "))?;
+        }
+    }
+
+    for (i, extract) in report.extracts.iter().enumerate() {
+        writeln!(out)?;
+        writeln!(out, "<a id=\"extract-{}\"></a>", i + 1)?;
+        writeln!(out)?;
+        writeln!(out, "## {}", escape(extract.title().trim_end_matches(':')))?;
+        writeln!(out)?;
+        writeln!(out, "| {} |", ExtractReport::HEADERS.join(" | "))?;
+        writeln!(out, "| --- | ---: | ---: |")?;
+        for value in &extract.values {
+            let cells: Vec<String> = value.cells().iter().map(|cell| escape(cell)).collect();
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+    }
+
+    for breakdown in &report.breakdowns {
+        writeln!(out)?;
+        writeln!(out, "<a id=\"latency-by-{}\"></a>", breakdown.key)?;
+        writeln!(out)?;
+        writeln!(
+            out,
+            "## {}",
+            escape(breakdown.title().trim_end_matches(':'))
+        )?;
+        writeln!(out)?;
+        writeln!(out, "| {} |", breakdown.headers().join(" | "))?;
+        writeln!(out, "| --- | ---: | ---: | ---: | ---: |")?;
+        for row in &breakdown.rows {
+            let cells: Vec<String> = row.cells().iter().map(|cell| escape(cell)).collect();
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+    }
+
+    for deviation in &report.deviations {
+        writeln!(out)?;
+        writeln!(out, "<a id=\"deviation-{}\"></a>", deviation.section)?;
+        writeln!(out)?;
+        writeln!(out, "## {}", deviation.title().trim_end_matches(':'))?;
+        writeln!(out)?;
+        writeln!(out, "| {} |", deviation.headers().join(" | "))?;
+        writeln!(out, "| --- | ---: | ---: | ---: |")?;
+        for key in &deviation.keys {
+            let cells: Vec<String> = key.cells().iter().map(|c| escape(c)).collect();
+            writeln!(out, "| {} |", cells.join(" | "))?;
+        }
+    }
+
+    for (i, drill) in report.drills.iter().enumerate() {
+        writeln!(out)?;
+        writeln!(out, "<a id=\"drill-{}\"></a>", i + 1)?;
+        writeln!(out)?;
+        writeln!(out, "## Drill: {}", escape(&drill.key))?;
+        writeln!(out)?;
+        for (label, value) in drill.items() {
+            writeln!(out, "- **{label}**: {value}")?;
+        }
+        for table in &drill.tables {
+            writeln!(out)?;
+            writeln!(out, "### {}", table.title)?;
+            writeln!(out)?;
+            writeln!(out, "| {} | Count |", table.key_label)?;
+            writeln!(out, "| --- | ---: |")?;
+            for row in &table.rows {
+                writeln!(out, "| {} | {} |", escape(&row.key), row.count)?;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::report::Report;
+use anyhow::Result;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+mod html;
+mod influx;
+mod markdown;
+mod parquet;
+mod prometheus;
+mod table;
+mod template;
+
+pub use template::write_template;
+
+/// The formats a `Report` can be written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+    Markdown,
+    Html,
+    Prometheus,
+    Influx,
+    Parquet,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 8] = [
+        OutputFormat::Table,
+        OutputFormat::Json,
+        OutputFormat::Yaml,
+        OutputFormat::Markdown,
+        OutputFormat::Html,
+        OutputFormat::Prometheus,
+        OutputFormat::Influx,
+        OutputFormat::Parquet,
+    ];
+
+    /// Binary formats shouldn't be written to a terminal
+    pub fn is_binary(&self) -> bool {
+        matches!(self, OutputFormat::Parquet)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Html => "html",
+            OutputFormat::Prometheus => "prometheus",
+            OutputFormat::Influx => "influx",
+            OutputFormat::Parquet => "parquet",
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        OutputFormat::ALL
+            .iter()
+            .find(|format| format.name() == s)
+            .copied()
+            .ok_or_else(|| format!("invalid output format '{s}'"))
+    }
+}
+
+/// Writes `report` to `out` in the given format
+pub fn write_report<W: Write>(report: &Report, format: OutputFormat, out: &mut W) -> Result<()> {
+    match format {
+        OutputFormat::Table => table::write(report, out)?,
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, report)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Yaml => serde_yaml::to_writer(out, report)?,
+        OutputFormat::Markdown => markdown::write(report, out)?,
+        OutputFormat::Html => html::write(report, out)?,
+        OutputFormat::Prometheus => prometheus::write(report, out)?,
+        OutputFormat::Influx => influx::write(report, out)?,
+        OutputFormat::Parquet => parquet::write(report, out)?,
+    }
+    Ok(())
+}
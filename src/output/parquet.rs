@@ -0,0 +1,203 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::report::Report;
+use anyhow::Result;
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::format::KeyValue;
+use parquet::schema::parser::parse_message_type;
+use std::io::Write;
+use std::sync::Arc;
+
+// one row per table row, in long format so every section fits the same schema
+const SCHEMA: &str = "message top_logs {
+    REQUIRED BYTE_ARRAY section (UTF8);
+    REQUIRED BYTE_ARRAY key (UTF8);
+    OPTIONAL INT64 from;
+    OPTIONAL INT64 to;
+    REQUIRED INT64 count;
+    OPTIONAL INT64 log_start (TIMESTAMP(MILLIS,true));
+    OPTIONAL INT64 log_end (TIMESTAMP(MILLIS,true));
+    OPTIONAL DOUBLE p50;
+    OPTIONAL DOUBLE p95;
+    OPTIONAL DOUBLE percent;
+    OPTIONAL DOUBLE low;
+    OPTIONAL DOUBLE high;
+    OPTIONAL INT64 server_errors;
+    OPTIONAL INT64 client_errors;
+    OPTIONAL DOUBLE megabytes;
+}";
+
+// values and definition levels for a nullable column
+fn optional<T>(values: impl Iterator<Item = Option<T>>) -> (Vec<T>, Vec<i16>) {
+    let mut present = Vec::new();
+    let mut levels = Vec::new();
+    for value in values {
+        match value {
+            Some(value) => {
+                present.push(value);
+                levels.push(1);
+            }
+            None => levels.push(0),
+        }
+    }
+    (present, levels)
+}
+
+pub(super) fn write<W: Write>(report: &Report, out: &mut W) -> Result<()> {
+    let rows: Vec<_> = report
+        .sections
+        .iter()
+        .flat_map(|section| section.rows.iter().map(move |row| (section.section, row)))
+        .collect();
+
+    let mut metadata = vec![
+        KeyValue::new("total_requests".into(), report.total_requests.to_string()),
+        KeyValue::new("parse_errors".into(), report.errors.to_string()),
+    ];
+    if let Some(filtered) = report.filtered {
+        metadata.push(KeyValue::new("filtered".into(), filtered.to_string()));
+    }
+    if let Some(unique) = &report.unique {
+        for (key, cardinality) in [
+            ("unique_client_ips", &unique.client_ips),
+            ("unique_paths", &unique.paths),
+            ("unique_user_agents", &unique.user_agents),
+        ] {
+            metadata.push(KeyValue::new(key.into(), cardinality.count.to_string()));
+        }
+    }
+    if let Some(concentration) = &report.concentration {
+        let fields = [
+            ("client_ips", &concentration.client_ips),
+            ("paths", &concentration.paths),
+        ];
+        for (field, share) in fields {
+            if let Some(share) = share {
+                for (key, value) in [
+                    ("top_1_percent", share.top_1_percent),
+                    ("top_10_percent", share.top_10_percent),
+                    ("gini", share.gini),
+                ] {
+                    metadata.push(KeyValue::new(format!("{field}_{key}"), value.to_string()));
+                }
+            }
+        }
+    }
+    if let Some(statistics) = &report.statistics {
+        for (field, stats) in statistics.times() {
+            for (key, value) in [
+                ("mean", stats.mean),
+                ("min", stats.min),
+                ("max", stats.max),
+                ("stddev", stats.stddev),
+            ] {
+                metadata.push(KeyValue::new(format!("{field}_{key}"), value.to_string()));
+            }
+        }
+        metadata.push(KeyValue::new(
+            "bytes_sent".into(),
+            statistics.bytes_sent.to_string(),
+        ));
+        if let Some(bytes) = statistics.bytes_received {
+            metadata.push(KeyValue::new("bytes_received".into(), bytes.to_string()));
+        }
+    }
+    for (key, value) in &report.tags {
+        metadata.push(KeyValue::new(format!("tag.{key}"), value.clone()));
+    }
+
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_key_value_metadata(Some(metadata))
+        .build();
+
+    // the writer needs to own a `Send` sink, so the file is built in memory
+    let mut buf = Vec::new();
+    let mut writer = SerializedFileWriter::new(
+        &mut buf,
+        Arc::new(parse_message_type(SCHEMA)?),
+        Arc::new(props),
+    )?;
+
+    // there's no time range without any requests
+    let log_start = report
+        .duration
+        .as_ref()
+        .map(|duration| duration.start.timestamp_millis());
+    let log_end = report
+        .duration
+        .as_ref()
+        .map(|duration| duration.end.timestamp_millis());
+
+    let mut row_group = writer.next_row_group()?;
+    let mut column = 0;
+    while let Some(mut writer) = row_group.next_column()? {
+        match column {
+            0 | 1 => {
+                let values: Vec<ByteArray> = rows
+                    .iter()
+                    .map(|(section, row)| match column {
+                        0 => ByteArray::from(section.name()),
+                        _ => ByteArray::from(row.key.as_str()),
+                    })
+                    .collect();
+                writer
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, None, None)?;
+            }
+            2 | 3 | 5 | 6 | 12 | 13 => {
+                let (values, levels) = optional(rows.iter().map(|(_, row)| match column {
+                    2 => row.from.map(|v| v as i64),
+                    3 => row.to.map(|v| v as i64),
+                    5 => log_start,
+                    6 => log_end,
+                    12 => row.server_errors.map(|v| v as i64),
+                    _ => row.client_errors.map(|v| v as i64),
+                }));
+                writer
+                    .typed::<Int64Type>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+            7..=11 | 14 => {
+                let (values, levels) = optional(rows.iter().map(|(_, row)| match column {
+                    7 => row.p50,
+                    8 => row.p95,
+                    9 => row.percent,
+                    10 => row.low,
+                    11 => row.high,
+                    _ => row.megabytes,
+                }));
+                writer
+                    .typed::<DoubleType>()
+                    .write_batch(&values, Some(&levels), None)?;
+            }
+            _ => {
+                let values: Vec<i64> = rows.iter().map(|(_, row)| row.count as i64).collect();
+                writer
+                    .typed::<Int64Type>()
+                    .write_batch(&values, None, None)?;
+            }
+        }
+        writer.close()?;
+        column += 1;
+    }
+    row_group.close()?;
+    writer.close()?;
+
+    out.write_all(&buf)?;
+    Ok(())
+}
@@ -0,0 +1,570 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::report::{Report, ReportSection};
+use crate::section::Section;
+use std::io::{self, Write};
+
+// metric name, label name and help text for each section
+fn metric(section: Section) -> (&'static str, &'static str, &'static str) {
+    match section {
+        Section::ResponseCodes => (
+            "top_logs_requests_total",
+            "status",
+            "Requests by response status code",
+        ),
+        Section::RequestMethods => (
+            "top_logs_requests_by_method_total",
+            "method",
+            "Requests by request method",
+        ),
+        Section::RequestsNoQuery => (
+            "top_logs_requests_by_path_total",
+            "path",
+            "Top requests by path, without query params",
+        ),
+        Section::RequestsQuery => (
+            "top_logs_requests_by_path_and_query_total",
+            "path",
+            "Top requests by path, with query params",
+        ),
+        Section::PathClusters => (
+            "top_logs_requests_by_path_cluster_total",
+            "cluster",
+            "Top requests by cluster of near identical paths, without query params",
+        ),
+        Section::ServerErrorPaths => (
+            "top_logs_server_errors_by_path_total",
+            "path",
+            "Top requests with a 5xx response by path, without query params",
+        ),
+        Section::ClientErrorPaths => (
+            "top_logs_client_errors_by_path_total",
+            "path",
+            "Top requests with a 4xx response by path, without query params",
+        ),
+        Section::UserAgents => (
+            "top_logs_requests_by_user_agent_total",
+            "user_agent",
+            "Top requests by user agent",
+        ),
+        Section::Platforms => (
+            "top_logs_requests_by_platform_total",
+            "platform",
+            "Requests by device and operating system of the user agent",
+        ),
+        Section::Referrers => (
+            "top_logs_requests_by_referrer_total",
+            "referrer",
+            "Top requests by referrer",
+        ),
+        Section::ClientIps => (
+            "top_logs_requests_by_client_ip_total",
+            "client_ip",
+            "Top requests by client IP",
+        ),
+        Section::ErrorClientIps => (
+            "top_logs_errors_by_client_ip_total",
+            "client_ip",
+            "Top requests with a 4xx or 5xx response by client IP",
+        ),
+        Section::ClientRequests => (
+            "top_logs_client_ips_by_requests",
+            "requests",
+            "Client IPs by number of requests",
+        ),
+        Section::BackendIps => (
+            "top_logs_requests_by_backend_total",
+            "backend",
+            "Top requests by backend address",
+        ),
+        Section::XForwardedFors => (
+            "top_logs_requests_by_x_forwarded_for_total",
+            "x_forwarded_for",
+            "Top requests by X-Forwarded-For",
+        ),
+        Section::Hosts => (
+            "top_logs_requests_by_host_total",
+            "host",
+            "Top requests by destination host",
+        ),
+        Section::HostClients => (
+            "top_logs_distinct_client_ips_by_host",
+            "host",
+            "Top destination hosts by distinct client IPs",
+        ),
+        Section::AppIds => (
+            "top_logs_requests_by_app_id_total",
+            "app_id",
+            "Top requests by application UUID",
+        ),
+        Section::AppIndexes => (
+            "top_logs_requests_by_app_index_total",
+            "app_index",
+            "Top requests by application index",
+        ),
+        Section::ResponseTimes => (
+            "top_logs_requests_by_response_time_total",
+            "bucket",
+            "Requests by response time bucket",
+        ),
+        Section::GorouterTimes => (
+            "top_logs_requests_by_gorouter_time_total",
+            "bucket",
+            "Requests by gorouter time bucket",
+        ),
+        Section::RequestSizes => (
+            "top_logs_requests_by_request_size_total",
+            "bucket",
+            "Requests by request body size bucket in bytes",
+        ),
+        Section::UploadPaths => (
+            "top_logs_received_bytes_by_path_total",
+            "path",
+            "Top paths by bytes of request bodies",
+        ),
+        Section::XCfRouterErrors => (
+            "top_logs_requests_by_router_error_total",
+            "router_error",
+            "Top requests by x_cf_routererror",
+        ),
+        Section::ExtraFields => (
+            "top_logs_requests_by_extra_field_total",
+            "field",
+            "Top requests by value of extra gorouter fields, as name=value",
+        ),
+        Section::TimeSeries => (
+            "top_logs_requests_by_minute_total",
+            "minute",
+            "Requests per minute",
+        ),
+        Section::ErrorRates => (
+            "top_logs_requests_by_interval_total",
+            "interval",
+            "Requests per interval of the error rates",
+        ),
+        Section::Bandwidth => (
+            "top_logs_requests_by_bandwidth_interval_total",
+            "interval",
+            "Requests per interval of the bandwidth",
+        ),
+        Section::Forecast => (
+            "top_logs_forecast_requests",
+            "interval",
+            "Expected requests per interval after the end of the logs",
+        ),
+        Section::Funnel => (
+            "top_logs_funnel_sessions_total",
+            "step",
+            "Sessions reaching each funnel step",
+        ),
+        Section::EntryPages => (
+            "top_logs_sessions_by_entry_page_total",
+            "path",
+            "Top sessions by first path",
+        ),
+        Section::ExitPages => (
+            "top_logs_sessions_by_exit_page_total",
+            "path",
+            "Top sessions by last path",
+        ),
+        Section::SessionDepths => (
+            "top_logs_sessions_by_depth_total",
+            "requests",
+            "Sessions by number of requests",
+        ),
+        Section::SessionDurations => (
+            "top_logs_sessions_by_duration_total",
+            "duration",
+            "Sessions by duration in seconds",
+        ),
+        Section::CertCoverage => (
+            "top_logs_requests_by_uncovered_host_total",
+            "host",
+            "Top requests for hosts no name of the certificate host list covers",
+        ),
+        Section::IpAudit => (
+            "top_logs_requests_by_flagged_client_ip_total",
+            "client_ip",
+            "Top requests from client IPs outside the allowlist or on the denylist",
+        ),
+        Section::Pii => (
+            "top_logs_requests_with_pii_total",
+            "path",
+            "Top requests with likely PII in the URL, by path and kind",
+        ),
+        Section::MalformedRequests => (
+            "top_logs_malformed_requests_total",
+            "request",
+            "Top malformed request lines, by kind and request as logged",
+        ),
+        Section::StatusTransitions => (
+            "top_logs_client_status_transitions_requests",
+            "transition",
+            "Requests after a change in the status codes of a top client",
+        ),
+        Section::RetryStorms => (
+            "top_logs_retry_storm_requests",
+            "request",
+            "Requests of the largest bursts of identical requests by one client",
+        ),
+        Section::SlowScans => (
+            "top_logs_slow_scan_distinct_paths",
+            "client_ip",
+            "Distinct paths requested by client IPs scanning at a low rate",
+        ),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// the tags of the report as labels, leaving out those named like a label of the metric
+fn tag_labels(report: &Report, reserved: &[&str]) -> Vec<String> {
+    report
+        .tags
+        .iter()
+        .filter(|(key, _)| !reserved.contains(&key.as_str()))
+        .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+        .collect()
+}
+
+fn write_metric<W: Write>(
+    out: &mut W,
+    name: &str,
+    kind: &str,
+    help: &str,
+    tags: &[String],
+    value: impl std::fmt::Display,
+) -> io::Result<()> {
+    writeln!(out, "# HELP {name} {help}")?;
+    writeln!(out, "# TYPE {name} {kind}")?;
+    if tags.is_empty() {
+        writeln!(out, "{name} {value}")
+    } else {
+        writeln!(out, "{name}{{{}}} {value}", tags.join(","))
+    }
+}
+
+fn write_section<W: Write>(
+    section: &ReportSection,
+    report: &Report,
+    out: &mut W,
+) -> io::Result<()> {
+    let (name, label, help) = metric(section.section);
+    let tags: String = tag_labels(report, &[label, "from", "to"])
+        .iter()
+        .map(|tag| format!("{tag},"))
+        .collect();
+
+    writeln!(out, "# HELP {name} {help}")?;
+    // forecasts and distinct counts are estimates, not counts of requests
+    let kind = match section.section {
+        Section::Forecast | Section::HostClients | Section::ClientRequests => "gauge",
+        _ => "counter",
+    };
+    writeln!(out, "# TYPE {name} {kind}")?;
+    for row in &section.rows {
+        let value = match section.section {
+            // "200 OK" becomes "200"
+            Section::ResponseCodes => row.key.split(' ').next().unwrap_or_default().to_string(),
+            _ => row.key.clone(),
+        };
+        match (row.from, row.to) {
+            (Some(from), Some(to)) => writeln!(
+                out,
+                "{name}{{{tags}from=\"{from}\",to=\"{to}\"}} {}",
+                row.count
+            )?,
+            _ => writeln!(
+                out,
+                "{name}{{{tags}{label}=\"{}\"}} {}",
+                escape(&value),
+                row.count
+            )?,
+        }
+    }
+
+    // only the time series has percentiles, which are keyed by the same label
+    if section.rows.iter().any(|row| row.p50.is_some()) {
+        for (suffix, percentile) in [("p50", 50), ("p95", 95)] {
+            let name = format!("top_logs_response_time_{suffix}_by_{label}_seconds");
+            writeln!(
+                out,
+                "# HELP {name} {percentile}th percentile response time by {label}"
+            )?;
+            writeln!(out, "# TYPE {name} gauge")?;
+            for row in &section.rows {
+                let value = if percentile == 50 { row.p50 } else { row.p95 };
+                if let Some(value) = value {
+                    writeln!(
+                        out,
+                        "{name}{{{tags}{label}=\"{}\"}} {value}",
+                        escape(&row.key)
+                    )?;
+                }
+            }
+        }
+    }
+
+    // only the error rates have the 5xx and 4xx responses
+    if section.rows.iter().any(|row| row.server_errors.is_some()) {
+        for (class, range) in [("server", "5xx"), ("client", "4xx")] {
+            let name = format!("top_logs_{class}_errors_by_{label}_total");
+            writeln!(
+                out,
+                "# HELP {name} Requests with a {range} response by {label}"
+            )?;
+            writeln!(out, "# TYPE {name} counter")?;
+            for row in &section.rows {
+                let value = if class == "server" {
+                    row.server_errors
+                } else {
+                    row.client_errors
+                };
+                if let Some(value) = value {
+                    writeln!(
+                        out,
+                        "{name}{{{tags}{label}=\"{}\"}} {value}",
+                        escape(&row.key)
+                    )?;
+                }
+            }
+        }
+    }
+
+    // only the bandwidth has the size of the responses
+    if section.rows.iter().any(|row| row.megabytes.is_some()) {
+        let name = format!("top_logs_sent_megabytes_by_{label}_total");
+        writeln!(
+            out,
+            "# HELP {name} Megabytes of the response bodies by {label}"
+        )?;
+        writeln!(out, "# TYPE {name} counter")?;
+        for row in &section.rows {
+            if let Some(value) = row.megabytes {
+                writeln!(
+                    out,
+                    "{name}{{{tags}{label}=\"{}\"}} {value}",
+                    escape(&row.key)
+                )?;
+            }
+        }
+    }
+
+    // only the forecast has a confidence band
+    if section.rows.iter().any(|row| row.low.is_some()) {
+        for (suffix, bound) in [("low", "Lower"), ("high", "Upper")] {
+            let name = format!("{name}_{suffix}");
+            writeln!(out, "# HELP {name} {bound} bound of the 95% forecast band")?;
+            writeln!(out, "# TYPE {name} gauge")?;
+            for row in &section.rows {
+                let value = if suffix == "low" { row.low } else { row.high };
+                if let Some(value) = value {
+                    writeln!(
+                        out,
+                        "{name}{{{tags}{label}=\"{}\"}} {value}",
+                        escape(&row.key)
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(super) fn write<W: Write>(report: &Report, out: &mut W) -> io::Result<()> {
+    let tags = tag_labels(report, &[]);
+    if let Some(duration) = &report.duration {
+        write_metric(
+            out,
+            "top_logs_log_start_timestamp_seconds",
+            "gauge",
+            "Timestamp of the oldest log entry",
+            &tags,
+            duration.start.timestamp(),
+        )?;
+        write_metric(
+            out,
+            "top_logs_log_end_timestamp_seconds",
+            "gauge",
+            "Timestamp of the newest log entry",
+            &tags,
+            duration.end.timestamp(),
+        )?;
+    }
+    write_metric(
+        out,
+        "top_logs_log_requests_total",
+        "counter",
+        "Total number of requests",
+        &tags,
+        report.total_requests,
+    )?;
+    write_metric(
+        out,
+        "top_logs_parse_errors_total",
+        "counter",
+        "Total number of log lines that could not be parsed",
+        &tags,
+        report.errors,
+    )?;
+    if let Some(filtered) = report.filtered {
+        write_metric(
+            out,
+            "top_logs_filtered_requests_total",
+            "counter",
+            "Total number of requests skipped by filters",
+            &tags,
+            filtered,
+        )?;
+    }
+
+    if let Some(unique) = &report.unique {
+        for (name, help, cardinality) in [
+            (
+                "top_logs_unique_client_ips",
+                "Distinct client IPs",
+                &unique.client_ips,
+            ),
+            (
+                "top_logs_unique_paths",
+                "Distinct paths, without query params",
+                &unique.paths,
+            ),
+            (
+                "top_logs_unique_user_agents",
+                "Distinct user agents",
+                &unique.user_agents,
+            ),
+        ] {
+            write_metric(out, name, "gauge", help, &tags, cardinality.count)?;
+        }
+    }
+
+    if let Some(concentration) = &report.concentration {
+        let fields = [
+            ("client_ips", "client IPs", &concentration.client_ips),
+            ("paths", "paths", &concentration.paths),
+        ];
+        for (field, name, share) in fields {
+            let Some(share) = share else {
+                continue;
+            };
+            write_metric(
+                out,
+                &format!("top_logs_{field}_top_1_percent_share"),
+                "gauge",
+                &format!("Percentage of the requests from the busiest 1% of the {name}"),
+                &tags,
+                share.top_1_percent,
+            )?;
+            write_metric(
+                out,
+                &format!("top_logs_{field}_top_10_percent_share"),
+                "gauge",
+                &format!("Percentage of the requests from the busiest 10% of the {name}"),
+                &tags,
+                share.top_10_percent,
+            )?;
+            write_metric(
+                out,
+                &format!("top_logs_{field}_gini"),
+                "gauge",
+                &format!("Gini index of the requests by {name}"),
+                &tags,
+                share.gini,
+            )?;
+        }
+    }
+
+    if let Some(statistics) = &report.statistics {
+        for (field, stats) in statistics.times() {
+            let name = field.replace('_', " ");
+            for (stat, help, value) in [
+                ("mean", "Mean", stats.mean),
+                ("min", "Shortest", stats.min),
+                ("max", "Longest", stats.max),
+                ("stddev", "Standard deviation of the", stats.stddev),
+            ] {
+                write_metric(
+                    out,
+                    &format!("top_logs_{field}_{stat}_seconds"),
+                    "gauge",
+                    &format!("{help} {name}"),
+                    &tags,
+                    value,
+                )?;
+            }
+        }
+        write_metric(
+            out,
+            "top_logs_bytes_sent_total",
+            "counter",
+            "Total bytes of the response bodies",
+            &tags,
+            statistics.bytes_sent,
+        )?;
+        if let Some(bytes) = statistics.bytes_received {
+            write_metric(
+                out,
+                "top_logs_bytes_received_total",
+                "counter",
+                "Total bytes of the request bodies",
+                &tags,
+                bytes,
+            )?;
+        }
+    }
+
+    if let Some(headline) = &report.headline {
+        write_metric(
+            out,
+            "top_logs_server_errors_total",
+            "counter",
+            "Total number of requests with a 5xx response",
+            &tags,
+            headline.server_errors,
+        )?;
+        if let Some(p95) = headline.p95_response_time {
+            write_metric(
+                out,
+                "top_logs_response_time_p95_seconds",
+                "gauge",
+                "95th percentile response time",
+                &tags,
+                p95,
+            )?;
+        }
+        if let Some(minute) = &headline.busiest_minute {
+            write_metric(
+                out,
+                "top_logs_busiest_minute_requests",
+                "gauge",
+                "Requests during the busiest minute",
+                &tags,
+                minute.count,
+            )?;
+        }
+    }
+
+    for section in &report.sections {
+        write_section(section, report, out)?;
+    }
+
+    Ok(())
+}
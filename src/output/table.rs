@@ -0,0 +1,286 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::extract::ExtractReport;
+use crate::layout::Field;
+use crate::outlier::{OutlierReport, SlowestReport};
+use crate::report::{Report, ReportRow};
+use crate::slices::SliceReport;
+use crate::timeline::TimelineReport;
+use prettytable::{cell, Row, Table};
+use std::io::{self, Write};
+
+// the longest bar of `--bars`, for the largest count of a table
+const BAR_WIDTH: usize = 30;
+
+// histogram buckets are right aligned to the widest bucket boundary
+fn bucket_width(rows: &[ReportRow]) -> usize {
+    rows.iter()
+        .filter_map(|row| row.to)
+        .max()
+        .map(|to| format!("{}", to - 1).len())
+        .unwrap_or(0)
+}
+
+// a bar of `count` relative to `max`, requests are never shown as an empty bar
+fn bar(count: usize, max: usize) -> String {
+    let length = match count {
+        0 => 0,
+        count => ((count * BAR_WIDTH + max / 2) / max.max(1)).max(1),
+    };
+    "█".repeat(length)
+}
+
+fn label(row: &ReportRow, width: usize) -> String {
+    match (row.from, row.to) {
+        (Some(from), Some(to)) => format!("{from:width$} to {to:width$}"),
+        _ => row.key.clone(),
+    }
+}
+
+pub(super) fn write<W: Write>(report: &Report, out: &mut W) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "Duration: {}", report.duration_label())?;
+    if let Some(sparkline) = &report.sparkline {
+        writeln!(out, "Traffic:  {sparkline}")?;
+    }
+    writeln!(out)?;
+
+    writeln!(out)?;
+    writeln!(out, "Total Requests: {}", report.total_requests)?;
+    writeln!(out, "Total Errors  : {}", report.errors)?;
+    if let Some(filtered) = report.filtered {
+        writeln!(out, "Total Filtered: {filtered}")?;
+    }
+    if let Some(coverage) = &report.coverage {
+        for (label, value) in coverage.items() {
+            writeln!(out, "{label:14}: {value}")?;
+        }
+    }
+    writeln!(out)?;
+
+    let blocks = [
+        (
+            "Unique",
+            report.unique.as_ref().map(|unique| unique.items()),
+        ),
+        (
+            "Concentration",
+            report.concentration.as_ref().map(|c| c.items()),
+        ),
+        ("Statistics", report.statistics.as_ref().map(|s| s.items())),
+    ];
+    for (title, items) in blocks {
+        let Some(items) = items else {
+            continue;
+        };
+        let width = items
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+        writeln!(out, "{title}:")?;
+        writeln!(out)?;
+        for (label, value) in items {
+            writeln!(out, "  {label:width$} : {value}")?;
+        }
+        writeln!(out)?;
+    }
+
+    if let Some(headline) = &report.headline {
+        let items = headline.items();
+        let width = items
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+        writeln!(out, "Headline:")?;
+        writeln!(out)?;
+        for (label, value) in items {
+            writeln!(out, "  {label:width$} : {value}")?;
+        }
+        writeln!(out)?;
+    }
+
+    if let Some(segment) = &report.segment {
+        for (title, side) in segment.tables() {
+            writeln!(out, "{title}")?;
+            writeln!(out)?;
+            let mut table = Table::new();
+            table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+            for row in &side.paths {
+                table.add_row(Row::new(vec![cell!(row.key), cell!(row.count)]));
+            }
+            table.print(out)?;
+            writeln!(out)?;
+        }
+    }
+
+    for section in &report.sections {
+        writeln!(out, "{}", section.title)?;
+        writeln!(out)?;
+
+        let width = bucket_width(&section.rows);
+        let columns = section.columns();
+        let max = section.rows.iter().map(|row| row.count).max().unwrap_or(0);
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+        if section.has_header() {
+            let mut titles = Vec::new();
+            for column in &columns {
+                titles.push(cell!(column.title));
+                if section.bars && column.field == Field::Count {
+                    titles.push(cell!(""));
+                }
+            }
+            table.set_titles(Row::new(titles));
+        }
+        for row in &section.rows {
+            let mut cells = Vec::new();
+            for column in &columns {
+                cells.push(match column.field {
+                    Field::Key => cell!(label(row, width)),
+                    _ => cell!(column.text(row)),
+                });
+                // next to the count, wherever a layout puts it
+                if section.bars && column.field == Field::Count {
+                    cells.push(cell!(bar(row.count, max)));
+                }
+            }
+            table.add_row(Row::new(cells));
+        }
+        table.print(out)?;
+
+        writeln!(out)?;
+    }
+
+    if let Some(outliers) = &report.outliers {
+        writeln!(out, "{}", outliers.title())?;
+        writeln!(out)?;
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+        table.set_titles(OutlierReport::HEADERS.iter().map(|h| cell!(h)).collect());
+        for outlier in &outliers.requests {
+            table.add_row(outlier.cells().iter().map(|c| cell!(c)).collect());
+        }
+        table.print(out)?;
+        writeln!(out)?;
+    }
+
+    if let Some(slowest) = &report.slowest {
+        writeln!(out, "{}", slowest.title())?;
+        writeln!(out)?;
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+        table.set_titles(SlowestReport::HEADERS.iter().map(|h| cell!(h)).collect());
+        for request in &slowest.requests {
+            table.add_row(request.slowest_cells().iter().map(|c| cell!(c)).collect());
+        }
+        table.print(out)?;
+        writeln!(out)?;
+    }
+
+    if let Some(slices) = &report.slices {
+        writeln!(out, "{}", slices.title())?;
+        writeln!(out)?;
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+        table.set_titles(SliceReport::HEADERS.iter().map(|h| cell!(h)).collect());
+        for slice in &slices.slices {
+            table.add_row(slice.cells().iter().map(|c| cell!(c)).collect());
+        }
+        table.print(out)?;
+        writeln!(out)?;
+    }
+
+    for timeline in &report.timelines {
+        writeln!(out, "{}", timeline.title())?;
+        writeln!(out)?;
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+        table.set_titles(TimelineReport::HEADERS.iter().map(|h| cell!(h)).collect());
+        for point in &timeline.points {
+            table.add_row(point.cells().iter().map(|c| cell!(c)).collect());
+        }
+        table.print(out)?;
+        writeln!(out)?;
+    }
+
+    for extract in &report.extracts {
+        writeln!(out, "{}", extract.title())?;
+        writeln!(out)?;
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+        table.set_titles(ExtractReport::HEADERS.iter().map(|h| cell!(h)).collect());
+        for value in &extract.values {
+            table.add_row(value.cells().iter().map(|c| cell!(c)).collect());
+        }
+        table.print(out)?;
+        writeln!(out)?;
+    }
+
+    for breakdown in &report.breakdowns {
+        writeln!(out, "{}", breakdown.title())?;
+        writeln!(out)?;
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+        table.set_titles(breakdown.headers().iter().map(|h| cell!(h)).collect());
+        for row in &breakdown.rows {
+            table.add_row(row.cells().iter().map(|c| cell!(c)).collect());
+        }
+        table.print(out)?;
+        writeln!(out)?;
+    }
+
+    for deviation in &report.deviations {
+        writeln!(out, "{}", deviation.title())?;
+        writeln!(out)?;
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+        table.set_titles(deviation.headers().iter().map(|h| cell!(h)).collect());
+        for key in &deviation.keys {
+            table.add_row(key.cells().iter().map(|c| cell!(c)).collect());
+        }
+        table.print(out)?;
+        writeln!(out)?;
+    }
+
+    for drill in &report.drills {
+        writeln!(out, "Drill: {}", drill.key)?;
+        writeln!(out)?;
+        let items = drill.items();
+        let width = items
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0);
+        for (label, value) in items {
+            writeln!(out, "  {label:width$} : {value}")?;
+        }
+        writeln!(out)?;
+
+        for drill_table in &drill.tables {
+            writeln!(out, "{}", drill_table.title)?;
+            writeln!(out)?;
+            let mut table = Table::new();
+            table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+            for row in &drill_table.rows {
+                table.add_row(Row::new(vec![cell!(row.key), cell!(row.count)]));
+            }
+            table.print(out)?;
+            writeln!(out)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::report::Report;
+use anyhow::{Context as _, Result};
+use std::io::Write;
+use std::path::Path;
+use tera::{Context, Tera};
+
+/// Renders `report` through the Tera template at `path`. The template sees the
+/// same fields as the JSON output, `duration`, `coverage`, `sparkline`,
+/// `total_requests`, `errors`, `filtered`, `unique`, `concentration`,
+/// `statistics`, `headline`, `segment`, `sections`, `outliers`, `slowest`,
+/// `slices`, `timelines`, `extracts`, `breakdowns`, `deviations` and `drills`.
+/// Templates ending in `.html`, `.htm` or `.xml` have their values escaped.
+pub fn write_template<W: Write>(report: &Report, path: &Path, out: &mut W) -> Result<()> {
+    let mut tera = Tera::default();
+    tera.add_template_file(path, None)
+        .with_context(|| format!("loading template {}", path.display()))?;
+
+    let context = Context::from_serialize(report)?;
+    tera.render_to(&path.to_string_lossy(), &context, out)
+        .with_context(|| format!("rendering template {}", path.display()))
+}
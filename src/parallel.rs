@@ -0,0 +1,353 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+    reader, Breakdown, DefaultHashMap, Drill, Extract, Outliers, Segment, Sessions, Slices,
+    Timeline, TopInfo,
+};
+use access_log_parser::LogType;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::hash::Hash;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+
+// adds the counts of `other` to `counts`
+pub(crate) fn add_counts<K>(counts: &mut DefaultHashMap<K, usize>, other: &DefaultHashMap<K, usize>)
+where
+    K: Eq + Hash + Clone,
+{
+    for (key, count) in other.iter() {
+        counts[key.clone()] += count;
+    }
+}
+
+// how many pieces per thread can be counted ahead of the first one not merged yet
+const LOOKAHEAD: usize = 2;
+
+// a part of the work of `process_files`, a whole file or a chunk of one
+struct Piece<'a> {
+    path: &'a str,
+    log_type: LogType,
+    // the whole file if `None`
+    range: Option<Range<u64>>,
+    // whether this is the last chunk of its file
+    last: bool,
+}
+
+impl TopInfo {
+    /// Counts the access logs in `files` on up to `jobs` threads. When there
+    /// are fewer files than threads, large files are split into chunks of
+    /// lines too. The result is the same as calling `process_file` for every
+    /// file in order, since each file or chunk is counted on its own and
+    /// merged in order.
+    ///
+    /// With a checkpoint, see `set_checkpoint`, files are always split into
+    /// chunks where possible, so the checkpoint can be written between them.
+    pub fn process_files(&mut self, files: &[(String, LogType)], jobs: usize) -> Result<()> {
+        let checkpointing = self.checkpoint.is_some();
+        let mut pieces = Vec::new();
+        for (path, log_type) in files {
+            // the same chunks for any number of jobs, so a run can be resumed with others
+            let chunks = if checkpointing {
+                reader::chunks(path, &self.read_options, usize::MAX)?
+            } else if jobs > files.len() {
+                reader::chunks(path, &self.read_options, jobs)?
+            } else {
+                Vec::new()
+            };
+            if chunks.is_empty() {
+                pieces.push(Piece {
+                    path,
+                    log_type: *log_type,
+                    range: None,
+                    last: true,
+                });
+            }
+            let count = chunks.len();
+            for (i, range) in chunks.into_iter().enumerate() {
+                pieces.push(Piece {
+                    path,
+                    log_type: *log_type,
+                    range: Some(range),
+                    last: i + 1 == count,
+                });
+            }
+        }
+
+        if let Some(checkpoint) = &self.checkpoint {
+            let mut remaining = Vec::new();
+            for mut piece in pieces {
+                if !checkpoint.skip(piece.path, &mut piece.range)? {
+                    remaining.push(piece);
+                }
+            }
+            pieces = remaining;
+        }
+
+        let jobs = jobs.min(pieces.len());
+        if jobs <= 1 && !checkpointing {
+            for (file, log_type) in files {
+                self.process_file(file, *log_type)?;
+            }
+            return Ok(());
+        }
+
+        let next = AtomicUsize::new(0);
+        // the number of merged pieces, `usize::MAX` once a piece failed. The
+        // counts of pieces finishing early are kept until they're merged, so
+        // one slow piece mustn't let the others run through every file.
+        let done = (Mutex::new(0_usize), Condvar::new());
+        let window = jobs * LOOKAHEAD;
+        let (sender, receiver) = mpsc::channel();
+        let pieces = &pieces;
+        thread::scope(|scope| {
+            for _ in 0..jobs {
+                let template = self.cleared();
+                let sender = sender.clone();
+                let next = &next;
+                let done = &done;
+                scope.spawn(move || loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(piece) = pieces.get(index) else {
+                        break;
+                    };
+                    let (merged, wake) = done;
+                    let mut merged = merged.lock().unwrap();
+                    while index >= merged.saturating_add(window) {
+                        merged = wake.wait(merged).unwrap();
+                    }
+                    if *merged == usize::MAX {
+                        break;
+                    }
+                    drop(merged);
+                    let mut ti = template.cleared();
+                    let result = match &piece.range {
+                        Some(range) => ti.process_chunk(piece.path, range.clone(), piece.log_type),
+                        None => ti.process_file(piece.path, piece.log_type).map(|_| (0, 0)),
+                    };
+                    let result = result.map(|lines| (ti, lines));
+                    if sender.send((index, result)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(sender);
+
+            // the other threads stop after their current piece
+            let stop = || {
+                next.store(pieces.len(), Ordering::SeqCst);
+                *done.0.lock().unwrap() = usize::MAX;
+                done.1.notify_all();
+            };
+            // pieces finishing early wait for the ones before them
+            let mut pending = BTreeMap::new();
+            let mut merged = 0;
+            // lines and parse errors of the chunks of the current file
+            let (mut lines, mut errors) = (0, 0);
+            for (index, result) in receiver {
+                match result {
+                    Ok(counts) => {
+                        pending.insert(index, counts);
+                    }
+                    Err(err) => {
+                        stop();
+                        return Err(err);
+                    }
+                }
+                while let Some((ti, (chunk_lines, chunk_errors))) = pending.remove(&merged) {
+                    self.merge(ti);
+                    // merges are rare enough to look at the time on every one
+                    self.check_interim(true);
+                    let piece = &pieces[merged];
+                    if let Some(checkpoint) = &mut self.checkpoint {
+                        let offset = piece.range.as_ref().map_or(0, |range| range.end);
+                        checkpoint.advance(piece.path, offset, piece.last);
+                        if let Err(err) = self.write_checkpoint(false) {
+                            stop();
+                            return Err(err);
+                        }
+                    }
+                    if piece.range.is_some() {
+                        lines += chunk_lines;
+                        errors += chunk_errors;
+                        if piece.last {
+                            reader::warn_unparsed(piece.path, lines, errors, &self.read_options);
+                            (lines, errors) = (0, 0);
+                        }
+                    }
+                    merged += 1;
+                    *done.0.lock().unwrap() = merged;
+                    done.1.notify_all();
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        })?;
+        self.write_checkpoint(true)
+    }
+
+    // counts the lines in `range` of the file at `path`, returns the number
+    // of lines and how many of them couldn't be parsed
+    pub(crate) fn process_chunk(
+        &mut self,
+        path: &str,
+        range: Range<u64>,
+        log_type: LogType,
+    ) -> Result<(usize, usize)> {
+        self.tags.add_file(path);
+        let options = self.read_options.clone();
+        let (lines, errors) = reader::read_chunk(path, range, log_type, &options, |log, line| {
+            self.process_entry(log, line);
+            Ok(())
+        })?;
+        self.errors += errors;
+        Ok((lines, errors))
+    }
+
+    // the same settings, without any counts
+    pub(crate) fn cleared(&self) -> TopInfo {
+        TopInfo {
+            read_options: self.read_options.clone(),
+            filter: self.filter.clone(),
+            extra_fields: self.extra_fields.cleared(),
+            tags: self.tags.clone(),
+            time_resolution: self.time_resolution,
+            interval: self.interval,
+            sessions: self.sessions.as_ref().map(Sessions::cleared),
+            slowest: self.slowest.as_ref().map(Outliers::cleared),
+            slices: self.slices.as_ref().map(Slices::cleared),
+            drills: self.drills.iter().map(Drill::cleared).collect(),
+            segment: self.segment.as_ref().map(Segment::cleared),
+            timelines: self.timelines.iter().map(Timeline::cleared).collect(),
+            extracts: self.extracts.iter().map(Extract::cleared).collect(),
+            breakdowns: self.breakdowns.iter().map(Breakdown::cleared).collect(),
+            warned_invalid_status: Arc::clone(&self.warned_invalid_status),
+            warned_invalid_method: Arc::clone(&self.warned_invalid_method),
+            ..TopInfo::new(self.max_results, self.read_options.ignore_parse_errors)
+        }
+    }
+
+    // adds the counts of `other`, a cleared copy of this TopInfo
+    pub(crate) fn merge(&mut self, other: TopInfo) {
+        // the duration of a TopInfo without requests is only a placeholder
+        if self.total_requests == 0 {
+            self.duration = other.duration.clone();
+        } else if other.total_requests > 0 {
+            self.duration.start = self.duration.start.min(other.duration.start);
+            self.duration.end = self.duration.end.max(other.duration.end);
+        }
+        self.total_requests += other.total_requests;
+        self.errors += other.errors;
+        self.filtered += other.filtered;
+        add_counts(&mut self.response_codes, &other.response_codes);
+        self.invalid_statuses += other.invalid_statuses;
+        add_counts(&mut self.request_methods, &other.request_methods);
+        self.invalid_methods += other.invalid_methods;
+        add_counts(&mut self.requests_no_query, &other.requests_no_query);
+        add_counts(&mut self.requests_query, &other.requests_query);
+        add_counts(&mut self.client_ips, &other.client_ips);
+        add_counts(&mut self.referrers, &other.referrers);
+        add_counts(&mut self.user_agents, &other.user_agents);
+        add_counts(&mut self.backend_ips, &other.backend_ips);
+        add_counts(&mut self.x_forwarded_fors, &other.x_forwarded_fors);
+        add_counts(&mut self.hosts, &other.hosts);
+        for (host, clients) in other.host_clients.iter() {
+            self.host_clients[host.clone()].merge(clients);
+        }
+        add_counts(&mut self.app_ids, &other.app_ids);
+        add_counts(&mut self.app_indexes, &other.app_indexes);
+        self.response_times.merge(&other.response_times);
+        self.gorouter_times.merge(&other.gorouter_times);
+        add_counts(&mut self.x_cf_routererrors, &other.x_cf_routererrors);
+        add_counts(&mut self.requests_per_minute, &other.requests_per_minute);
+        for (minute, latencies) in other.latencies_per_minute.iter() {
+            self.latencies_per_minute[*minute].merge(latencies);
+        }
+        add_counts(&mut self.requests_per_second, &other.requests_per_second);
+        for (second, latencies) in other.latencies_per_second.iter() {
+            self.latencies_per_second[*second].merge(latencies);
+        }
+        add_counts(
+            &mut self.server_errors_per_minute,
+            &other.server_errors_per_minute,
+        );
+        add_counts(
+            &mut self.client_errors_per_minute,
+            &other.client_errors_per_minute,
+        );
+        add_counts(
+            &mut self.server_errors_per_second,
+            &other.server_errors_per_second,
+        );
+        add_counts(
+            &mut self.client_errors_per_second,
+            &other.client_errors_per_second,
+        );
+        add_counts(
+            &mut self.bytes_sent_per_minute,
+            &other.bytes_sent_per_minute,
+        );
+        add_counts(
+            &mut self.bytes_sent_per_second,
+            &other.bytes_sent_per_second,
+        );
+        add_counts(&mut self.error_paths, &other.error_paths);
+        add_counts(&mut self.client_error_paths, &other.client_error_paths);
+        add_counts(&mut self.client_errors, &other.client_errors);
+        add_counts(&mut self.pii_paths, &other.pii_paths);
+        add_counts(&mut self.malformed_requests, &other.malformed_requests);
+        self.extra_fields.merge(&other.extra_fields);
+        self.tags.merge(other.tags);
+        self.unique_client_ips.merge(&other.unique_client_ips);
+        self.unique_paths.merge(&other.unique_paths);
+        self.unique_user_agents.merge(&other.unique_user_agents);
+        self.response_time_stats.merge(&other.response_time_stats);
+        self.gorouter_time_stats.merge(&other.gorouter_time_stats);
+        self.bytes_sent += other.bytes_sent;
+        if let Some(bytes) = other.bytes_received {
+            *self.bytes_received.get_or_insert(0) += bytes;
+        }
+        add_counts(&mut self.request_sizes, &other.request_sizes);
+        add_counts(
+            &mut self.bytes_received_by_path,
+            &other.bytes_received_by_path,
+        );
+        self.outliers.merge(&other.outliers);
+        if let (Some(slowest), Some(other)) = (&mut self.slowest, &other.slowest) {
+            slowest.merge(other);
+        }
+        if let (Some(sessions), Some(other)) = (&mut self.sessions, other.sessions) {
+            sessions.merge(other);
+        }
+        if let (Some(slices), Some(other)) = (&mut self.slices, other.slices) {
+            slices.merge(other);
+        }
+        for (drill, other) in self.drills.iter_mut().zip(&other.drills) {
+            drill.merge(other);
+        }
+        if let (Some(segment), Some(other)) = (&mut self.segment, &other.segment) {
+            segment.merge(other);
+        }
+        for (timeline, other) in self.timelines.iter_mut().zip(&other.timelines) {
+            timeline.merge(other);
+        }
+        for (extract, other) in self.extracts.iter_mut().zip(&other.extracts) {
+            extract.merge(other);
+        }
+        for (breakdown, other) in self.breakdowns.iter_mut().zip(&other.breakdowns) {
+            breakdown.merge(other);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::fmt;
+
+/// Personal or payment data which shouldn't show up in URLs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pii {
+    CardNumber,
+    Email,
+    Ssn,
+}
+
+impl fmt::Display for Pii {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Pii::CardNumber => "card number",
+            Pii::Email => "email",
+            Pii::Ssn => "SSN",
+        })
+    }
+}
+
+/// The kinds of PII in a request path and query, each reported once
+pub fn scan(uri: &str) -> Vec<Pii> {
+    let decoded = percent_decode(uri);
+    let mut found = Vec::new();
+    if has_card_number(&decoded) {
+        found.push(Pii::CardNumber);
+    }
+    if has_email(&decoded) {
+        found.push(Pii::Email);
+    }
+    if has_ssn(&decoded) {
+        found.push(Pii::Ssn);
+    }
+    found
+}
+
+// query strings encode '@' as %40 and spaces as '+', invalid escapes are kept as is
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                (Some(high), Some(low)) => {
+                    decoded.push(high << 4 | low);
+                    i += 3;
+                    continue;
+                }
+                _ => decoded.push(b'%'),
+            },
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+pub(crate) fn luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| match (i % 2, digit * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => *digit,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+// runs of 13 to 19 digits passing the Luhn check, single spaces or dashes may
+// group the digits like they're printed on the card
+fn has_card_number(text: &str) -> bool {
+    let mut digits = Vec::new();
+    let mut separator = false;
+    for c in text.chars().chain(std::iter::once('\0')) {
+        match c.to_digit(10) {
+            Some(digit) => {
+                digits.push(digit);
+                separator = false;
+            }
+            None if (c == ' ' || c == '-') && !digits.is_empty() && !separator => {
+                separator = true;
+            }
+            None => {
+                if (13..=19).contains(&digits.len()) && luhn(&digits) {
+                    return true;
+                }
+                digits.clear();
+                separator = false;
+            }
+        }
+    }
+    false
+}
+
+fn has_email(text: &str) -> bool {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || c == '.' || c == '-';
+
+    text.match_indices('@').any(|(at, _)| {
+        let local = text[..at]
+            .chars()
+            .rev()
+            .take_while(|c| is_local(*c))
+            .count();
+        let domain: String = text[at + 1..]
+            .chars()
+            .take_while(|c| is_domain(*c))
+            .collect();
+        let domain = domain.trim_end_matches('.');
+        let tld = domain.rsplit_once('.').map(|(_, tld)| tld);
+        local > 0
+            && tld.is_some_and(|tld| tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()))
+    })
+}
+
+// ddd-dd-dddd, skipping numbers which are never issued
+fn has_ssn(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.windows(11).enumerate().any(|(i, window)| {
+        let digit_at = |j: usize| window[j].is_ascii_digit();
+        let shape = (0..11).all(|j| {
+            if j == 3 || j == 6 {
+                window[j] == b'-'
+            } else {
+                digit_at(j)
+            }
+        });
+        let bounded = (i == 0 || !bytes[i - 1].is_ascii_digit())
+            && bytes.get(i + 11).is_none_or(|b| !b.is_ascii_digit());
+        if !shape || !bounded {
+            return false;
+        }
+
+        let area = &window[0..3];
+        area != b"000"
+            && area != b"666"
+            && area[0] != b'9'
+            && &window[4..6] != b"00"
+            && &window[7..11] != b"0000"
+    })
+}
@@ -0,0 +1,108 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::fmt;
+
+/// The rough kind of client behind a user agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Device {
+    Desktop,
+    Mobile,
+    Tablet,
+    Bot,
+    /// Command line tools and HTTP client libraries, like curl
+    HttpClient,
+    Unknown,
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Device::Desktop => "Desktop",
+            Device::Mobile => "Mobile",
+            Device::Tablet => "Tablet",
+            Device::Bot => "Bot",
+            Device::HttpClient => "HTTP client",
+            Device::Unknown => "Unknown",
+        })
+    }
+}
+
+const BOT_MARKERS: [&str; 6] = [
+    "bot",
+    "spider",
+    "crawl",
+    "slurp",
+    "facebookexternalhit",
+    "monitor",
+];
+
+const HTTP_CLIENT_MARKERS: [&str; 10] = [
+    "curl/",
+    "wget/",
+    "python-requests",
+    "python-urllib",
+    "go-http-client",
+    "okhttp",
+    "java/",
+    "apache-httpclient",
+    "postmanruntime",
+    "axios",
+];
+
+// checked in order, the more specific names first
+const OPERATING_SYSTEMS: [(&str, &str); 10] = [
+    ("windows phone", "Windows Phone"),
+    ("iphone", "iOS"),
+    ("ipad", "iOS"),
+    ("ipod", "iOS"),
+    ("android", "Android"),
+    ("cros", "ChromeOS"),
+    ("mac os x", "macOS"),
+    ("macintosh", "macOS"),
+    ("windows", "Windows"),
+    ("linux", "Linux"),
+];
+
+/// Guesses the device and operating system of a client from its user agent.
+/// This is a heuristic on well known tokens, good for a breakdown of the
+/// traffic but not for identifying single clients.
+pub fn platform(user_agent: &str) -> (Device, Option<&'static str>) {
+    let ua = user_agent.to_lowercase();
+
+    if BOT_MARKERS.iter().any(|marker| ua.contains(marker)) {
+        return (Device::Bot, None);
+    }
+    if HTTP_CLIENT_MARKERS.iter().any(|marker| ua.contains(marker)) {
+        return (Device::HttpClient, None);
+    }
+
+    let os = OPERATING_SYSTEMS
+        .iter()
+        .find(|(token, _)| ua.contains(token))
+        .map(|(_, name)| *name);
+
+    // Android tablets leave out "Mobile", phones include it
+    let device = if ua.contains("ipad") || ua.contains("tablet") {
+        Device::Tablet
+    } else if ua.contains("mobi") || ua.contains("iphone") || ua.contains("ipod") {
+        Device::Mobile
+    } else if ua.contains("android") {
+        Device::Tablet
+    } else if os.is_some() {
+        Device::Desktop
+    } else {
+        Device::Unknown
+    };
+    (device, os)
+}
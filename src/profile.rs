@@ -0,0 +1,189 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares the traffic with an expected profile, the JSON report of a run
+//! known to be healthy, to show what's different about it.
+use crate::report::round3;
+use crate::{Section, SortOrder, TopInfo};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+// the expected count of a key missing from the profile, so it still counts
+// as a deviation without dividing by zero
+const MIN_EXPECTED: f64 = 0.5;
+
+/// The counts of the keys of every section of a JSON report, other fields
+/// of the report are ignored
+#[derive(Debug, Clone, Deserialize)]
+pub struct Profile {
+    total_requests: usize,
+    sections: Vec<ProfileSection>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ProfileSection {
+    section: String,
+    rows: Vec<ProfileRow>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ProfileRow {
+    key: String,
+    count: usize,
+}
+
+impl Profile {
+    /// Reads a report written with `--output json`
+    pub fn load(path: &Path) -> Result<Profile> {
+        let file =
+            File::open(path).with_context(|| format!("opening profile {}", path.display()))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("reading profile {}", path.display()))
+    }
+}
+
+/// How far the keys of a section are from their share of the requests in
+/// the profile
+#[derive(Debug, Serialize)]
+pub struct Deviation {
+    pub section: Section,
+    /// The sum of (observed - expected)² / expected over every key, where
+    /// expected is the share of the key in the profile applied to the
+    /// requests counted now
+    pub chi_square: f64,
+    /// The keys which contribute the most, largest first
+    pub keys: Vec<KeyDeviation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyDeviation {
+    pub key: String,
+    /// Percent of all requests in the profile
+    pub expected: f64,
+    /// Percent of all requests counted now
+    pub observed: f64,
+    pub chi_square: f64,
+}
+
+impl Deviation {
+    pub fn title(&self) -> String {
+        format!(
+            "Deviation of {} from the Profile (chi-square {}):",
+            self.section.name(),
+            self.chi_square
+        )
+    }
+
+    /// The column headers of `KeyDeviation::cells`
+    pub fn headers(&self) -> [&'static str; 4] {
+        [
+            self.section.key_label(),
+            "Expected %",
+            "Observed %",
+            "Chi-Square",
+        ]
+    }
+}
+
+impl KeyDeviation {
+    pub fn cells(&self) -> Vec<String> {
+        vec![
+            self.key.clone(),
+            self.expected.to_string(),
+            self.observed.to_string(),
+            self.chi_square.to_string(),
+        ]
+    }
+}
+
+fn order(section: Section) -> Option<usize> {
+    Section::ALL.iter().position(|s| *s == section)
+}
+
+impl TopInfo {
+    /// Compares the count sections of `profile` with the counts so far, the
+    /// sections which deviate the most first, with up to `max` keys each.
+    /// Sections which aren't tables of counts, like the time buckets, are
+    /// left out.
+    pub fn deviations(&self, profile: &Profile, max: usize) -> Vec<Deviation> {
+        if self.total_requests == 0 || profile.total_requests == 0 {
+            return Vec::new();
+        }
+        let total = self.total_requests as f64;
+
+        let mut deviations: Vec<Deviation> = profile
+            .sections
+            .iter()
+            .filter_map(|expected| {
+                let section: Section = expected.section.parse().ok()?;
+                let mut observed: HashMap<String, usize> = self
+                    .iter_top(section, SortOrder::ByValue)?
+                    .map(|row| (row.key, row.count))
+                    .collect();
+
+                // keys of the profile, then the ones it doesn't have
+                let mut counts: Vec<(String, f64, usize)> = expected
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        let share = row.count as f64 / profile.total_requests as f64;
+                        let count = observed.remove(&row.key).unwrap_or(0);
+                        (row.key.clone(), share, count)
+                    })
+                    .collect();
+                counts.extend(observed.into_iter().map(|(key, count)| (key, 0.0, count)));
+
+                let mut keys: Vec<KeyDeviation> = counts
+                    .into_iter()
+                    .map(|(key, share, count)| {
+                        let expected = (share * total).max(MIN_EXPECTED);
+                        let difference = count as f64 - expected;
+                        KeyDeviation {
+                            key,
+                            expected: round3(share * 100.0),
+                            observed: round3(count as f64 / total * 100.0),
+                            chi_square: difference * difference / expected,
+                        }
+                    })
+                    .collect();
+                keys.sort_by(|a, b| {
+                    b.chi_square
+                        .total_cmp(&a.chi_square)
+                        .then_with(|| a.key.cmp(&b.key))
+                });
+
+                let chi_square = keys.iter().map(|key| key.chi_square).sum();
+                keys.truncate(max);
+                keys.iter_mut()
+                    .for_each(|key| key.chi_square = round3(key.chi_square));
+                Some(Deviation {
+                    section,
+                    chi_square: round3(chi_square),
+                    keys,
+                })
+            })
+            .collect();
+
+        deviations.sort_by(|a, b| {
+            b.chi_square
+                .total_cmp(&a.chi_square)
+                .then_with(|| order(a.section).cmp(&order(b.section)))
+        });
+        deviations
+    }
+}
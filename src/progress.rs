@@ -0,0 +1,111 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Progress bars for the files being read, drawn on STDERR.
+//!
+//! Diagnostics are written through `suspend` so they don't tear the bars.
+
+use indicatif::{MultiProgress, ProgressBar, ProgressBarIter, ProgressState, ProgressStyle};
+use std::io::Read;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static BARS: LazyLock<MultiProgress> = LazyLock::new(MultiProgress::new);
+
+// lines are added to the shared count in batches
+const LINE_BATCH: u64 = 1024;
+
+/// Show a progress bar for every file while it's read
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Runs `f`, which writes to STDERR, with the progress bars hidden
+pub(crate) fn suspend<F: FnOnce()>(f: F) {
+    if is_enabled() {
+        BARS.suspend(f)
+    } else {
+        f()
+    }
+}
+
+/// The progress of reading one file, or chunk of a file, of `len` bytes.
+/// The bar is removed when it's dropped.
+pub(crate) struct Progress {
+    bar: ProgressBar,
+    lines: Arc<AtomicU64>,
+    pending: u64,
+}
+
+impl Progress {
+    // `None` unless progress bars are enabled
+    pub(crate) fn start(name: &str, len: u64) -> Option<Progress> {
+        if !is_enabled() {
+            return None;
+        }
+
+        let lines = Arc::new(AtomicU64::new(0));
+        let counted = lines.clone();
+        let style = ProgressStyle::with_template(
+            "{msg} [{bar:30}] {binary_bytes}/{binary_total_bytes} ({percent}%) {lines_per_sec}",
+        )
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .with_key(
+            "lines_per_sec",
+            move |state: &ProgressState, w: &mut dyn std::fmt::Write| {
+                let secs = state.elapsed().as_secs_f64().max(0.001);
+                let lines = counted.load(Ordering::Relaxed) as f64;
+                let _ = write!(w, "{:.0} lines/s", lines / secs);
+            },
+        )
+        .progress_chars("=> ");
+        let bar = BARS.add(ProgressBar::new(len).with_style(style));
+        bar.set_message(name.to_string());
+        Some(Progress {
+            bar,
+            lines,
+            pending: 0,
+        })
+    }
+
+    /// Moves the bar along with the bytes read from `read`
+    pub(crate) fn wrap<R: Read>(&self, read: R) -> ProgressBarIter<R> {
+        self.bar.wrap_read(read)
+    }
+
+    /// Counts a line, `end` is its offset in the file when the bytes aren't
+    /// read through `wrap`
+    pub(crate) fn line(&mut self, end: Option<u64>) {
+        self.pending += 1;
+        if self.pending == LINE_BATCH {
+            self.lines.fetch_add(self.pending, Ordering::Relaxed);
+            self.pending = 0;
+            if let Some(end) = end {
+                self.bar.set_position(end);
+            }
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+        BARS.remove(&self.bar);
+    }
+}
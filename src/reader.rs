@@ -0,0 +1,1085 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::cloud::{is_object_url, list_objects, open_object};
+use crate::compression::Compression;
+use crate::diagnostics;
+use crate::path_matches;
+use crate::progress::Progress;
+use access_log_parser::{AccessLogError, LogEntry, LogType};
+use anyhow::{anyhow, bail, Context, Result};
+use http::StatusCode;
+use memmap2::Mmap;
+use regex::Regex;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+
+// every file below `dir`, descending into subdirectories when `recursive` is set
+fn list_files(dir: &Path, recursive: bool, files: &mut Vec<String>) -> Result<()> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("reading directory {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("reading directory {}", dir.display()))?;
+        let file_type = entry.file_type()?;
+        if file_type.is_file() {
+            files.push(entry.path().to_string_lossy().into_owned());
+        } else if file_type.is_dir() && recursive {
+            list_files(&entry.path(), recursive, files)?;
+        }
+    }
+    Ok(())
+}
+
+// true for access logs which are fetched over HTTP(S)
+pub(crate) fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+// streams the body of `url`, the body is read as it is parsed and never held
+// in memory as a whole
+fn open_url(url: &str) -> Result<Box<dyn BufRead>> {
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("fetching {url}"))?;
+    Ok(Box::new(io::BufReader::new(
+        response.into_body().into_reader(),
+    )))
+}
+
+/// Expands the access log arguments. Directories are replaced by the files in
+/// them, including those in subdirectories if `recursive` is set, and glob
+/// patterns, like `logs/access.log*`, by the paths they match, both sorted by
+/// name. Object store URLs ending in `/`, like `s3://bucket/logs/`, are
+/// replaced by every object under that prefix. Other arguments, including `-`
+/// for STDIN and URLs, are kept.
+pub fn expand_paths(args: &[String], recursive: bool) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for arg in args {
+        let path = Path::new(arg);
+        if is_object_url(arg) && arg.ends_with('/') {
+            let objects = list_objects(arg)?;
+            if objects.is_empty() {
+                bail!("no objects under {arg}");
+            }
+            paths.extend(objects);
+        } else if is_url(arg) || is_object_url(arg) {
+            paths.push(arg.clone());
+        } else if path.is_dir() {
+            let mut files = Vec::new();
+            list_files(path, recursive, &mut files)?;
+            files.sort();
+            paths.extend(files);
+        } else if !path.exists() && arg.contains(['*', '?', '[']) {
+            let mut files = glob::glob(&normalize_pattern(arg))
+                .with_context(|| format!("invalid pattern {arg}"))?
+                .filter_map(|entry| entry.ok())
+                .filter(|path| path.is_file())
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect::<Vec<_>>();
+            if files.is_empty() {
+                bail!("no files match {arg}");
+            }
+            files.sort();
+            paths.extend(files);
+        } else {
+            paths.push(arg.clone());
+        }
+    }
+    Ok(paths)
+}
+
+/// Picks the log format of a file by its path, for trees of logs in
+/// different formats like support bundles
+#[derive(Debug, Clone, Default)]
+pub struct FormatMap {
+    rules: Vec<(glob::Pattern, LogType)>,
+}
+
+impl FormatMap {
+    /// Parses `pattern=format` rules, like `gorouter/**=gorouter`
+    pub fn parse(rules: &[String]) -> Result<FormatMap> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let (pattern, format) = rule.rsplit_once('=').ok_or_else(|| {
+                    anyhow!("invalid format mapping '{rule}', expected PATTERN=FORMAT")
+                })?;
+                let pattern = glob::Pattern::new(&normalize_pattern(pattern))
+                    .with_context(|| format!("invalid pattern in format mapping '{rule}'"))?;
+                let log_type = format
+                    .trim()
+                    .parse()
+                    .map_err(|e| anyhow!("invalid format in format mapping '{rule}': {e}"))?;
+                Ok((pattern, log_type))
+            })
+            .collect::<Result<_>>()?;
+        Ok(FormatMap { rules })
+    }
+
+    /// The format of the first rule matching `path`. A rule matches if its
+    /// pattern matches the whole path or the path without some of its
+    /// leading directories, so `gorouter/**` matches `bundle/gorouter/access.log`.
+    pub fn log_type(&self, path: &str) -> Option<LogType> {
+        self.rules
+            .iter()
+            .find(|(pattern, _)| matches_path(pattern, path))
+            .map(|(_, log_type)| *log_type)
+    }
+}
+
+// patterns are matched against paths with `/` separators, so patterns
+// written with Windows' `\` match too
+pub(crate) fn normalize_pattern(pattern: &str) -> String {
+    pattern.trim().replace('\\', "/")
+}
+
+// whether `pattern` matches the whole path or the path without some of its
+// leading directories
+pub(crate) fn matches_path(pattern: &glob::Pattern, path: &str) -> bool {
+    let path = path.replace('\\', "/");
+    pattern.matches(&path)
+        || path
+            .match_indices('/')
+            .any(|(i, _)| pattern.matches(&path[i + 1..]))
+}
+
+/// Settings for reading access logs
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// Don't report lines which can't be parsed, they're still counted
+    pub ignore_parse_errors: bool,
+    /// Only read the tar archive members whose path matches this pattern,
+    /// where `*` matches any characters. By default every file is read.
+    pub tar_members: Option<String>,
+    /// Formats to try, in order, on lines that don't parse in the format of
+    /// the file. For files with other lines mixed in, like platform logs.
+    pub fallback_formats: Vec<LogType>,
+    /// Lines matching this pattern continue the entry of the line before
+    /// them and are joined to it, separated by a space, before parsing
+    pub join_continuations: Option<Regex>,
+    /// Lines are the output of `cf logs`, only the gorouter (`[RTR/0]`) lines
+    /// are parsed, without the Loggregator prefix, and other lines are skipped
+    pub cf_logs: bool,
+    /// A prefix, like the timestamp a log shipper adds, removed from every
+    /// line before parsing. The pattern has to match at the start of the line.
+    pub strip_prefix: Option<Regex>,
+    /// Map local, uncompressed files into memory and parse their lines in
+    /// place, instead of copying every line into a new string
+    pub mmap: bool,
+}
+
+// the line without the part matched by `ReadOptions::strip_prefix`
+fn strip_prefix<'a>(line: &'a str, options: &ReadOptions) -> &'a str {
+    match &options.strip_prefix {
+        Some(pattern) => match pattern.find(line) {
+            Some(prefix) if prefix.start() == 0 => &line[prefix.end()..],
+            _ => line,
+        },
+        None => line,
+    }
+}
+
+// the message of a `cf logs` line from the router, like
+// `   2024-06-01T10:00:01.00+0000 [RTR/0] OUT <access log line>`
+fn strip_cf_logs_prefix(line: &str) -> Option<&str> {
+    let (_timestamp, rest) = line.trim_start().split_once(' ')?;
+    let (source, rest) = rest.trim_start().split_once(' ')?;
+    if !(source.starts_with("[RTR/") && source.ends_with(']')) {
+        return None;
+    }
+    let (stream, message) = rest.trim_start().split_once(' ')?;
+    matches!(stream, "OUT" | "ERR").then_some(message)
+}
+
+// joins continuation lines to the line before them. Every line is held back
+// until the next one shows whether it's complete.
+#[derive(Debug, Default)]
+struct Continuations {
+    pending: Option<String>,
+}
+
+impl Continuations {
+    // returns the entry completed by `line`, unless `line` continues it
+    fn push(&mut self, line: &str, pattern: &Regex) -> Option<String> {
+        match &mut self.pending {
+            Some(pending) if pattern.is_match(line) => {
+                pending.push(' ');
+                pending.push_str(line.trim_start());
+                None
+            }
+            _ => self.pending.replace(line.to_string()),
+        }
+    }
+
+    fn finish(&mut self) -> Option<String> {
+        self.pending.take()
+    }
+}
+
+// parses `line`, or with --join-continuations the entry `line` completes,
+// returns false if it can't be parsed
+fn parse_joined<F>(
+    line: &str,
+    continuations: &mut Continuations,
+    log_type: LogType,
+    options: &ReadOptions,
+    f: &mut F,
+) -> Result<bool>
+where
+    F: FnMut(LogEntry, &str) -> Result<()>,
+{
+    match &options.join_continuations {
+        Some(pattern) => match continuations.push(line, pattern) {
+            Some(entry) => parse_line(&entry, log_type, options, f),
+            None => Ok(true),
+        },
+        None => parse_line(line, log_type, options, f),
+    }
+}
+
+// wraps `reader` in a decoder when `name` has the extension of a supported
+// compression format or the first bytes match one
+fn decompress<'a>(mut reader: Box<dyn BufRead + 'a>, name: &str) -> Result<Box<dyn BufRead + 'a>> {
+    let compression = match Compression::from_extension(name) {
+        Some(compression) => Some(compression),
+        None => Compression::from_magic(reader.fill_buf()?),
+    };
+    match compression {
+        Some(compression) => Ok(Box::new(io::BufReader::new(compression.decoder(reader)?))),
+        None => Ok(reader),
+    }
+}
+
+// tar headers have the "ustar" magic at offset 257
+fn is_tar(reader: &mut dyn BufRead) -> Result<bool> {
+    Ok(reader.fill_buf()?.get(257..262) == Some(b"ustar".as_slice()))
+}
+
+// calls `f` with every line of the file or URL at `path`, or STDIN for `-`, or of
+// the matching members of a tar archive, until it returns false. The bytes and
+// lines read from a local file move `progress` along.
+fn for_each_line<F>(
+    path: &str,
+    options: &ReadOptions,
+    mut progress: Option<&mut Progress>,
+    mut f: F,
+) -> Result<()>
+where
+    F: FnMut(&str) -> Result<bool>,
+{
+    if options.mmap {
+        if let Some(map) = map_file(path)? {
+            map_lines(&map, progress, &mut f)?;
+            return Ok(());
+        }
+    }
+
+    let reader: Box<dyn BufRead> = if path.trim() == "-" {
+        Box::new(io::stdin().lock())
+    } else if is_url(path) {
+        open_url(path)?
+    } else if is_object_url(path) {
+        open_object(path)?
+    } else {
+        let file = fs::File::open(path).with_context(|| format!("opening {path}"))?;
+        match &progress {
+            // compressed files move the bar by the compressed bytes, like their size
+            Some(progress) => Box::new(io::BufReader::new(progress.wrap(file))),
+            None => Box::new(io::BufReader::new(file)),
+        }
+    };
+    // the query string of a URL isn't part of the file name
+    let name = path.split(['?', '#']).next().unwrap_or(path);
+    let mut reader = decompress(reader, name)?;
+
+    if !is_tar(&mut reader)? {
+        read_lines(reader, progress, &mut f)?;
+        return Ok(());
+    }
+
+    let mut archive = tar::Archive::new(reader);
+    for member in archive
+        .entries()
+        .with_context(|| format!("reading {path}"))?
+    {
+        let member = member.with_context(|| format!("reading {path}"))?;
+        if !member.header().entry_type().is_file() {
+            continue;
+        }
+        let name = member.path()?.to_string_lossy().into_owned();
+        if let Some(pattern) = &options.tar_members {
+            if !path_matches(pattern, &name) {
+                continue;
+            }
+        }
+
+        // members are often rotated, compressed logs themselves
+        let member = decompress(Box::new(io::BufReader::new(member)), &name)?;
+        if !read_lines(member, progress.as_deref_mut(), &mut f)
+            .with_context(|| format!("reading {name} in {path}"))?
+        {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// a line without what Windows tools add: the `\r` of CRLF line endings and
+// the byte order mark some editors write at the start of a file, which also
+// ends up in the middle of files concatenated from such files
+pub(crate) fn clean_line(line: &str) -> &str {
+    line.strip_prefix('\u{feff}')
+        .unwrap_or(line)
+        .trim_end_matches('\r')
+}
+
+// returns false if `f` asked to stop
+fn read_lines<R, F>(reader: R, mut progress: Option<&mut Progress>, f: &mut F) -> Result<bool>
+where
+    R: BufRead,
+    F: FnMut(&str) -> Result<bool>,
+{
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(msg) => {
+                diagnostics::emit(
+                    diagnostics::Kind::ReadError,
+                    &format!("Read failed: {msg:#?}"),
+                );
+                // a line that isn't UTF-8 can be skipped, anything else, like a
+                // truncated compressed file, would fail again on the next read
+                if msg.kind() == io::ErrorKind::InvalidData {
+                    continue;
+                }
+                break;
+            }
+        };
+
+        if let Some(progress) = progress.as_deref_mut() {
+            progress.line(None);
+        }
+        if !f(clean_line(&line))? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+// maps the file at `path` into memory, unless it's not a local file, empty,
+// compressed or a tar archive, which are read through `read_lines` instead
+fn map_file(path: &str) -> Result<Option<Mmap>> {
+    if path.trim() == "-" || is_url(path) || is_object_url(path) {
+        return Ok(None);
+    }
+    let file = fs::File::open(path).with_context(|| format!("opening {path}"))?;
+    let metadata = file.metadata()?;
+    if !metadata.is_file() || metadata.len() == 0 || Compression::from_extension(path).is_some() {
+        return Ok(None);
+    }
+
+    // SAFETY: the mapping is only read. A file truncated while it's mapped,
+    // like a log rotated with copytruncate, makes reading past its new end
+    // fail with SIGBUS, which is why mapping files is opt-in.
+    let map = unsafe { Mmap::map(&file) }.with_context(|| format!("mapping {path}"))?;
+    if Compression::from_magic(&map).is_some() || map.get(257..262) == Some(b"ustar".as_slice()) {
+        return Ok(None);
+    }
+    Ok(Some(map))
+}
+
+// like `read_lines` for the contents of a mapped file, the lines are slices of
+// the mapping. Returns false if `f` asked to stop.
+fn map_lines<F>(bytes: &[u8], mut progress: Option<&mut Progress>, f: &mut F) -> Result<bool>
+where
+    F: FnMut(&str) -> Result<bool>,
+{
+    if bytes.is_empty() {
+        return Ok(true);
+    }
+    let start = bytes.as_ptr() as usize;
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    for line in bytes.split(|b| *b == b'\n') {
+        if let Some(progress) = progress.as_deref_mut() {
+            // nothing is read, the bar moves to the end of the line instead
+            progress.line(Some((line.as_ptr() as usize - start + line.len()) as u64));
+        }
+        match std::str::from_utf8(line) {
+            Ok(line) => {
+                if !f(clean_line(line))? {
+                    return Ok(false);
+                }
+            }
+            // like with `read_lines`, a line that isn't UTF-8 is skipped
+            Err(msg) => {
+                diagnostics::emit(diagnostics::Kind::ReadError, &format!("Read failed: {msg}"))
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Calls `f` with every entry parsed from the access log at `path` and the
+/// line it was parsed from. `path` can also be an `http://` or `https://` URL
+/// or an object in S3, Google Cloud Storage or Azure, or STDIN if `path` is
+/// `-`. Gzip, zstd, bzip2 and xz compressed logs are decompressed on the fly
+/// and tar archives are read member by member. Lines that can't be parsed are
+/// reported, unless `ignore_parse_errors` is set, and counted in the returned
+/// total.
+pub fn read_log<F>(path: &str, log_type: LogType, options: &ReadOptions, mut f: F) -> Result<usize>
+where
+    F: FnMut(LogEntry, &str) -> Result<()>,
+{
+    let mut lines = 0;
+    let mut errors = 0;
+    let mut continuations = Continuations::default();
+    // only local files have a size to show the progress against
+    let mut progress = fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .and_then(|metadata| Progress::start(path, metadata.len()));
+    for_each_line(path, options, progress.as_mut(), |line| {
+        lines += 1;
+        if !parse_joined(line, &mut continuations, log_type, options, &mut f)? {
+            errors += 1;
+        }
+        Ok(true)
+    })?;
+    if let Some(entry) = continuations.finish() {
+        if !parse_line(&entry, log_type, options, &mut f)? {
+            errors += 1;
+        }
+    }
+
+    warn_unparsed(path, lines, errors, options);
+    Ok(errors)
+}
+
+// usually the access log lines are wrapped in something else when none of them parse
+pub(crate) fn warn_unparsed(path: &str, lines: usize, errors: usize, options: &ReadOptions) {
+    if lines > 0 && errors == lines && options.strip_prefix.is_none() {
+        diagnostics::emit(
+            diagnostics::Kind::Warning,
+            &format!(
+                "None of the lines of {path} could be parsed. If they have a prefix, like a timestamp added by a log shipper, remove it with --strip-prefix."
+            ),
+        );
+    }
+}
+
+// files are only split when every chunk has at least this many bytes
+const MIN_CHUNK_LEN: u64 = 8 * 1024 * 1024;
+
+// the length of the file at `path` and a reader at its start, if it's a local
+// file which is neither compressed nor a tar archive, so it can be read from
+// any offset
+fn open_plain(path: &str) -> Result<Option<(u64, io::BufReader<fs::File>)>> {
+    let len = match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => metadata.len(),
+        _ => return Ok(None),
+    };
+    if path.trim() == "-"
+        || is_url(path)
+        || is_object_url(path)
+        || Compression::from_extension(path).is_some()
+    {
+        return Ok(None);
+    }
+
+    let mut reader =
+        io::BufReader::new(fs::File::open(path).with_context(|| format!("opening {path}"))?);
+    if Compression::from_magic(reader.fill_buf()?).is_some() || is_tar(&mut reader)? {
+        return Ok(None);
+    }
+    Ok(Some((len, reader)))
+}
+
+// the length of the file at `path` with the byte ranges of its first and last
+// `bytes`, both starting at the beginning of a line, or the whole file if it's
+// not much larger. `None` for files `chunks` can't split either.
+pub(crate) fn sample_ranges(path: &str, bytes: u64) -> Result<Option<(u64, Vec<Range<u64>>)>> {
+    let Some((len, mut reader)) = open_plain(path)? else {
+        return Ok(None);
+    };
+    if len <= bytes * 2 {
+        return Ok(Some((len, vec![Range { start: 0, end: len }])));
+    }
+
+    let mut skipped = Vec::new();
+    reader.seek(io::SeekFrom::Start(bytes))?;
+    let head_end = bytes + reader.read_until(b'\n', &mut skipped)? as u64;
+    reader.seek(io::SeekFrom::Start(len - bytes))?;
+    skipped.clear();
+    let tail_start = (len - bytes + reader.read_until(b'\n', &mut skipped)? as u64).max(head_end);
+    Ok(Some((len, vec![0..head_end, tail_start..len])))
+}
+
+// splits the file at `path` into up to `count` byte ranges which start at the
+// beginning of a line. Only local files which are neither compressed nor a tar
+// archive can be split, and only when continuation lines aren't joined, as
+// an entry could span two chunks.
+pub(crate) fn chunks(path: &str, options: &ReadOptions, count: usize) -> Result<Vec<Range<u64>>> {
+    let Some((len, mut reader)) = open_plain(path)? else {
+        return Ok(Vec::new());
+    };
+    let count = (len / MIN_CHUNK_LEN).min(count as u64);
+    if count < 2 || options.join_continuations.is_some() {
+        return Ok(Vec::new());
+    }
+
+    let mut starts = vec![0];
+    let mut skipped = Vec::new();
+    for i in 1..count {
+        let start = (len * i / count).max(*starts.last().unwrap_or(&0));
+        reader.seek(io::SeekFrom::Start(start))?;
+        // the chunk starts after the end of the line it would start in
+        skipped.clear();
+        let start = start + reader.read_until(b'\n', &mut skipped)? as u64;
+        if start < len && starts.last() != Some(&start) {
+            starts.push(start);
+        }
+    }
+    starts.push(len);
+    Ok(starts.windows(2).map(|w| w[0]..w[1]).collect())
+}
+
+/// Like `read_log`, for the lines in `range` of a file split by `chunks`.
+/// Returns the number of lines read and how many of them couldn't be parsed.
+pub(crate) fn read_chunk<F>(
+    path: &str,
+    range: Range<u64>,
+    log_type: LogType,
+    options: &ReadOptions,
+    mut f: F,
+) -> Result<(usize, usize)>
+where
+    F: FnMut(LogEntry, &str) -> Result<()>,
+{
+    let mut lines = 0;
+    let mut errors = 0;
+    let mut parse = |line: &str| {
+        lines += 1;
+        if !parse_line(line, log_type, options, &mut f)? {
+            errors += 1;
+        }
+        Ok(true)
+    };
+
+    let mut progress = Progress::start(path, range.end - range.start);
+    // chunks are only made of files `map_file` maps too
+    match options.mmap.then(|| map_file(path)).transpose()?.flatten() {
+        Some(map) => {
+            let end = (range.end as usize).min(map.len());
+            map_lines(
+                &map[(range.start as usize).min(end)..end],
+                progress.as_mut(),
+                &mut parse,
+            )?;
+        }
+        None => {
+            let mut file = fs::File::open(path).with_context(|| format!("opening {path}"))?;
+            file.seek(io::SeekFrom::Start(range.start))?;
+            let chunk = file.take(range.end - range.start);
+            let chunk: Box<dyn Read> = match &progress {
+                Some(progress) => Box::new(progress.wrap(chunk)),
+                None => Box::new(chunk),
+            };
+            read_lines(io::BufReader::new(chunk), progress.as_mut(), &mut parse)?;
+        }
+    }
+    Ok((lines, errors))
+}
+
+// the first `count` lines of the access log at `path` which aren't blank
+pub(crate) fn first_lines(path: &str, options: &ReadOptions, count: usize) -> Result<Vec<String>> {
+    let mut lines = Vec::new();
+    if count == 0 {
+        return Ok(lines);
+    }
+    for_each_line(path, options, None, |line| {
+        if !line.trim().is_empty() {
+            lines.push(line.to_string());
+        }
+        Ok(lines.len() < count)
+    })?;
+    Ok(lines)
+}
+
+// the number of lines `detect_format` looks at
+const DETECT_SAMPLE_LINES: usize = 100;
+
+/// Tries every log format on the first lines of the access log at `path` and
+/// returns the one that parses the most of them, together with the share of
+/// lines it parsed. More specific formats win ties, so a file that parses as
+/// both combined and common is combined. Returns `None` if no format fits.
+pub fn detect_format(path: &str, options: &ReadOptions) -> Result<Option<(LogType, f64)>> {
+    let mut sample = Vec::new();
+    for_each_line(path, options, None, |line| {
+        let line = strip_prefix(line, options);
+        if !line.trim().is_empty() {
+            sample.push(line.to_string());
+        }
+        Ok(sample.len() < DETECT_SAMPLE_LINES)
+    })?;
+
+    let mut best: Option<(LogType, usize)> = None;
+    for log_type in [
+        LogType::GorouterLog,
+        LogType::CloudControllerLog,
+        LogType::CombinedLog,
+        LogType::CommonLog,
+    ] {
+        let parsed = sample
+            .iter()
+            .filter(|line| access_log_parser::parse(log_type, line).is_ok())
+            .count();
+        if parsed > best.map_or(0, |(_, count)| count) {
+            best = Some((log_type, parsed));
+        }
+    }
+    Ok(best.map(|(log_type, parsed)| (log_type, parsed as f64 / sample.len() as f64)))
+}
+
+// where the status code is, it follows the quoted request in every format
+fn status_span(line: &str) -> Option<Range<usize>> {
+    let request = line.find("] \"")? + 3;
+    let start = request + line[request..].find("\" ")? + 2;
+    let end = line[start..].find(' ').map_or(line.len(), |i| start + i);
+    Some(start..end)
+}
+
+// the status code of an access log line as logged, like `200`, `"-"` or `000`
+pub(crate) fn status_field(line: &str) -> Option<&str> {
+    status_span(line).map(|span| &line[span])
+}
+
+// where the request is, without the quotes
+pub(crate) fn request_span(line: &str) -> Option<Range<usize>> {
+    let start = line.find("] \"")? + 3;
+    let len = line[start..].find("\" ")?;
+    Some(start..start + len)
+}
+
+// the request of an access log line as logged, without the quotes, like
+// `GET / HTTP/1.1`
+pub(crate) fn request_field(line: &str) -> Option<&str> {
+    request_span(line).map(|span| &line[span])
+}
+
+// the parser rejects lines with a status code outside of 100 to 999, like
+// `000`, the line with `"-"` in its place parses as a line without a status
+fn with_missing_status(line: &str) -> Option<String> {
+    let span = status_span(line)?;
+    let status = &line[span.clone()];
+    if status == "\"-\""
+        || status
+            .parse::<u16>()
+            .is_ok_and(|code| StatusCode::from_u16(code).is_ok())
+    {
+        return None;
+    }
+    Some(format!("{}\"-\"{}", &line[..span.start], &line[span.end..]))
+}
+
+// passes the parsed line to `f`, returns false if the line can't be parsed.
+// Skipped `cf logs` lines count as parsed.
+// Lines with an invalid status code are passed on as if it was missing.
+pub(crate) fn parse_line<F>(
+    line: &str,
+    log_type: LogType,
+    options: &ReadOptions,
+    f: &mut F,
+) -> Result<bool>
+where
+    F: FnMut(LogEntry, &str) -> Result<()>,
+{
+    let line = strip_prefix(line, options);
+    let line = if options.cf_logs {
+        match strip_cf_logs_prefix(line) {
+            Some(message) => message,
+            // app logs, staging output and headers aren't access log lines
+            None => return Ok(true),
+        }
+    } else {
+        line
+    };
+    let parsed = access_log_parser::parse(log_type, line).or_else(|err| {
+        options
+            .fallback_formats
+            .iter()
+            .find_map(|fallback| access_log_parser::parse(*fallback, line).ok())
+            .ok_or(err)
+    });
+    let repaired;
+    let parsed = match parsed {
+        Err(err) => match with_missing_status(line) {
+            Some(line) => {
+                repaired = line;
+                access_log_parser::parse(log_type, &repaired).map_err(|_| err)
+            }
+            None => Err(err),
+        },
+        parsed => parsed,
+    };
+
+    match parsed {
+        Ok(log) => {
+            f(log, line)?;
+            Ok(true)
+        }
+        // the error of the file's own format is the most useful
+        Err(err) => {
+            if !options.ignore_parse_errors {
+                let message = if diagnostics::is_structured() {
+                    let AccessLogError::ParseError { msg } = err;
+                    format!("line '{line}': {msg}")
+                } else {
+                    format!("Parse error: {err:#?} with line '{line}'")
+                };
+                diagnostics::emit(diagnostics::Kind::ParseError, &message);
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// Reads the lines piped into STDIN as they arrive, for pipelines like
+/// `kubectl logs -f`. A thread reads ahead at most `buffer_lines` lines and
+/// then stops reading until they're taken, so the pipe fills up and the
+/// program writing to it waits, instead of the lines piling up in memory.
+pub struct StdinFollower {
+    lines: mpsc::Receiver<String>,
+    buffer_lines: usize,
+    continuations: Continuations,
+}
+
+impl StdinFollower {
+    pub fn spawn(buffer_lines: usize) -> StdinFollower {
+        let buffer_lines = buffer_lines.max(1);
+        let (sender, lines) = mpsc::sync_channel(buffer_lines);
+        thread::spawn(move || {
+            let mut stdin = io::stdin().lock();
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                match stdin.read_until(b'\n', &mut line) {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        let line = String::from_utf8_lossy(&line);
+                        // blocks while the buffer is full, the backpressure
+                        if sender
+                            .send(clean_line(line.trim_end_matches('\n')).to_string())
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => {
+                        diagnostics::emit(
+                            diagnostics::Kind::ReadError,
+                            &format!("reading STDIN: {err}"),
+                        );
+                        break;
+                    }
+                }
+            }
+        });
+
+        StdinFollower {
+            lines,
+            buffer_lines,
+            continuations: Continuations::default(),
+        }
+    }
+
+    /// Calls `f` with every entry, and its line, parsed from the lines read
+    /// since the last call and returns the number of lines that couldn't be
+    /// parsed. At most `buffer_lines` lines are taken per call, so a fast
+    /// producer can't hold up the report.
+    pub fn read_new<F>(
+        &mut self,
+        log_type: LogType,
+        options: &ReadOptions,
+        mut f: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(LogEntry, &str) -> Result<()>,
+    {
+        let mut errors = 0;
+        for _ in 0..self.buffer_lines {
+            match self.lines.try_recv() {
+                Ok(line) => {
+                    if !parse_joined(&line, &mut self.continuations, log_type, options, &mut f)? {
+                        errors += 1;
+                    }
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                // nothing follows the last entry once STDIN is closed
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    if let Some(entry) = self.continuations.finish() {
+                        if !parse_line(&entry, log_type, options, &mut f)? {
+                            errors += 1;
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+        Ok(errors)
+    }
+}
+
+/// Reads the lines appended to an access log while it is being written, like
+/// `tail -f`. A truncated file is read again from the start and a file that
+/// was replaced, by log rotation for example, is reopened.
+pub struct LogFollower {
+    path: String,
+    reader: io::BufReader<fs::File>,
+    // the end of the file without a new line yet
+    partial: String,
+    // the last entry, until the next line shows it has no continuation lines
+    continuations: Continuations,
+}
+
+impl LogFollower {
+    pub fn open(path: &str) -> Result<LogFollower> {
+        if is_url(path) || is_object_url(path) {
+            bail!("can't follow {path}, only files can be followed");
+        }
+        Ok(LogFollower {
+            path: path.to_string(),
+            reader: io::BufReader::new(
+                fs::File::open(path).with_context(|| format!("opening {path}"))?,
+            ),
+            partial: String::new(),
+            continuations: Continuations::default(),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Calls `f` with every entry, and its line, parsed from the complete lines
+    /// added since the last call and returns the number of lines that couldn't be parsed
+    pub fn read_new<F>(
+        &mut self,
+        log_type: LogType,
+        options: &ReadOptions,
+        mut f: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(LogEntry, &str) -> Result<()>,
+    {
+        let mut errors = 0;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match self.reader.read_line(&mut line) {
+                Ok(0) => {
+                    let (restarted, flushed_errors) = self.restart(log_type, options, &mut f)?;
+                    errors += flushed_errors;
+                    if restarted {
+                        continue;
+                    }
+                    break;
+                }
+                Ok(_) if !line.ends_with('\n') => {
+                    self.partial.push_str(&line);
+                    break;
+                }
+                Ok(_) => {
+                    let line = std::mem::take(&mut self.partial) + line.trim_end_matches('\n');
+                    if !parse_joined(
+                        clean_line(&line),
+                        &mut self.continuations,
+                        log_type,
+                        options,
+                        &mut f,
+                    )? {
+                        errors += 1;
+                    }
+                }
+                Err(msg) if msg.kind() == io::ErrorKind::InvalidData => {
+                    diagnostics::emit(
+                        diagnostics::Kind::ReadError,
+                        &format!("Read failed: {msg:#?}"),
+                    );
+                }
+                Err(msg) => {
+                    return Err(msg).with_context(|| format!("reading {}", self.path));
+                }
+            }
+        }
+        Ok(errors)
+    }
+
+    // at the end of the file, starts over if the file was truncated or
+    // replaced. Returns whether it did and the number of lines of the old
+    // file that couldn't be parsed, see `flush`.
+    fn restart<F>(
+        &mut self,
+        log_type: LogType,
+        options: &ReadOptions,
+        f: &mut F,
+    ) -> Result<(bool, usize)>
+    where
+        F: FnMut(LogEntry, &str) -> Result<()>,
+    {
+        let current = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata,
+            // in the middle of a rotation, the new file will show up later
+            Err(_) => return Ok((false, 0)),
+        };
+        let position = self.reader.stream_position()?;
+
+        if !same_file(&self.reader.get_ref().metadata()?, &current) {
+            let errors = self.flush(log_type, options, f)?;
+            *self = LogFollower::open(&self.path)?;
+            Ok((true, errors))
+        } else if current.len() < position {
+            let errors = self.flush(log_type, options, f)?;
+            self.reader.seek(io::SeekFrom::Start(0))?;
+            Ok((true, errors))
+        } else {
+            Ok((false, 0))
+        }
+    }
+
+    // parses the last line of a file that's been rotated or truncated, even
+    // without a new line, and the entry held back for continuation lines, as
+    // nothing is added to them anymore. Returns the number of lines that
+    // couldn't be parsed.
+    fn flush<F>(&mut self, log_type: LogType, options: &ReadOptions, f: &mut F) -> Result<usize>
+    where
+        F: FnMut(LogEntry, &str) -> Result<()>,
+    {
+        let mut errors = 0;
+        let partial = std::mem::take(&mut self.partial);
+        if !partial.trim().is_empty()
+            && !parse_joined(
+                clean_line(&partial),
+                &mut self.continuations,
+                log_type,
+                options,
+                f,
+            )?
+        {
+            errors += 1;
+        }
+        if let Some(entry) = self.continuations.finish() {
+            if !parse_line(&entry, log_type, options, f)? {
+                errors += 1;
+            }
+        }
+        Ok(errors)
+    }
+}
+
+#[cfg(unix)]
+fn same_file(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+// without inodes a replaced file is only noticed when it is smaller
+#[cfg(not(unix))]
+fn same_file(_: &fs::Metadata, _: &fs::Metadata) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the lines `read_lines` and `map_lines` pass on for `text`
+    fn lines(text: &str) -> (Vec<String>, Vec<String>) {
+        let mut read = Vec::new();
+        read_lines(io::Cursor::new(text), None, &mut |line: &str| {
+            read.push(line.to_string());
+            Ok(true)
+        })
+        .unwrap();
+        let mut mapped = Vec::new();
+        map_lines(text.as_bytes(), None, &mut |line: &str| {
+            mapped.push(line.to_string());
+            Ok(true)
+        })
+        .unwrap();
+        (read, mapped)
+    }
+
+    #[test]
+    fn clean_line_removes_crlf() {
+        assert_eq!(clean_line("GET / HTTP/1.1\r"), "GET / HTTP/1.1");
+        assert_eq!(clean_line("GET / HTTP/1.1"), "GET / HTTP/1.1");
+    }
+
+    #[test]
+    fn clean_line_removes_leading_bom() {
+        assert_eq!(clean_line("\u{feff}GET / HTTP/1.1\r"), "GET / HTTP/1.1");
+    }
+
+    #[test]
+    fn crlf_lines_are_read_without_cr() {
+        let expected = vec!["first".to_string(), "second".to_string()];
+        assert_eq!(lines("first\r\nsecond\r\n"), (expected.clone(), expected));
+    }
+
+    #[test]
+    fn bom_of_concatenated_files_is_removed() {
+        let expected = vec![
+            "first".to_string(),
+            "second".to_string(),
+            "third".to_string(),
+        ];
+        assert_eq!(
+            lines("\u{feff}first\r\nsecond\r\n\u{feff}third\r\n"),
+            (expected.clone(), expected)
+        );
+    }
+
+    #[test]
+    fn windows_patterns_match_paths() {
+        let pattern = glob::Pattern::new(&normalize_pattern("gorouter\\**")).unwrap();
+        assert!(matches_path(&pattern, "bundle/gorouter/access.log"));
+        assert!(matches_path(&pattern, "bundle\\gorouter\\access.log"));
+        assert!(!matches_path(&pattern, "bundle/nginx/access.log"));
+    }
+
+    #[test]
+    fn windows_globs_are_expanded() {
+        let dir = std::env::temp_dir().join(format!("top-logs-reader-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["access.log", "access.log.1", "error.log"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+
+        let dir_name = dir.to_string_lossy().into_owned();
+        let paths = expand_paths(&[format!("{dir_name}\\access.log*")], false);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            paths.unwrap(),
+            vec![
+                format!("{dir_name}/access.log"),
+                format!("{dir_name}/access.log.1")
+            ]
+        );
+    }
+}
@@ -0,0 +1,1400 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::breakdown::BreakdownReport;
+use crate::cluster;
+use crate::concentration::Concentration;
+use crate::drill::DrillReport;
+use crate::extract::ExtractReport;
+use crate::forecast;
+use crate::layout::{self, Column, NumberFormat, SectionLayout};
+use crate::outlier::{OutlierReport, Outliers, SlowestReport};
+use crate::profile::Deviation;
+use crate::segment::SegmentReport;
+use crate::slices::{SliceReport, Slices};
+use crate::timeline::TimelineReport;
+use crate::{
+    path_matches, DefaultHashMap, Latencies, LogDuration, ReportOptions, Section, Session,
+    SortOrder, Stats, TimeHistogram, TimeResolution, TopInfo, UniqueCount, Visit,
+};
+use chrono::Duration;
+use http::StatusCode;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::IpAddr;
+
+/// The summary of a `TopInfo`, independent of how it is displayed
+#[derive(Debug, Serialize)]
+pub struct Report {
+    /// Labels given with `TopInfo::set_tags`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+    /// Unknown without any requests
+    pub duration: Option<LogDuration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<Coverage>,
+    /// The requests over the log duration, see `TopInfo::sparkline`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sparkline: Option<String>,
+    pub total_requests: usize,
+    pub errors: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filtered: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unique: Option<Unique>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub concentration: Option<Concentration>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statistics: Option<Statistics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub headline: Option<Headline>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment: Option<SegmentReport>,
+    pub sections: Vec<ReportSection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outliers: Option<OutlierReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slowest: Option<SlowestReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slices: Option<SliceReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub timelines: Vec<TimelineReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extracts: Vec<ExtractReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub breakdowns: Vec<BreakdownReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deviations: Vec<Deviation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub drills: Vec<DrillReport>,
+}
+
+/// The number of distinct values of a few fields
+#[derive(Debug, Serialize)]
+pub struct Unique {
+    pub client_ips: Cardinality,
+    /// Without query params
+    pub paths: Cardinality,
+    pub user_agents: Cardinality,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Cardinality {
+    pub count: usize,
+    /// The count is a HyperLogLog estimate, small inputs are counted exactly
+    pub estimated: bool,
+}
+
+impl From<&UniqueCount> for Cardinality {
+    fn from(unique: &UniqueCount) -> Self {
+        Cardinality {
+            count: unique.count(),
+            estimated: unique.is_estimate(),
+        }
+    }
+}
+
+impl Unique {
+    /// Label and display value of each count, estimates start with a `~`
+    pub fn items(&self) -> Vec<(&'static str, String)> {
+        let value = |c: &Cardinality| {
+            if c.estimated {
+                format!("~{}", c.count)
+            } else {
+                c.count.to_string()
+            }
+        };
+        vec![
+            ("Client IPs", value(&self.client_ips)),
+            ("Paths", value(&self.paths)),
+            ("User Agents", value(&self.user_agents)),
+        ]
+    }
+}
+
+/// Descriptive statistics of the durations and sizes of every counted request
+#[derive(Debug, Serialize)]
+pub struct Statistics {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_time: Option<TimeStatistics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gorouter_time: Option<TimeStatistics>,
+    /// Bytes of the response bodies
+    pub bytes_sent: u64,
+    /// Bytes of the request bodies, only known for gorouter logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_received: Option<u64>,
+}
+
+/// In seconds
+#[derive(Debug, Serialize)]
+pub struct TimeStatistics {
+    pub count: usize,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    pub stddev: f64,
+}
+
+impl TimeStatistics {
+    // rounded to milliseconds
+    fn new(stats: &Stats) -> Option<TimeStatistics> {
+        Some(TimeStatistics {
+            count: stats.count(),
+            mean: round3(stats.mean()?),
+            min: round3(stats.min()?),
+            max: round3(stats.max()?),
+            stddev: round3(stats.stddev()?),
+        })
+    }
+}
+
+impl Statistics {
+    /// The duration statistics, by the field name used in metrics
+    pub fn times(&self) -> Vec<(&'static str, &TimeStatistics)> {
+        vec![
+            ("response_time", &self.response_time),
+            ("gorouter_time", &self.gorouter_time),
+        ]
+        .into_iter()
+        .filter_map(|(name, stats)| Some((name, stats.as_ref()?)))
+        .collect()
+    }
+
+    /// Label and display value of each statistic
+    pub fn items(&self) -> Vec<(&'static str, String)> {
+        let time = |stats: &TimeStatistics| {
+            format!(
+                "mean {}s, min {}s, max {}s, stddev {}s of {} requests",
+                stats.mean, stats.min, stats.max, stats.stddev, stats.count
+            )
+        };
+        let mut items = Vec::new();
+        if let Some(stats) = &self.response_time {
+            items.push(("Response Time", time(stats)));
+        }
+        if let Some(stats) = &self.gorouter_time {
+            items.push(("Gorouter Time", time(stats)));
+        }
+        items.push(("Bytes Sent", self.bytes_sent.to_string()));
+        if let Some(bytes) = self.bytes_received {
+            items.push(("Bytes Received", bytes.to_string()));
+        }
+        items
+    }
+}
+
+/// How much of the log duration had traffic
+#[derive(Debug, Serialize)]
+pub struct Coverage {
+    /// From the oldest to the newest log entry
+    pub seconds: i64,
+    /// Average over the covered time
+    pub requests_per_second: f64,
+    /// Minutes with at least one request
+    pub active_minutes: usize,
+    /// Every minute from the oldest to the newest log entry
+    pub minutes: usize,
+    /// Percentage of the minutes with at least one request
+    pub active_percent: f64,
+}
+
+/// A quick verdict on the logs, computed from every counted request rather
+/// than just the selected sections
+#[derive(Debug, Serialize)]
+pub struct Headline {
+    /// Percentage of requests with a 5xx response
+    pub error_rate: f64,
+    pub server_errors: usize,
+    /// In seconds, only known for log formats that record response times
+    pub p95_response_time: Option<f64>,
+    pub busiest_minute: Option<ReportRow>,
+    /// The path with the most 5xx responses
+    pub top_error_path: Option<ReportRow>,
+    pub top_client: Option<ReportRow>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportSection {
+    pub section: Section,
+    pub title: String,
+    pub rows: Vec<ReportRow>,
+    /// Set from `ReportOptions::layouts`
+    #[serde(skip)]
+    pub layout: Option<SectionLayout>,
+    /// Set from `ReportOptions::numbers`
+    #[serde(skip)]
+    pub numbers: NumberFormat,
+    /// Set from `ReportOptions::bars`
+    #[serde(skip)]
+    pub bars: bool,
+    /// Set from `TopInfo::time_resolution`, for the unit of the time buckets
+    #[serde(skip)]
+    pub resolution: TimeResolution,
+}
+
+/// A single table row. Histogram buckets also carry their range, `from`
+/// inclusive and `to` exclusive, response and gorouter time buckets in the
+/// unit of the `TimeResolution`. Time series rows their response time
+/// percentiles in seconds, forecast rows their confidence band and funnel
+/// steps their conversion.
+#[derive(Debug, Serialize)]
+pub struct ReportRow {
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<usize>,
+    pub count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p50: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p95: Option<f64>,
+    /// Percentage of the previous funnel step, or of the requests of the
+    /// client for error client IPs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percent: Option<f64>,
+    /// Lower bound of the 95% confidence band of a forecast
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub low: Option<f64>,
+    /// Upper bound of the 95% confidence band of a forecast
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub high: Option<f64>,
+    /// Requests with a 5xx and with a 4xx response, of the error rates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_errors: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_errors: Option<usize>,
+    /// Megabytes of the response bodies, of the bandwidth
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub megabytes: Option<f64>,
+}
+
+impl ReportSection {
+    /// The columns the table, Markdown and HTML output show, in order. By
+    /// default that's key, count and any other column with values.
+    pub fn columns(&self) -> Vec<Column> {
+        layout::columns(
+            self.section,
+            self.layout.as_ref(),
+            &self.numbers,
+            self.resolution,
+            &self.rows,
+        )
+    }
+
+    /// True if the section has more than the key and count columns or a
+    /// layout, then tables get a header row
+    pub fn has_header(&self) -> bool {
+        self.layout.is_some() || self.columns().len() > 2
+    }
+}
+
+impl Report {
+    /// The log duration for display, like `2024-06-01 10:00:01 +00:00 to 2024-06-01 11:14:52 +00:00`
+    pub fn duration_label(&self) -> String {
+        match &self.duration {
+            Some(duration) => format!("{} to {}", duration.start, duration.end),
+            None => "n/a, no requests were counted".into(),
+        }
+    }
+}
+
+impl Coverage {
+    /// Label and display value of each item, in display order
+    pub fn items(&self) -> Vec<(&'static str, String)> {
+        let (hours, minutes, seconds) = (
+            self.seconds / 3600,
+            self.seconds / 60 % 60,
+            self.seconds % 60,
+        );
+        let covered = match (hours, minutes) {
+            (0, 0) => format!("{seconds}s"),
+            (0, _) => format!("{minutes}m {seconds}s"),
+            _ => format!("{hours}h {minutes}m {seconds}s"),
+        };
+        vec![
+            ("Covered Time", covered),
+            (
+                "Average Rate",
+                format!("{} requests/s", self.requests_per_second),
+            ),
+            (
+                "Active Minutes",
+                format!(
+                    "{} of {} ({}%)",
+                    self.active_minutes, self.minutes, self.active_percent
+                ),
+            ),
+        ]
+    }
+}
+
+impl Headline {
+    /// Label and display value of each headline item, in display order
+    pub fn items(&self) -> Vec<(&'static str, String)> {
+        let row = |row: &Option<ReportRow>| match row {
+            Some(row) => format!("{} ({} requests)", row.key, row.count),
+            None => "n/a".into(),
+        };
+
+        vec![
+            (
+                "Error Rate",
+                format!(
+                    "{}% ({} 5xx responses)",
+                    self.error_rate, self.server_errors
+                ),
+            ),
+            (
+                "p95 Response Time",
+                self.p95_response_time
+                    .map(|t| format!("{t}s"))
+                    .unwrap_or_else(|| "n/a".into()),
+            ),
+            ("Busiest Minute", row(&self.busiest_minute)),
+            ("Top Error Path", row(&self.top_error_path)),
+            ("Top Client", row(&self.top_client)),
+        ]
+    }
+}
+
+impl ReportRow {
+    pub(crate) fn new(key: String, count: usize) -> ReportRow {
+        ReportRow {
+            key,
+            from: None,
+            to: None,
+            count,
+            p50: None,
+            p95: None,
+            percent: None,
+            low: None,
+            high: None,
+            server_errors: None,
+            client_errors: None,
+            megabytes: None,
+        }
+    }
+
+    fn bucket(from: usize, to: usize, count: usize) -> ReportRow {
+        ReportRow {
+            key: format!("{from} to {to}"),
+            from: Some(from),
+            to: Some(to),
+            count,
+            p50: None,
+            p95: None,
+            percent: None,
+            low: None,
+            high: None,
+            server_errors: None,
+            client_errors: None,
+            megabytes: None,
+        }
+    }
+
+    fn percentiles(key: String, count: usize, latencies: &Latencies) -> ReportRow {
+        ReportRow {
+            p50: latencies.percentile(50.0),
+            p95: latencies.percentile(95.0),
+            ..ReportRow::new(key, count)
+        }
+    }
+}
+
+/// `value` rounded to three decimal places, like every fraction of a report,
+/// which also hides the last digits that depend on the order the requests
+/// were counted in
+pub(crate) fn round3(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+/// `count` as a percentage of `total`, rounded like `round3`, 0 without a total
+pub(crate) fn percent(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        (count as f64 * 100_000.0 / total as f64).round() / 1000.0
+    }
+}
+
+pub(crate) fn top_row<'a, I, K>(iter: I) -> Option<ReportRow>
+where
+    K: ToString,
+    I: Iterator<Item = (K, &'a usize)>,
+{
+    top_rows(iter, &SortOrder::ByValue, 1).pop()
+}
+
+pub(crate) fn top_rows<'a, I, K>(iter: I, sort_order: &SortOrder, max: usize) -> Vec<ReportRow>
+where
+    K: ToString,
+    I: Iterator<Item = (K, &'a usize)>,
+{
+    crate::top::select(
+        iter.map(|(key, val)| (Cow::Owned(key.to_string()), *val)),
+        *sort_order,
+        None,
+        max,
+    )
+    .into_iter()
+    .map(|(key, val)| ReportRow::new(key.into_owned(), val))
+    .collect()
+}
+
+// the status code with its reason phrase, including the common codes proxies and
+// load balancers use which aren't part of the HTTP spec
+pub(crate) fn status_label(code: &StatusCode) -> String {
+    let reason = code.canonical_reason().or(match code.as_u16() {
+        444 => Some("No Response"),
+        460 => Some("Client Closed Connection"),
+        463 => Some("Too Many Forwarded IPs"),
+        494 => Some("Request Header Too Large"),
+        495 => Some("SSL Certificate Error"),
+        496 => Some("SSL Certificate Required"),
+        497 => Some("HTTP Request Sent to HTTPS Port"),
+        499 => Some("Client Closed Request"),
+        520 => Some("Web Server Returned an Unknown Error"),
+        521 => Some("Web Server Is Down"),
+        522 => Some("Connection Timed Out"),
+        523 => Some("Origin Is Unreachable"),
+        524 => Some("A Timeout Occurred"),
+        525 => Some("SSL Handshake Failed"),
+        526 => Some("Invalid SSL Certificate"),
+        561 => Some("Unauthorized"),
+        _ => None,
+    });
+    match reason {
+        Some(reason) => format!("{} {}", code.as_str(), reason),
+        None => code.as_str().to_string(),
+    }
+}
+
+// adds the count of values that couldn't be read as an `invalid` entry
+fn with_invalid<'a>(
+    iter: impl Iterator<Item = (String, &'a usize)>,
+    invalid: &'a usize,
+) -> impl Iterator<Item = (String, &'a usize)> {
+    iter.chain((*invalid > 0).then(|| ("invalid".to_string(), invalid)))
+}
+
+// groups adjacent buckets together until they hold at least `threshold` requests,
+// requests without a time are reported separately
+fn bucket_rows(
+    times: &TimeHistogram,
+    threshold: usize,
+    resolution: TimeResolution,
+) -> Vec<ReportRow> {
+    let buckets = times.buckets(resolution);
+    let max_key = buckets.keys().next_back().copied().unwrap_or(0);
+
+    let mut rows = Vec::new();
+    let mut bucket_val: usize = 0;
+    let mut bucket_start: Option<usize> = None;
+
+    for (key, count) in buckets {
+        let start = *bucket_start.get_or_insert(key);
+
+        bucket_val += count;
+
+        if bucket_val >= threshold {
+            rows.push(ReportRow::bucket(start, resolution.end(key), bucket_val));
+            bucket_start = None;
+            bucket_val = 0;
+        }
+    }
+
+    if let Some(start) = bucket_start {
+        rows.push(ReportRow::bucket(
+            start,
+            resolution.end(max_key),
+            bucket_val,
+        ));
+    }
+
+    if times.untimed() > 0 {
+        rows.push(ReportRow::new("<none>".into(), times.untimed()));
+    }
+
+    rows
+}
+
+// bucket boundaries of the session histograms, the last bucket is open ended
+const SESSION_DEPTHS: [usize; 7] = [2, 3, 5, 10, 20, 50, 100];
+const SESSION_DURATIONS: [usize; 8] = [0, 10, 30, 60, 300, 900, 1800, 3600];
+
+// bucket boundaries of the request body sizes in bytes, the last bucket is open ended
+pub(crate) const REQUEST_SIZES: [usize; 6] = [0, 1_000, 10_000, 100_000, 1_000_000, 10_000_000];
+
+// the requests of every bucket of `REQUEST_SIZES`
+fn request_size_rows(sizes: &DefaultHashMap<usize, usize>) -> Vec<ReportRow> {
+    REQUEST_SIZES
+        .iter()
+        .enumerate()
+        .map(|(i, from)| match REQUEST_SIZES.get(i + 1) {
+            Some(to) => ReportRow::bucket(*from, *to, *sizes.get(from)),
+            None => ReportRow::new(format!("{from}+"), *sizes.get(from)),
+        })
+        .collect()
+}
+
+// bucket boundaries of the clients by number of requests, the last bucket is open ended
+const CLIENT_REQUESTS: [usize; 5] = [1, 2, 11, 101, 1001];
+
+// the number of clients in each bucket of `CLIENT_REQUESTS`
+fn client_request_rows(client_ips: &DefaultHashMap<IpAddr, usize>) -> Vec<ReportRow> {
+    let mut counts = vec![0; CLIENT_REQUESTS.len()];
+    for requests in client_ips.values() {
+        if let Some(i) = CLIENT_REQUESTS.iter().rposition(|bound| bound <= requests) {
+            counts[i] += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| match CLIENT_REQUESTS.get(i + 1) {
+            Some(to) => ReportRow::bucket(CLIENT_REQUESTS[i], *to, count),
+            None => ReportRow::new(format!("{}+", CLIENT_REQUESTS[i]), count),
+        })
+        .collect()
+}
+
+// single request sessions get their own row, the others are counted in the
+// bucket their value falls into
+fn session_rows<F>(sessions: &[Session], bounds: &[usize], value: F) -> Vec<ReportRow>
+where
+    F: Fn(&Session) -> usize,
+{
+    let mut counts = vec![0; bounds.len()];
+    let mut bounces = 0;
+    for session in sessions {
+        if session.is_bounce() {
+            bounces += 1;
+            continue;
+        }
+        let value = value(session);
+        if let Some(i) = bounds.iter().rposition(|bound| *bound <= value) {
+            counts[i] += 1;
+        }
+    }
+
+    let mut rows = vec![ReportRow::new("bounce (1 request)".into(), bounces)];
+    for (i, count) in counts.into_iter().enumerate() {
+        rows.push(match bounds.get(i + 1) {
+            Some(to) => ReportRow::bucket(bounds[i], *to, count),
+            None => ReportRow::new(format!("{}+", bounds[i]), count),
+        });
+    }
+    rows
+}
+
+// a status class has to last this many requests before a change counts,
+// single errors among successful requests aren't a transition
+const MIN_PHASE: usize = 5;
+
+// changes of the status class, like 2xx to 4xx, of the clients with the most
+// requests. Each row is a transition and counts the requests of the new phase.
+fn transition_rows(clients: Vec<(&str, Vec<&Visit>)>, max: usize) -> Vec<ReportRow> {
+    let mut clients = clients;
+    clients.sort_by(|(a, a_visits), (b, b_visits)| {
+        b_visits.len().cmp(&a_visits.len()).then_with(|| a.cmp(b))
+    });
+
+    let mut rows = Vec::new();
+    for (client, visits) in clients.into_iter().take(max) {
+        // runs of the same status class, short runs are dropped as noise and
+        // their neighbours merged when they have the same class
+        let mut phases: Vec<(u16, &Visit, usize)> = Vec::new();
+        for run in visits.chunk_by(|a, b| a.status / 100 == b.status / 100) {
+            if run.len() < MIN_PHASE {
+                continue;
+            }
+            match phases.last_mut() {
+                Some((class, _, count)) if *class == run[0].status / 100 => *count += run.len(),
+                _ => phases.push((run[0].status / 100, run[0], run.len())),
+            }
+        }
+
+        for pair in phases.windows(2) {
+            let ((from, _, _), (to, visit, count)) = (pair[0], pair[1]);
+            rows.push(ReportRow::new(
+                format!("{client}: {from}xx -> {to}xx at {}", visit.timestamp),
+                count,
+            ));
+        }
+    }
+    rows
+}
+
+// identical requests of a client at most this far apart are retries, a burst
+// needs at least `MIN_RETRIES` of them to be a storm
+const RETRY_INTERVAL_MILLIS: i64 = 2000;
+const MIN_RETRIES: usize = 5;
+
+// the largest burst of every client, method and path, biggest bursts first
+fn retry_rows(clients: Vec<(&str, Vec<&Visit>)>, max: usize) -> Vec<ReportRow> {
+    let mut rows = Vec::new();
+    for (client, visits) in clients {
+        // visits are in chronological order, so every request's are too
+        let mut requests: HashMap<(&str, &str), Vec<&Visit>> = HashMap::new();
+        for visit in visits {
+            requests
+                .entry((visit.method.as_str(), visit.path.as_str()))
+                .or_default()
+                .push(visit);
+        }
+
+        for ((method, path), visits) in requests {
+            let burst = visits
+                .chunk_by(|a, b| {
+                    (b.timestamp - a.timestamp).num_milliseconds() <= RETRY_INTERVAL_MILLIS
+                })
+                .max_by_key(|burst| burst.len());
+            if let Some(burst) = burst.filter(|burst| burst.len() >= MIN_RETRIES) {
+                let first = burst[0].timestamp;
+                let seconds =
+                    (burst[burst.len() - 1].timestamp - first).num_milliseconds() as f64 / 1000.0;
+                rows.push(ReportRow::new(
+                    format!("{client} {method} {path} ({seconds}s from {first})"),
+                    burst.len(),
+                ));
+            }
+        }
+    }
+
+    rows.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    rows.truncate(max);
+    rows
+}
+
+// a slow scan touches many distinct paths over a long time, at a rate low
+// enough to stay below rate limits
+const MIN_SCAN_PATHS: usize = 20;
+const MIN_SCAN_MINUTES: i64 = 10;
+const MAX_SCAN_RATE: f64 = 0.1;
+
+// client IPs matching a slow scan, counting their distinct paths
+fn slow_scan_rows(clients: Vec<(&str, Vec<&Visit>)>, max: usize) -> Vec<ReportRow> {
+    // clients are told apart by IP address and user agent, scanners are
+    // counted per IP address
+    let mut ips: HashMap<&str, Vec<&Visit>> = HashMap::new();
+    for (client, visits) in clients {
+        let ip = client.split_once(' ').map_or(client, |(ip, _)| ip);
+        ips.entry(ip).or_default().extend(visits);
+    }
+
+    let mut rows = Vec::new();
+    for (ip, visits) in ips {
+        let (Some(first), Some(last)) = (
+            visits.iter().map(|visit| visit.timestamp).min(),
+            visits.iter().map(|visit| visit.timestamp).max(),
+        ) else {
+            continue;
+        };
+        let span = last - first;
+        let paths: HashSet<&str> = visits.iter().map(|visit| visit.path.as_str()).collect();
+        let rate = visits.len() as f64 / span.num_seconds().max(1) as f64;
+
+        if paths.len() >= MIN_SCAN_PATHS
+            && span.num_minutes() >= MIN_SCAN_MINUTES
+            && rate <= MAX_SCAN_RATE
+        {
+            rows.push(ReportRow::new(
+                format!(
+                    "{ip} ({} requests over {} minutes, {:.3} req/s)",
+                    visits.len(),
+                    span.num_minutes(),
+                    rate
+                ),
+                paths.len(),
+            ));
+        }
+    }
+
+    rows.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    rows.truncate(max);
+    rows
+}
+
+// sessions reaching every step, steps have to be visited in order but other
+// requests may come in between
+fn funnel_rows(sessions: &[Session], steps: &[String]) -> Vec<ReportRow> {
+    let mut reached = vec![0; steps.len()];
+    for session in sessions {
+        let mut step = 0;
+        for visit in &session.visits {
+            if step < steps.len() && path_matches(&steps[step], &visit.path) {
+                reached[step] += 1;
+                step += 1;
+            }
+        }
+    }
+
+    let mut previous = sessions.len();
+    steps
+        .iter()
+        .zip(reached)
+        .map(|(step, count)| {
+            let share = percent(count, previous);
+            previous = count;
+            ReportRow {
+                percent: Some(share),
+                ..ReportRow::new(step.clone(), count)
+            }
+        })
+        .collect()
+}
+
+impl TopInfo {
+    pub(crate) fn headline(&self) -> Headline {
+        let server_errors: usize = self
+            .response_codes
+            .iter()
+            .filter(|(code, _)| code.is_server_error())
+            .map(|(_, count)| count)
+            .sum();
+        let error_rate = percent(server_errors, self.total_requests);
+
+        Headline {
+            error_rate,
+            server_errors,
+            p95_response_time: self.response_times.percentile(95.0),
+            busiest_minute: top_row(self.requests_per_minute.iter()),
+            top_error_path: top_row(self.error_paths.iter()),
+            top_client: top_row(self.clients().iter()),
+        }
+    }
+
+    /// Requests by client IP, or by the first X-Forwarded-For address like
+    /// `Entry::client` for logs without client IPs
+    pub(crate) fn clients(&self) -> Cow<'_, DefaultHashMap<IpAddr, usize>> {
+        if !self.client_ips.is_empty() {
+            return Cow::Borrowed(&self.client_ips);
+        }
+        let mut clients = DefaultHashMap::new();
+        for (forwarded, count) in self.x_forwarded_fors.iter() {
+            if let Some(Ok(ip)) = forwarded.split(',').next().map(|ip| ip.trim().parse()) {
+                clients[ip] += count;
+            }
+        }
+        Cow::Owned(clients)
+    }
+
+    /// Builds the report for the sections selected in `options`
+    pub fn report(&self, options: &ReportOptions) -> Report {
+        let max = self.max_results;
+        let by_value = options.sort_or(&SortOrder::ByValue);
+        let resolution = self.time_resolution();
+        let mut sections = Vec::new();
+
+        let mut add = |section: Section, title: String, mut rows: Vec<ReportRow>| {
+            let layout = options.layouts.get(&section).cloned();
+            rows.iter_mut()
+                .for_each(|row| options.numbers.round(row, layout.as_ref()));
+            sections.push(ReportSection {
+                section,
+                title: layout
+                    .as_ref()
+                    .and_then(|layout| layout.title.clone())
+                    .unwrap_or(title),
+                rows,
+                layout,
+                numbers: options.numbers,
+                bars: options.bars,
+                resolution,
+            })
+        };
+
+        // sections are added in the order of `Section::ALL`, which is the
+        // order `--list-sections` shows them in
+        if options.shows(Section::ResponseCodes) {
+            add(
+                Section::ResponseCodes,
+                "Response Codes:".into(),
+                top_rows(
+                    with_invalid(
+                        self.response_codes
+                            .iter()
+                            .map(|(code, count)| (status_label(code), count)),
+                        &self.invalid_statuses,
+                    ),
+                    options.sort_or(&SortOrder::ByKey),
+                    usize::MAX,
+                ),
+            );
+        }
+
+        if options.shows(Section::RequestMethods) {
+            add(
+                Section::RequestMethods,
+                "Request Methods:".into(),
+                top_rows(
+                    with_invalid(
+                        self.request_methods
+                            .iter()
+                            .map(|(method, count)| (method.to_string(), count)),
+                        &self.invalid_methods,
+                    ),
+                    by_value,
+                    usize::MAX,
+                ),
+            );
+        }
+
+        if options.shows(Section::RequestsNoQuery) {
+            add(
+                Section::RequestsNoQuery,
+                format!("Top '{max}' Requests (no query params)"),
+                top_rows(self.requests_no_query.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::RequestsQuery) {
+            add(
+                Section::RequestsQuery,
+                format!("Top '{max}' Requests (with query params)"),
+                top_rows(self.requests_query.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::PathClusters) {
+            add(
+                Section::PathClusters,
+                format!("Top '{max}' Path Clusters (no query params)"),
+                top_rows(
+                    cluster::cluster_paths(&self.requests_no_query).iter(),
+                    by_value,
+                    max,
+                ),
+            );
+        }
+
+        if options.shows(Section::ServerErrorPaths) {
+            add(
+                Section::ServerErrorPaths,
+                format!("Top '{max}' Paths with 5xx Responses"),
+                top_rows(self.error_paths.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::ClientErrorPaths) {
+            add(
+                Section::ClientErrorPaths,
+                format!("Top '{max}' Paths with 4xx Responses"),
+                top_rows(self.client_error_paths.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::UserAgents) && !self.user_agents.is_empty() {
+            add(
+                Section::UserAgents,
+                format!("Top '{max}' User Agents"),
+                top_rows(self.user_agents.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::Platforms) && !self.user_agents.is_empty() {
+            let mut platforms: HashMap<String, usize> = HashMap::new();
+            for (user_agent, count) in self.user_agents.iter() {
+                let key = match crate::platform(user_agent) {
+                    (device, Some(os)) => format!("{device} ({os})"),
+                    (device, None) => device.to_string(),
+                };
+                *platforms.entry(key).or_insert(0) += count;
+            }
+            add(
+                Section::Platforms,
+                "Client Platforms".into(),
+                top_rows(platforms.iter(), by_value, usize::MAX),
+            );
+        }
+
+        if options.shows(Section::Referrers) && !self.referrers.is_empty() {
+            add(
+                Section::Referrers,
+                format!("Top '{max}' Referrers"),
+                top_rows(self.referrers.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::ClientIps) && !self.client_ips.is_empty() {
+            add(
+                Section::ClientIps,
+                format!("Top '{max}' Client IPs"),
+                top_rows(self.client_ips.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::ErrorClientIps) && !self.client_ips.is_empty() {
+            let rows = top_rows(self.client_errors.iter(), by_value, max)
+                .into_iter()
+                .map(|row| {
+                    let requests = row
+                        .key
+                        .parse::<IpAddr>()
+                        .map_or(0, |ip| *self.client_ips.get(ip));
+                    ReportRow {
+                        percent: (requests > 0).then(|| percent(row.count, requests)),
+                        ..row
+                    }
+                })
+                .collect();
+            add(
+                Section::ErrorClientIps,
+                format!("Top '{max}' Client IPs by Errors (4xx and 5xx)"),
+                rows,
+            );
+        }
+
+        if options.shows(Section::ClientRequests) && !self.client_ips.is_empty() {
+            add(
+                Section::ClientRequests,
+                "Client IPs by Number of Requests".into(),
+                client_request_rows(&self.client_ips),
+            );
+        }
+
+        if options.shows(Section::BackendIps) && !self.backend_ips.is_empty() {
+            add(
+                Section::BackendIps,
+                format!("Top '{max}' Backend Address (Cells & Platform VMs)"),
+                top_rows(self.backend_ips.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::XForwardedFors) && !self.x_forwarded_fors.is_empty() {
+            add(
+                Section::XForwardedFors,
+                format!("Top '{max}' X-Forwarded-For Ips"),
+                top_rows(self.x_forwarded_fors.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::Hosts) && !self.hosts.is_empty() {
+            add(
+                Section::Hosts,
+                format!("Top '{max}' Destination Hosts"),
+                top_rows(self.hosts.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::HostClients) && !self.host_clients.is_empty() {
+            let clients: Vec<(&String, usize)> = self
+                .host_clients
+                .iter()
+                .map(|(host, clients)| (host, clients.count()))
+                .collect();
+            add(
+                Section::HostClients,
+                format!("Top '{max}' Destination Hosts by Distinct Client IPs"),
+                top_rows(
+                    clients.iter().map(|(host, count)| (host, count)),
+                    by_value,
+                    max,
+                ),
+            );
+        }
+
+        if options.shows(Section::AppIds) && !self.app_ids.is_empty() {
+            add(
+                Section::AppIds,
+                format!("Top '{max}' Application UUIDs"),
+                top_rows(self.app_ids.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::AppIndexes) && !self.app_indexes.is_empty() {
+            add(
+                Section::AppIndexes,
+                format!("Top '{max}' Application Indexes"),
+                top_rows(self.app_indexes.iter(), by_value, max),
+            );
+        }
+
+        // buckets are whole seconds unless they're labeled otherwise
+        let unit = match self.time_resolution() {
+            TimeResolution::Seconds(_) => String::new(),
+            resolution => format!(" ({})", resolution.unit()),
+        };
+
+        if options.shows(Section::ResponseTimes) && !self.response_times.is_empty() {
+            add(
+                Section::ResponseTimes,
+                format!("Top Response Times{unit}"),
+                bucket_rows(
+                    &self.response_times,
+                    options.min_response_time_threshold,
+                    self.time_resolution(),
+                ),
+            );
+        }
+
+        if options.shows(Section::GorouterTimes) && !self.gorouter_times.is_empty() {
+            add(
+                Section::GorouterTimes,
+                format!("Top Gorouter Times{unit}"),
+                bucket_rows(
+                    &self.gorouter_times,
+                    options.min_response_time_threshold,
+                    self.time_resolution(),
+                ),
+            );
+        }
+
+        if options.shows(Section::RequestSizes) && !self.request_sizes.is_empty() {
+            add(
+                Section::RequestSizes,
+                "Request Body Sizes (bytes)".into(),
+                request_size_rows(&self.request_sizes),
+            );
+        }
+
+        if options.shows(Section::UploadPaths) && !self.bytes_received_by_path.is_empty() {
+            add(
+                Section::UploadPaths,
+                format!("Top '{max}' Paths by Bytes Received (no query params)"),
+                top_rows(self.bytes_received_by_path.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::XCfRouterErrors) && !self.x_cf_routererrors.is_empty() {
+            add(
+                Section::XCfRouterErrors,
+                format!("Top '{max}' CF Router Errors"),
+                top_rows(self.x_cf_routererrors.iter(), by_value, max),
+            );
+        }
+
+        // the top values of every field, one field after the other
+        if options.shows(Section::ExtraFields) && !self.extra_fields.is_empty() {
+            add(
+                Section::ExtraFields,
+                format!("Top '{max}' Extra Field Values"),
+                self.extra_fields
+                    .iter()
+                    .flat_map(|(name, counts)| {
+                        top_rows(counts.iter(), by_value, max)
+                            .into_iter()
+                            .map(move |row| ReportRow {
+                                key: format!("{name}={}", row.key),
+                                ..row
+                            })
+                    })
+                    .collect(),
+            );
+        }
+
+        if options.shows(Section::TimeSeries) && !self.requests_per_minute.is_empty() {
+            add(
+                Section::TimeSeries,
+                format!("Requests per {}", self.interval().label()),
+                self.time_series()
+                    .into_iter()
+                    .map(|point| {
+                        ReportRow::percentiles(
+                            point.start.to_string(),
+                            point.requests,
+                            &point.latencies,
+                        )
+                    })
+                    .collect(),
+            );
+        }
+
+        if options.shows(Section::ErrorRates) && !self.requests_per_minute.is_empty() {
+            add(
+                Section::ErrorRates,
+                format!("Error Rates per {}", self.interval().label()),
+                self.time_series()
+                    .into_iter()
+                    .map(|point| ReportRow {
+                        server_errors: Some(point.server_errors),
+                        client_errors: Some(point.client_errors),
+                        percent: Some(percent(point.server_errors, point.requests)),
+                        ..ReportRow::new(point.start.to_string(), point.requests)
+                    })
+                    .collect(),
+            );
+        }
+
+        if options.shows(Section::Bandwidth) && !self.requests_per_minute.is_empty() {
+            add(
+                Section::Bandwidth,
+                format!("Bandwidth per {}", self.interval().label()),
+                self.time_series()
+                    .into_iter()
+                    .map(|point| ReportRow {
+                        megabytes: Some(round3(point.bytes_sent as f64 / 1_000_000.0)),
+                        ..ReportRow::percentiles(
+                            point.start.to_string(),
+                            point.requests,
+                            &point.latencies,
+                        )
+                    })
+                    .collect(),
+            );
+        }
+
+        // logs too short to forecast from leave the section out, like the
+        // other sections without rows
+        let forecast = if options.shows(Section::Forecast) {
+            forecast::forecast(&self.requests_per_minute)
+        } else {
+            Vec::new()
+        };
+        if !forecast.is_empty() {
+            let interval = match forecast.as_slice() {
+                [first, second, ..] if second.start - first.start >= Duration::hours(1) => "Hour",
+                _ => "Minute",
+            };
+            add(
+                Section::Forecast,
+                format!("Forecast Requests per {interval}"),
+                forecast
+                    .into_iter()
+                    .map(|f| ReportRow {
+                        low: Some(f.low),
+                        high: Some(f.high),
+                        ..ReportRow::new(f.start.to_string(), f.requests as usize)
+                    })
+                    .collect(),
+            );
+        }
+
+        let sessions = match &self.sessions {
+            Some(sessions) if options.needs_sessions() => sessions.sessions(),
+            _ => Vec::new(),
+        };
+
+        if options.shows(Section::Funnel) && !options.funnel.is_empty() && !sessions.is_empty() {
+            add(
+                Section::Funnel,
+                format!("Funnel ({} sessions)", sessions.len()),
+                funnel_rows(&sessions, &options.funnel),
+            );
+        }
+
+        if options.shows(Section::EntryPages) && !sessions.is_empty() {
+            let mut entry_pages: DefaultHashMap<&str, usize> = DefaultHashMap::new();
+            for session in &sessions {
+                entry_pages[session.entry_page()] += 1;
+            }
+            add(
+                Section::EntryPages,
+                format!("Top '{max}' Entry Pages"),
+                top_rows(entry_pages.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::ExitPages) && !sessions.is_empty() {
+            let mut exit_pages: DefaultHashMap<&str, usize> = DefaultHashMap::new();
+            for session in &sessions {
+                exit_pages[session.exit_page()] += 1;
+            }
+            add(
+                Section::ExitPages,
+                format!("Top '{max}' Exit Pages"),
+                top_rows(exit_pages.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::SessionDepths) && !sessions.is_empty() {
+            add(
+                Section::SessionDepths,
+                "Session Depth (requests per session)".into(),
+                session_rows(&sessions, &SESSION_DEPTHS, |s| s.visits.len()),
+            );
+        }
+
+        if options.shows(Section::SessionDurations) && !sessions.is_empty() {
+            add(
+                Section::SessionDurations,
+                "Session Duration (seconds)".into(),
+                session_rows(&sessions, &SESSION_DURATIONS, |s| {
+                    s.duration().num_seconds().max(0) as usize
+                }),
+            );
+        }
+
+        if options.shows(Section::IpAudit) && options.ip_audit.is_enabled() {
+            let clients = self.clients();
+            let total: usize = clients.values().sum();
+            let mut flagged: DefaultHashMap<IpAddr, usize> = DefaultHashMap::new();
+            for (ip, count) in clients.iter() {
+                if options.ip_audit.flags(ip) {
+                    flagged[*ip] += count;
+                }
+            }
+            let flagged_total: usize = flagged.values().sum();
+            let percent = percent(flagged_total, total);
+
+            add(
+                Section::IpAudit,
+                format!(
+                    "IP Audit: {flagged_total} of {total} requests ({percent}%) from {} IPs {}",
+                    flagged.len(),
+                    options.ip_audit.describe()
+                ),
+                top_rows(flagged.iter(), by_value, max),
+            );
+        }
+
+        if let Some(cert_hosts) = options
+            .cert_hosts
+            .as_ref()
+            .filter(|_| options.shows(Section::CertCoverage))
+        {
+            let total: usize = self.hosts.values().sum();
+            let mut uncovered: DefaultHashMap<&str, usize> = DefaultHashMap::new();
+            for (host, count) in self.hosts.iter() {
+                if !cert_hosts.covers(host) {
+                    uncovered[host.as_str()] += count;
+                }
+            }
+            let uncovered_total: usize = uncovered.values().sum();
+            let percent = percent(uncovered_total, total);
+
+            add(
+                Section::CertCoverage,
+                format!(
+                    "Cert Coverage: {uncovered_total} of {total} requests ({percent}%) for {} hosts no certificate covers",
+                    uncovered.len()
+                ),
+                top_rows(uncovered.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::Pii) && !self.pii_paths.is_empty() {
+            add(
+                Section::Pii,
+                format!("Top '{max}' Paths with PII in the URL"),
+                top_rows(self.pii_paths.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::MalformedRequests) && !self.malformed_requests.is_empty() {
+            add(
+                Section::MalformedRequests,
+                format!("Top '{max}' Malformed Requests"),
+                top_rows(self.malformed_requests.iter(), by_value, max),
+            );
+        }
+
+        if options.shows(Section::StatusTransitions) {
+            let rows = match &self.sessions {
+                Some(tracked) => transition_rows(tracked.clients(), max),
+                None => Vec::new(),
+            };
+            if !rows.is_empty() {
+                add(
+                    Section::StatusTransitions,
+                    format!("Status Transitions of the Top '{max}' Clients"),
+                    rows,
+                );
+            }
+        }
+
+        if options.shows(Section::RetryStorms) {
+            let rows = match &self.sessions {
+                Some(tracked) => retry_rows(tracked.clients(), max),
+                None => Vec::new(),
+            };
+            if !rows.is_empty() {
+                add(
+                    Section::RetryStorms,
+                    format!("Top '{max}' Retry Storms"),
+                    rows,
+                );
+            }
+        }
+
+        if options.shows(Section::SlowScans) {
+            let rows = match &self.sessions {
+                Some(tracked) => slow_scan_rows(tracked.clients(), max),
+                None => Vec::new(),
+            };
+            if !rows.is_empty() {
+                add(
+                    Section::SlowScans,
+                    format!("Top '{max}' Slow Scans by Distinct Paths"),
+                    rows,
+                );
+            }
+        }
+
+        // the timelines show their share of all requests of each interval
+        let series = if self.timelines.is_empty() {
+            Vec::new()
+        } else {
+            self.time_series()
+        };
+
+        Report {
+            tags: self.tags.values(),
+            duration: self.log_duration().cloned(),
+            coverage: self.coverage(),
+            sparkline: options.sparkline.then(|| self.sparkline()).flatten(),
+            total_requests: self.total_requests,
+            errors: self.errors,
+            filtered: (!self.filter.is_empty()).then_some(self.filtered),
+            unique: options.unique.then(|| Unique {
+                client_ips: (&self.unique_client_ips).into(),
+                paths: (&self.unique_paths).into(),
+                user_agents: (&self.unique_user_agents).into(),
+            }),
+            concentration: options.concentration.then(|| self.concentration()),
+            // shown along with the response time buckets
+            statistics: (options.shows(Section::ResponseTimes)
+                || options.shows(Section::GorouterTimes))
+            .then(|| Statistics {
+                response_time: TimeStatistics::new(&self.response_time_stats),
+                gorouter_time: TimeStatistics::new(&self.gorouter_time_stats),
+                bytes_sent: self.bytes_sent,
+                bytes_received: self.bytes_received,
+            }),
+            headline: options.headline.then(|| self.headline()),
+            segment: self.segment.as_ref().map(|segment| segment.report(max)),
+            sections,
+            outliers: options
+                .outliers
+                .then(|| self.outliers.report(&self.response_times, max))
+                .flatten(),
+            slowest: self.slowest.as_ref().and_then(Outliers::slowest),
+            slices: self.slices.as_ref().map(Slices::report),
+            timelines: self
+                .timelines
+                .iter()
+                .map(|timeline| timeline.report(self.interval, &self.duration, &series))
+                .collect(),
+            extracts: self
+                .extracts
+                .iter()
+                .map(|extract| extract.report(max))
+                .collect(),
+            breakdowns: self
+                .breakdowns
+                .iter()
+                .map(|breakdown| breakdown.report(max))
+                .collect(),
+            deviations: options
+                .profile
+                .as_ref()
+                .map(|profile| self.deviations(profile, max))
+                .unwrap_or_default(),
+            drills: self.drills.iter().map(|drill| drill.report(max)).collect(),
+        }
+    }
+}
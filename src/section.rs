@@ -0,0 +1,348 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A report section which can be individually selected for display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    ResponseCodes,
+    RequestMethods,
+    RequestsNoQuery,
+    RequestsQuery,
+    PathClusters,
+    ServerErrorPaths,
+    ClientErrorPaths,
+    UserAgents,
+    Platforms,
+    Referrers,
+    ClientIps,
+    ErrorClientIps,
+    ClientRequests,
+    BackendIps,
+    XForwardedFors,
+    Hosts,
+    HostClients,
+    AppIds,
+    AppIndexes,
+    ResponseTimes,
+    GorouterTimes,
+    RequestSizes,
+    UploadPaths,
+    XCfRouterErrors,
+    ExtraFields,
+    TimeSeries,
+    ErrorRates,
+    Bandwidth,
+    Forecast,
+    Funnel,
+    EntryPages,
+    ExitPages,
+    SessionDepths,
+    SessionDurations,
+    IpAudit,
+    CertCoverage,
+    Pii,
+    MalformedRequests,
+    StatusTransitions,
+    RetryStorms,
+    SlowScans,
+}
+
+impl Section {
+    /// All sections, in the order they are displayed
+    pub const ALL: [Section; 41] = [
+        Section::ResponseCodes,
+        Section::RequestMethods,
+        Section::RequestsNoQuery,
+        Section::RequestsQuery,
+        Section::PathClusters,
+        Section::ServerErrorPaths,
+        Section::ClientErrorPaths,
+        Section::UserAgents,
+        Section::Platforms,
+        Section::Referrers,
+        Section::ClientIps,
+        Section::ErrorClientIps,
+        Section::ClientRequests,
+        Section::BackendIps,
+        Section::XForwardedFors,
+        Section::Hosts,
+        Section::HostClients,
+        Section::AppIds,
+        Section::AppIndexes,
+        Section::ResponseTimes,
+        Section::GorouterTimes,
+        Section::RequestSizes,
+        Section::UploadPaths,
+        Section::XCfRouterErrors,
+        Section::ExtraFields,
+        Section::TimeSeries,
+        Section::ErrorRates,
+        Section::Bandwidth,
+        Section::Forecast,
+        Section::Funnel,
+        Section::EntryPages,
+        Section::ExitPages,
+        Section::SessionDepths,
+        Section::SessionDurations,
+        Section::IpAudit,
+        Section::CertCoverage,
+        Section::Pii,
+        Section::MalformedRequests,
+        Section::StatusTransitions,
+        Section::RetryStorms,
+        Section::SlowScans,
+    ];
+
+    /// The sections shown when none are selected. That's everything but the
+    /// time series, error rates and bandwidth, which have a row for every
+    /// minute of the logs, and the forecast, path clusters, client platforms,
+    /// distinct clients per host, clients by number of requests and session
+    /// sections, which only show up when asked for. The funnel is the
+    /// exception, as it needs `--funnel` anyway.
+    pub fn defaults() -> Vec<Section> {
+        Section::ALL
+            .iter()
+            .filter(|section| {
+                **section == Section::Funnel
+                    || !(matches!(
+                        section,
+                        Section::TimeSeries
+                            | Section::ErrorRates
+                            | Section::Bandwidth
+                            | Section::Forecast
+                            | Section::PathClusters
+                            | Section::Platforms
+                            | Section::HostClients
+                            | Section::ClientRequests
+                    ) || section.needs_sessions())
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Sessions keep every request in memory, so they are only tracked for these sections
+    pub fn needs_sessions(&self) -> bool {
+        matches!(
+            self,
+            Section::Funnel
+                | Section::EntryPages
+                | Section::ExitPages
+                | Section::SessionDepths
+                | Section::SessionDurations
+                | Section::StatusTransitions
+                | Section::RetryStorms
+                | Section::SlowScans
+        )
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Section::ResponseCodes => "response-codes",
+            Section::RequestMethods => "request-methods",
+            Section::RequestsNoQuery => "requests-no-query",
+            Section::RequestsQuery => "requests-query",
+            Section::PathClusters => "path-clusters",
+            Section::ServerErrorPaths => "server-error-paths",
+            Section::ClientErrorPaths => "client-error-paths",
+            Section::UserAgents => "user-agents",
+            Section::Platforms => "platforms",
+            Section::Referrers => "referrers",
+            Section::ClientIps => "client-ips",
+            Section::ErrorClientIps => "error-client-ips",
+            Section::ClientRequests => "client-requests",
+            Section::BackendIps => "backend-ips",
+            Section::XForwardedFors => "x-forwarded-fors",
+            Section::Hosts => "hosts",
+            Section::HostClients => "host-clients",
+            Section::AppIds => "app-ids",
+            Section::AppIndexes => "app-indexes",
+            Section::ResponseTimes => "response-times",
+            Section::GorouterTimes => "gorouter-times",
+            Section::RequestSizes => "request-sizes",
+            Section::UploadPaths => "upload-paths",
+            Section::XCfRouterErrors => "x-cf-routererrors",
+            Section::ExtraFields => "extra-fields",
+            Section::TimeSeries => "time-series",
+            Section::ErrorRates => "error-rates",
+            Section::Bandwidth => "bandwidth",
+            Section::Forecast => "forecast",
+            Section::Funnel => "funnel",
+            Section::EntryPages => "entry-pages",
+            Section::ExitPages => "exit-pages",
+            Section::SessionDepths => "session-depths",
+            Section::SessionDurations => "session-durations",
+            Section::IpAudit => "ip-audit",
+            Section::CertCoverage => "cert-coverage",
+            Section::Pii => "pii",
+            Section::MalformedRequests => "malformed-requests",
+            Section::StatusTransitions => "status-transitions",
+            Section::RetryStorms => "retry-storms",
+            Section::SlowScans => "slow-scans",
+        }
+    }
+
+    /// Describes the key column of the section, for outputs with table headers.
+    /// The time buckets leave out their unit, which depends on the
+    /// `TimeResolution`.
+    pub fn key_label(&self) -> &'static str {
+        match self {
+            Section::ResponseCodes => "Response Code",
+            Section::RequestMethods => "Method",
+            Section::RequestsNoQuery => "Path",
+            Section::RequestsQuery => "Path and Query",
+            Section::PathClusters => "Path Cluster",
+            Section::ServerErrorPaths => "Path",
+            Section::ClientErrorPaths => "Path",
+            Section::UserAgents => "User Agent",
+            Section::Platforms => "Platform",
+            Section::Referrers => "Referrer",
+            Section::ClientIps => "Client IP",
+            Section::ErrorClientIps => "Client IP",
+            Section::ClientRequests => "Requests",
+            Section::BackendIps => "Backend Address",
+            Section::XForwardedFors => "X-Forwarded-For",
+            Section::Hosts => "Host",
+            Section::HostClients => "Host",
+            Section::AppIds => "Application UUID",
+            Section::AppIndexes => "Application Index",
+            Section::ResponseTimes => "Response Time",
+            Section::GorouterTimes => "Gorouter Time",
+            Section::RequestSizes => "Request Size (bytes)",
+            Section::UploadPaths => "Path",
+            Section::XCfRouterErrors => "Router Error",
+            Section::ExtraFields => "Field",
+            Section::TimeSeries => "Interval",
+            Section::ErrorRates => "Interval",
+            Section::Bandwidth => "Interval",
+            Section::Forecast => "Interval",
+            Section::Funnel => "Step",
+            Section::EntryPages => "Path",
+            Section::ExitPages => "Path",
+            Section::SessionDepths => "Requests",
+            Section::SessionDurations => "Duration (s)",
+            Section::IpAudit => "Client IP",
+            Section::CertCoverage => "Host",
+            Section::Pii => "Path",
+            Section::MalformedRequests => "Request",
+            Section::StatusTransitions => "Client",
+            Section::RetryStorms => "Request",
+            Section::SlowScans => "Client IP",
+        }
+    }
+
+    /// One line summary of what the section shows, for `--list-sections`
+    pub fn description(&self) -> &'static str {
+        match self {
+            Section::ResponseCodes => "Requests by response status code",
+            Section::RequestMethods => "Requests by request method",
+            Section::RequestsNoQuery => "Top requested paths, without query params",
+            Section::RequestsQuery => "Top requested paths, with query params",
+            Section::PathClusters => {
+                "Top requested paths with near identical ones grouped, like /users/* (not shown by default)"
+            }
+            Section::ServerErrorPaths => "Top paths of requests with a 5xx response",
+            Section::ClientErrorPaths => "Top paths of requests with a 4xx response",
+            Section::UserAgents => "Top user agents",
+            Section::Platforms => {
+                "Requests by device and operating system, guessed from the user agent (not shown by default)"
+            }
+            Section::Referrers => "Top referrers",
+            Section::ClientIps => "Top client IPs",
+            Section::ErrorClientIps => {
+                "Top client IPs by 4xx and 5xx responses, with their share of the client's requests"
+            }
+            Section::ClientRequests => {
+                "Client IPs by number of requests, from one to over a thousand (not shown by default)"
+            }
+            Section::BackendIps => "Top backend addresses (gorouter)",
+            Section::XForwardedFors => "Top X-Forwarded-For IPs (gorouter, cloud_controller)",
+            Section::Hosts => "Top destination hosts (gorouter, cloud_controller)",
+            Section::HostClients => {
+                "Top destination hosts by distinct client IPs (gorouter, not shown by default)"
+            }
+            Section::AppIds => "Top application UUIDs (gorouter)",
+            Section::AppIndexes => "Top application indexes (gorouter)",
+            Section::ResponseTimes => "Response time histogram (gorouter, cloud_controller)",
+            Section::GorouterTimes => "Gorouter time histogram (gorouter)",
+            Section::RequestSizes => "Request body size histogram in bytes (gorouter)",
+            Section::UploadPaths => "Top paths by bytes of request bodies (gorouter)",
+            Section::XCfRouterErrors => "Top x_cf_routererror values (gorouter)",
+            Section::ExtraFields => "Top values of the fields named by --extra-fields (gorouter)",
+            Section::TimeSeries => {
+                "Requests per minute, or per --interval, with p50 and p95 response times (not shown by default)"
+            }
+            Section::ErrorRates => {
+                "Requests with 5xx and 4xx responses per minute, or per --interval, and the share of 5xx (not shown by default)"
+            }
+            Section::Bandwidth => {
+                "MB of response bodies per minute, or per --interval, with p50 and p95 response times (not shown by default)"
+            }
+            Section::Forecast => {
+                "Expected requests per hour of the next day, or per minute of the next hour, with 95% bands (not shown by default)"
+            }
+            Section::Funnel => "Sessions reaching each step of --funnel, in order",
+            Section::EntryPages => "Top first paths of sessions (not shown by default)",
+            Section::ExitPages => "Top last paths of sessions (not shown by default)",
+            Section::SessionDepths => {
+                "Sessions by number of requests, bounces separately (not shown by default)"
+            }
+            Section::SessionDurations => {
+                "Sessions by duration, bounces separately (not shown by default)"
+            }
+            Section::IpAudit => "Client IPs outside --allowlist or on --denylist",
+            Section::CertCoverage => "Request hosts no name of --cert-hosts covers",
+            Section::Pii => "Paths with card numbers, emails or SSNs in the URL",
+            Section::MalformedRequests => {
+                "Request lines the parser couldn't make sense of, with the request as logged"
+            }
+            Section::StatusTransitions => {
+                "Changes in the status codes of the top clients (not shown by default)"
+            }
+            Section::RetryStorms => {
+                "Clients repeating the same request in quick succession (not shown by default)"
+            }
+            Section::SlowScans => {
+                "Client IPs requesting many distinct paths at a low rate (not shown by default)"
+            }
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl Serialize for Section {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl FromStr for Section {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase().replace('_', "-");
+        Section::ALL
+            .iter()
+            .find(|section| section.name() == s)
+            .copied()
+            .ok_or_else(|| format!("invalid section '{s}'"))
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Splits the requests by a single field value, like a campaign's referrer,
+//! into paired tables of the matching and all the other requests, a light
+//! alternative to a full group-by.
+use crate::parallel::add_counts;
+use crate::report::{percent, top_rows, ReportRow};
+use crate::{DefaultHashMap, SortOrder};
+use access_log_parser::{LogEntry, RequestResult};
+use anyhow::{anyhow, bail, Result};
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::str::FromStr;
+
+// the field value a segment selects requests by
+#[derive(Debug, Clone)]
+enum Predicate {
+    /// Without query params
+    Path(String),
+    /// The client IP, or the first X-Forwarded-For address
+    ClientIp(IpAddr),
+    Host(String),
+    UserAgent(String),
+    /// The domain of the referrer, subdomains included
+    Referrer(String),
+}
+
+impl Predicate {
+    fn matches(&self, log_entry: &LogEntry) -> bool {
+        let (request, client_ip, host, user_agent, referrer) = match log_entry {
+            LogEntry::CommonLog(log) => (&log.request, Some(log.ip), None, None, None),
+            LogEntry::CombinedLog(log) => (
+                &log.request,
+                Some(log.ip),
+                None,
+                log.user_agent,
+                log.referrer.as_ref(),
+            ),
+            LogEntry::GorouterLog(log) => (
+                &log.request,
+                Some(log.remote_addr),
+                Some(log.request_host),
+                log.user_agent,
+                log.referrer.as_ref(),
+            ),
+            LogEntry::CloudControllerLog(log) => (
+                &log.request,
+                log.x_forwarded_for.first().copied(),
+                Some(log.request_host),
+                log.user_agent,
+                log.referrer.as_ref(),
+            ),
+        };
+
+        match self {
+            Predicate::Path(path) => match request {
+                RequestResult::Valid(req) => req.uri().path() == path,
+                _ => false,
+            },
+            Predicate::ClientIp(ip) => client_ip == Some(*ip),
+            Predicate::Host(name) => host.is_some_and(|host| host.eq_ignore_ascii_case(name)),
+            Predicate::UserAgent(agent) => user_agent == Some(agent.as_str()),
+            Predicate::Referrer(domain) => referrer
+                .and_then(|referrer| referrer.host())
+                .map(|host| host.to_lowercase())
+                .is_some_and(|host| {
+                    host == *domain
+                        || host
+                            .strip_suffix(domain.as_str())
+                            .is_some_and(|sub| sub.ends_with('.'))
+                }),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct SegmentCounts {
+    requests: usize,
+    server_errors: usize,
+    /// Without query params
+    paths: DefaultHashMap<String, usize>,
+}
+
+impl SegmentCounts {
+    fn record(&mut self, status_code: StatusCode, path: Option<&str>) {
+        self.requests += 1;
+        if status_code.is_server_error() {
+            self.server_errors += 1;
+        }
+        if let Some(path) = path {
+            self.paths[path.to_string()] += 1;
+        }
+    }
+
+    fn merge(&mut self, other: &SegmentCounts) {
+        self.requests += other.requests;
+        self.server_errors += other.server_errors;
+        add_counts(&mut self.paths, &other.paths);
+    }
+
+    fn report(&self, max: usize) -> SegmentSide {
+        SegmentSide {
+            requests: self.requests,
+            server_errors: self.server_errors,
+            error_rate: percent(self.server_errors, self.requests),
+            paths: top_rows(self.paths.iter(), &SortOrder::ByValue, max),
+        }
+    }
+}
+
+// the counts of a segment, without its predicate, as saved by `TopInfo::state`
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct SegmentState {
+    key: String,
+    matching: SegmentCounts,
+    others: SegmentCounts,
+}
+
+/// Counts the requests with one field value apart from all the others
+#[derive(Debug)]
+pub struct Segment {
+    key: String,
+    predicate: Predicate,
+    matching: SegmentCounts,
+    others: SegmentCounts,
+}
+
+impl FromStr for Segment {
+    type Err = anyhow::Error;
+
+    /// Parses `field=value`, like `referrer=campaign.example.com` or
+    /// `user-agent=curl/7.64.1`
+    fn from_str(s: &str) -> Result<Self> {
+        let (field, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid segment '{s}', expected FIELD=VALUE"))?;
+        let value = value.trim();
+        let predicate = match field.trim().to_lowercase().replace('_', "-").as_str() {
+            "path" => Predicate::Path(value.to_string()),
+            "client-ip" => Predicate::ClientIp(
+                value
+                    .parse()
+                    .map_err(|_| anyhow!("invalid IP address '{value}' in segment '{s}'"))?,
+            ),
+            "host" => Predicate::Host(value.to_string()),
+            "user-agent" => Predicate::UserAgent(value.to_string()),
+            "referrer" => Predicate::Referrer(value.trim_start_matches('.').to_lowercase()),
+            _ => bail!(
+                "invalid segment field '{field}', expected path, client-ip, host, user-agent or referrer"
+            ),
+        };
+        Ok(Segment {
+            key: format!("{}={value}", field.trim()),
+            predicate,
+            matching: SegmentCounts::default(),
+            others: SegmentCounts::default(),
+        })
+    }
+}
+
+impl Segment {
+    // the same field value, without any requests
+    pub(crate) fn cleared(&self) -> Segment {
+        Segment {
+            key: self.key.clone(),
+            predicate: self.predicate.clone(),
+            matching: SegmentCounts::default(),
+            others: SegmentCounts::default(),
+        }
+    }
+
+    // adds the requests counted by `other`, a cleared copy of this segment
+    pub(crate) fn merge(&mut self, other: &Segment) {
+        self.matching.merge(&other.matching);
+        self.others.merge(&other.others);
+    }
+
+    pub(crate) fn state(&self) -> SegmentState {
+        SegmentState {
+            key: self.key.clone(),
+            matching: self.matching.clone(),
+            others: self.others.clone(),
+        }
+    }
+
+    // adds the requests of the saved segment, if it has the same key
+    pub(crate) fn restore(&mut self, saved: &SegmentState) {
+        if saved.key != self.key {
+            return;
+        }
+        self.matching.merge(&saved.matching);
+        self.others.merge(&saved.others);
+    }
+
+    pub(crate) fn record(&mut self, log_entry: &LogEntry) {
+        let (status_code, request) = match log_entry {
+            LogEntry::CommonLog(log) => (log.status_code, &log.request),
+            LogEntry::CombinedLog(log) => (log.status_code, &log.request),
+            LogEntry::GorouterLog(log) => (log.status_code, &log.request),
+            LogEntry::CloudControllerLog(log) => (log.status_code, &log.request),
+        };
+        let path = match request {
+            RequestResult::Valid(req) => Some(req.uri().path()),
+            _ => None,
+        };
+        if self.predicate.matches(log_entry) {
+            self.matching.record(status_code, path);
+        } else {
+            self.others.record(status_code, path);
+        }
+    }
+
+    /// The top `max` paths of the matching and the other requests
+    pub(crate) fn report(&self, max: usize) -> SegmentReport {
+        SegmentReport {
+            key: self.key.clone(),
+            max,
+            matching: self.matching.report(max),
+            others: self.others.report(max),
+        }
+    }
+}
+
+/// The paired tables of a `--segment`
+#[derive(Debug, Serialize)]
+pub struct SegmentReport {
+    /// The field and value, like `referrer=campaign.example.com`
+    pub key: String,
+    #[serde(skip)]
+    pub max: usize,
+    pub matching: SegmentSide,
+    pub others: SegmentSide,
+}
+
+/// The requests on one side of a segment
+#[derive(Debug, Serialize)]
+pub struct SegmentSide {
+    pub requests: usize,
+    pub server_errors: usize,
+    /// Percentage of requests with a 5xx response
+    pub error_rate: f64,
+    /// Without query params
+    pub paths: Vec<ReportRow>,
+}
+
+impl SegmentReport {
+    /// The title and side of both tables, the matching requests first
+    pub fn tables(&self) -> [(String, &SegmentSide); 2] {
+        let title = |with: &str, side: &SegmentSide| {
+            format!(
+                "Top '{}' Requests {with} {} ({} requests, {}% 5xx):",
+                self.max, self.key, side.requests, side.error_rate
+            )
+        };
+        [
+            (title("with", &self.matching), &self.matching),
+            (title("without", &self.others), &self.others),
+        ]
+    }
+}
@@ -0,0 +1,247 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `top-logs selftest`, runs sample logs of every format bundled with the
+//! binary through the whole pipeline and checks that the counts add up.
+use crate::explain::format_name;
+use crate::reader::detect_format;
+use crate::{OutputFormat, ReportOptions, Section, TopInfo};
+use access_log_parser::LogType;
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::Write;
+use std::process;
+
+struct Sample {
+    log_type: LogType,
+    contents: &'static str,
+    // one line of every sample is not an access log
+    requests: usize,
+    errors: usize,
+    // only some formats record response times
+    timed: bool,
+    // from the oldest to the newest entry
+    seconds: i64,
+}
+
+const SAMPLES: [Sample; 4] = [
+    Sample {
+        log_type: LogType::CommonLog,
+        contents: include_str!("../samples/common.log"),
+        requests: 12,
+        errors: 1,
+        timed: false,
+        seconds: 17,
+    },
+    Sample {
+        log_type: LogType::CombinedLog,
+        contents: include_str!("../samples/combined.log"),
+        requests: 12,
+        errors: 1,
+        timed: false,
+        seconds: 17,
+    },
+    Sample {
+        log_type: LogType::GorouterLog,
+        contents: include_str!("../samples/gorouter.log"),
+        requests: 12,
+        errors: 1,
+        timed: true,
+        seconds: 17,
+    },
+    Sample {
+        log_type: LogType::CloudControllerLog,
+        contents: include_str!("../samples/cloud_controller.log"),
+        requests: 12,
+        errors: 1,
+        timed: true,
+        seconds: 187,
+    },
+];
+
+// the width of the format names
+const NAME_WIDTH: usize = 18;
+
+fn new_top_info() -> TopInfo {
+    let mut top_info = TopInfo::new(10, true);
+    top_info.track_sessions(chrono::Duration::minutes(30));
+    top_info
+}
+
+// the ways the counts of `top_info` don't add up to the expected totals
+fn check_counts(sample: &Sample, top_info: &TopInfo, run: &str, failures: &mut Vec<String>) {
+    let mut expect = |what: &str, actual: usize, expected: usize| {
+        if actual != expected {
+            failures.push(format!("{run} counted {actual} {what}, not {expected}"));
+        }
+    };
+    expect("requests", top_info.total_requests, sample.requests);
+    expect("parse errors", top_info.errors, sample.errors);
+    let total = top_info.total_requests;
+    expect(
+        "requests by response code",
+        top_info.response_codes.values().sum::<usize>() + top_info.invalid_statuses,
+        total,
+    );
+    expect(
+        "requests by method",
+        top_info.request_methods.values().sum::<usize>() + top_info.invalid_methods,
+        total,
+    );
+    if sample.timed {
+        expect(
+            "requests by response time",
+            top_info.response_times.count(),
+            total,
+        );
+    }
+    expect(
+        "requests by minute",
+        top_info.requests_per_minute.values().sum(),
+        total,
+    );
+
+    let duration = &top_info.duration;
+    let seconds = (duration.end - duration.start).num_seconds();
+    if seconds != sample.seconds {
+        failures.push(format!(
+            "{run} covered {seconds}s, from {} to {}, not {}s",
+            duration.start, duration.end, sample.seconds
+        ));
+    }
+}
+
+// the ways reading `path` doesn't give the expected report
+fn check(sample: &Sample, path: &str) -> Result<Vec<String>> {
+    let mut failures = Vec::new();
+    let serial = {
+        let mut top_info = new_top_info();
+        match detect_format(path, &top_info.read_options)? {
+            Some((log_type, _)) if format_name(log_type) == format_name(sample.log_type) => (),
+            Some((log_type, _)) => {
+                failures.push(format!("detected as {} format", format_name(log_type)))
+            }
+            None => failures.push("format not detected".to_string()),
+        }
+        top_info.process_file(path, sample.log_type)?;
+        check_counts(sample, &top_info, "reading", &mut failures);
+        top_info
+    };
+
+    // chunks of the file counted on several threads add up to the same
+    let mut parallel = new_top_info();
+    parallel.process_files(&[(path.to_string(), sample.log_type)], 4)?;
+    check_counts(sample, &parallel, "reading in parallel", &mut failures);
+
+    // the counts survive being saved and restored, like with --checkpoint
+    let saved = bincode::serialize(&serial.state())?;
+    let mut restored = new_top_info();
+    restored.restore(bincode::deserialize(&saved)?)?;
+    check_counts(sample, &restored, "restoring", &mut failures);
+
+    let options = ReportOptions {
+        sections: Section::ALL.to_vec(),
+        funnel: vec!["/".to_string()],
+        unique: true,
+        concentration: true,
+        outliers: true,
+        ..ReportOptions::default()
+    };
+    let report = serial.report(&options);
+    if report.total_requests != sample.requests {
+        failures.push(format!(
+            "the report shows {} requests, not {}",
+            report.total_requests, sample.requests
+        ));
+    }
+    for format in OutputFormat::ALL {
+        let mut out = Vec::new();
+        match crate::write_report(&report, format, &mut out) {
+            Ok(()) if out.is_empty() => failures.push(format!("{format} output is empty")),
+            Ok(()) => (),
+            Err(err) => failures.push(format!("{format} output failed: {err:#}")),
+        }
+    }
+    Ok(failures)
+}
+
+// the same sample saved by a Windows tool, with a byte order mark and CRLF
+// line endings, counts the same
+fn check_windows(sample: &Sample, path: &str) -> Result<Vec<String>> {
+    let mut failures = Vec::new();
+    let mut top_info = new_top_info();
+    top_info.process_file(path, sample.log_type)?;
+    check_counts(sample, &top_info, "reading the Windows copy", &mut failures);
+
+    let mut mapped = new_top_info();
+    mapped.read_options.mmap = true;
+    mapped.process_file(path, sample.log_type)?;
+    check_counts(
+        sample,
+        &mapped,
+        "reading the Windows copy mapped into memory",
+        &mut failures,
+    );
+
+    let mut parallel = new_top_info();
+    parallel.process_files(&[(path.to_string(), sample.log_type)], 4)?;
+    check_counts(
+        sample,
+        &parallel,
+        "reading the Windows copy in parallel",
+        &mut failures,
+    );
+    Ok(failures)
+}
+
+/// Runs the sample logs bundled with top-logs, one of every log format,
+/// through reading, parsing, counting and every output format, and writes
+/// whether the counts add up for each to `out`. Returns false if any didn't.
+pub fn selftest<W: Write>(out: &mut W) -> Result<bool> {
+    let mut passed = true;
+    for sample in &SAMPLES {
+        let path = std::env::temp_dir().join(format!(
+            "top-logs-selftest-{}-{}.log",
+            process::id(),
+            format_name(sample.log_type)
+        ));
+        fs::write(&path, sample.contents)
+            .with_context(|| format!("writing sample log {}", path.display()))?;
+        let failures = check(sample, &path.to_string_lossy());
+        let windows = format!("\u{feff}{}", sample.contents.replace('\n', "\r\n"));
+        let windows_failures = fs::write(&path, windows)
+            .with_context(|| format!("writing sample log {}", path.display()))
+            .and_then(|_| check_windows(sample, &path.to_string_lossy()));
+        let _ = fs::remove_file(&path);
+
+        let mut failures = failures?;
+        failures.extend(windows_failures?);
+        if failures.is_empty() {
+            writeln!(
+                out,
+                "{:NAME_WIDTH$}ok      {} of {} lines counted",
+                format_name(sample.log_type),
+                sample.requests,
+                sample.requests + sample.errors
+            )?;
+        } else {
+            passed = false;
+            writeln!(out, "{:NAME_WIDTH$}FAILED", format_name(sample.log_type))?;
+            for failure in failures {
+                writeln!(out, "  {failure}")?;
+            }
+        }
+    }
+    Ok(passed)
+}
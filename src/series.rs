@@ -0,0 +1,232 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The requests over time, in intervals of a minute by default. Requests are
+//! always counted per minute, for the headline and the forecast, and per
+//! second when the interval is shorter than a minute. Longer intervals are
+//! built from the minutes when the report is rendered.
+use crate::report::{percent, round3, Coverage};
+use crate::{Latencies, LogDuration, TopInfo};
+use chrono::prelude::*;
+use chrono::Duration;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The length of the time series intervals, whole seconds under a minute or
+/// whole minutes from there on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval(u32);
+
+impl Default for Interval {
+    fn default() -> Self {
+        Interval(60)
+    }
+}
+
+impl Interval {
+    pub fn seconds(&self) -> u32 {
+        self.0
+    }
+
+    /// Intervals under a minute need the requests counted per second
+    pub fn is_sub_minute(&self) -> bool {
+        self.0 < 60
+    }
+
+    /// The start of the interval `time` falls into. Intervals are aligned to
+    /// the local time of `time`, so hours start on the hour in every time zone.
+    pub fn start(&self, time: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+        let offset = time.offset().local_minus_utc() as i64;
+        let local = time.timestamp() + offset;
+        let into = local.rem_euclid(self.0 as i64);
+        time.with_nanosecond(0).unwrap_or(time) - Duration::seconds(into)
+    }
+
+    /// The interval in words, for titles, like `Minute` or `10 Seconds`
+    pub fn label(&self) -> String {
+        let (count, unit) = match self.0 {
+            s if s % 3600 == 0 => (s / 3600, "Hour"),
+            s if s % 60 == 0 => (s / 60, "Minute"),
+            s => (s, "Second"),
+        };
+        match count {
+            1 => unit.to_string(),
+            _ => format!("{count} {unit}s"),
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            s if s % 3600 == 0 => write!(f, "{}h", s / 3600),
+            s if s % 60 == 0 => write!(f, "{}m", s / 60),
+            s => write!(f, "{s}s"),
+        }
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    /// A number of seconds, minutes or hours, like `10s`, `5m` or `1h`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || {
+            format!("invalid interval '{s}', use a number of seconds, minutes or hours like '10s', '5m' or '1h'")
+        };
+        let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let count: u32 = s[..split].parse().map_err(|_| invalid())?;
+        let unit = match &s[split..] {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3600,
+            _ => return Err(invalid()),
+        };
+        match count.checked_mul(unit) {
+            Some(0) | None => Err(invalid()),
+            Some(seconds) if seconds > 60 && seconds % 60 != 0 => Err(format!(
+                "invalid interval '{s}', intervals over a minute have to be whole minutes"
+            )),
+            Some(seconds) => Ok(Interval(seconds)),
+        }
+    }
+}
+
+/// The requests of one interval of the time series
+#[derive(Debug, Clone)]
+pub struct SeriesPoint {
+    pub start: DateTime<FixedOffset>,
+    pub requests: usize,
+    /// The response times of the requests, where the log format has them
+    pub latencies: Latencies,
+    /// The requests with a 5xx and with a 4xx response
+    pub server_errors: usize,
+    pub client_errors: usize,
+    /// Bytes of the response bodies
+    pub bytes_sent: u64,
+}
+
+impl TopInfo {
+    /// The requests per interval of `set_interval`, in order. Intervals
+    /// without requests aren't included.
+    pub fn time_series(&self) -> Vec<SeriesPoint> {
+        let (requests, latencies, server_errors, client_errors, bytes_sent) =
+            if self.interval.is_sub_minute() {
+                (
+                    &self.requests_per_second,
+                    &self.latencies_per_second,
+                    &self.server_errors_per_second,
+                    &self.client_errors_per_second,
+                    &self.bytes_sent_per_second,
+                )
+            } else {
+                (
+                    &self.requests_per_minute,
+                    &self.latencies_per_minute,
+                    &self.server_errors_per_minute,
+                    &self.client_errors_per_minute,
+                    &self.bytes_sent_per_minute,
+                )
+            };
+
+        let mut points: BTreeMap<DateTime<FixedOffset>, SeriesPoint> = BTreeMap::new();
+        for (time, count) in requests.iter() {
+            let start = self.interval.start(*time);
+            let point = points.entry(start).or_insert_with(|| SeriesPoint {
+                start,
+                requests: 0,
+                latencies: Latencies::default(),
+                server_errors: 0,
+                client_errors: 0,
+                bytes_sent: 0,
+            });
+            point.requests += count;
+            point.latencies.merge(latencies.get(time));
+            point.server_errors += server_errors.get(time);
+            point.client_errors += client_errors.get(time);
+            point.bytes_sent += *bytes_sent.get(time) as u64;
+        }
+        points.into_values().collect()
+    }
+}
+
+// the most characters of the sparkline, longer logs share a character between minutes
+const SPARKLINE_WIDTH: usize = 60;
+const SPARKLINE_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+impl TopInfo {
+    /// The requests per minute across the log duration as a line of block
+    /// characters, one per minute or per group of minutes for logs over an
+    /// hour. Minutes without requests are blank, so outages stand out.
+    pub(crate) fn sparkline(&self) -> Option<String> {
+        if self.requests_per_minute.is_empty() {
+            return None;
+        }
+
+        let (first, minutes) = self.minutes()?;
+        let width = minutes.min(SPARKLINE_WIDTH);
+
+        let mut counts = vec![0usize; width];
+        for (time, count) in self.requests_per_minute.iter() {
+            let index = ((*time - first).num_minutes().max(0) as usize).min(minutes - 1);
+            counts[index * width / minutes] += count;
+        }
+
+        let max = counts.iter().copied().max().unwrap_or(0).max(1);
+        Some(
+            counts
+                .iter()
+                .map(|&count| match count {
+                    0 => ' ',
+                    count => SPARKLINE_LEVELS[(count * 8).div_ceil(max) - 1],
+                })
+                .collect(),
+        )
+    }
+}
+
+impl TopInfo {
+    /// The oldest and newest log entries, `None` until a request is counted
+    pub fn log_duration(&self) -> Option<&LogDuration> {
+        (self.total_requests > 0).then_some(&self.duration)
+    }
+
+    // the first minute of the logs and the number of minutes up to the last one
+    fn minutes(&self) -> Option<(DateTime<FixedOffset>, usize)> {
+        let duration = self.log_duration()?;
+        let minute = Interval::default();
+        let first = minute.start(duration.start);
+        let minutes = (minute.start(duration.end) - first).num_minutes().max(0) + 1;
+        Some((first, minutes as usize))
+    }
+
+    /// The covered time, the average requests per second and the share of
+    /// the minutes with traffic, `None` until a request is counted
+    pub(crate) fn coverage(&self) -> Option<Coverage> {
+        let duration = self.log_duration()?;
+        let (_, minutes) = self.minutes()?;
+        let seconds = (duration.end - duration.start).num_seconds().max(0);
+        // a minute without requests isn't counted at all
+        let active_minutes = self.requests_per_minute.len().min(minutes);
+        Some(Coverage {
+            seconds,
+            requests_per_second: round3(self.total_requests as f64 / seconds.max(1) as f64),
+            active_minutes,
+            minutes,
+            active_percent: percent(active_minutes, minutes),
+        })
+    }
+}
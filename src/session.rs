@@ -0,0 +1,177 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::Entry;
+use chrono::prelude::*;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A single request of a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Visit {
+    pub timestamp: DateTime<FixedOffset>,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+}
+
+/// The chronological requests of one client, without a gap longer than the
+/// session timeout
+#[derive(Debug)]
+pub struct Session<'a> {
+    pub client: &'a str,
+    pub visits: Vec<&'a Visit>,
+}
+
+impl<'a> Session<'a> {
+    /// The path of the first request
+    pub fn entry_page(&self) -> &'a str {
+        self.visits.first().map_or("", |visit| visit.path.as_str())
+    }
+
+    /// The path of the last request
+    pub fn exit_page(&self) -> &'a str {
+        self.visits.last().map_or("", |visit| visit.path.as_str())
+    }
+
+    /// A single request session, the client left right away
+    pub fn is_bounce(&self) -> bool {
+        self.visits.len() == 1
+    }
+
+    /// Time between the first and the last request
+    pub fn duration(&self) -> Duration {
+        match (self.visits.first(), self.visits.last()) {
+            (Some(first), Some(last)) => last.timestamp - first.timestamp,
+            _ => Duration::zero(),
+        }
+    }
+}
+
+/// Groups requests into per-client sessions. Clients are told apart by their
+/// IP address and user agent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sessions {
+    // only the requests are saved with the counts
+    #[serde(skip)]
+    timeout: Duration,
+    clients: HashMap<String, Vec<Visit>>,
+}
+
+impl Sessions {
+    /// A new session starts when a client is inactive for longer than `timeout`
+    pub fn new(timeout: Duration) -> Sessions {
+        Sessions {
+            timeout,
+            clients: HashMap::new(),
+        }
+    }
+
+    // the same timeout, without any requests
+    pub(crate) fn cleared(&self) -> Sessions {
+        Sessions::new(self.timeout)
+    }
+
+    // adds the requests of `other` after the ones recorded so far
+    pub(crate) fn merge(&mut self, other: Sessions) {
+        for (client, visits) in other.clients {
+            self.clients.entry(client).or_default().extend(visits);
+        }
+    }
+
+    pub fn record(&mut self, entry: &Entry) {
+        let Some(ip) = entry.client() else {
+            return;
+        };
+        let client = match &entry.user_agent {
+            Some(user_agent) => format!("{ip} {user_agent}"),
+            None => ip.to_string(),
+        };
+
+        self.clients.entry(client).or_default().push(Visit {
+            timestamp: entry.timestamp,
+            method: entry.method.clone().unwrap_or_default(),
+            path: entry.path.clone().unwrap_or_default(),
+            status: entry.status,
+        });
+    }
+
+    /// Every request of every client in chronological order, ordered by client
+    pub fn clients(&self) -> Vec<(&str, Vec<&Visit>)> {
+        let mut clients: Vec<_> = self
+            .clients
+            .iter()
+            .map(|(client, visits)| {
+                // log files don't have to be given in order
+                let mut visits: Vec<&Visit> = visits.iter().collect();
+                visits.sort_by_key(|visit| visit.timestamp);
+                (client.as_str(), visits)
+            })
+            .collect();
+        clients.sort_by_key(|(client, _)| *client);
+        clients
+    }
+
+    /// Every session, ordered by client and start time
+    pub fn sessions(&self) -> Vec<Session<'_>> {
+        let mut sessions = Vec::new();
+        for (client, visits) in self.clients() {
+            let mut current: Vec<&Visit> = Vec::new();
+            for visit in visits {
+                if let Some(last) = current.last() {
+                    if visit.timestamp - last.timestamp > self.timeout {
+                        sessions.push(Session {
+                            client,
+                            visits: std::mem::take(&mut current),
+                        });
+                    }
+                }
+                current.push(visit);
+            }
+            if !current.is_empty() {
+                sessions.push(Session {
+                    client,
+                    visits: current,
+                });
+            }
+        }
+        sessions
+    }
+}
+
+/// True if `path` matches `pattern`, where `*` in the pattern matches any
+/// number of characters
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match path.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    match parts.split_last() {
+        // no wildcard, the whole path has to match
+        None => rest.is_empty(),
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(i) => rest = &rest[i + part.len()..],
+                    None => return false,
+                }
+            }
+            rest.ends_with(last)
+        }
+    }
+}
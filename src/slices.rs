@@ -0,0 +1,168 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A condensed summary of every time slice of the logs, like every hour, to
+//! see how the traffic changed over an incident window. Slices are counted as
+//! the logs are read, only when asked for with `TopInfo::set_slices`.
+use crate::report::{percent, top_row};
+use crate::{DefaultHashMap, Interval, Latencies, ReportRow};
+use chrono::prelude::*;
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SliceCounts {
+    requests: usize,
+    server_errors: usize,
+    latencies: Latencies,
+    /// Without query params
+    paths: DefaultHashMap<String, usize>,
+}
+
+/// The requests by time slice
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Slices {
+    // a setting, restored slices are merged into the slices of this interval
+    #[serde(skip)]
+    interval: Interval,
+    slices: BTreeMap<DateTime<FixedOffset>, SliceCounts>,
+}
+
+impl Slices {
+    pub fn new(interval: Interval) -> Slices {
+        Slices {
+            interval,
+            slices: BTreeMap::new(),
+        }
+    }
+
+    /// The same interval, without any requests
+    pub fn cleared(&self) -> Slices {
+        Slices::new(self.interval)
+    }
+
+    /// Counts a request, `path` is `None` for requests without a valid one
+    pub fn record(
+        &mut self,
+        timestamp: DateTime<FixedOffset>,
+        status: StatusCode,
+        response_time: Option<f64>,
+        path: Option<&str>,
+    ) {
+        let slice = self
+            .slices
+            .entry(self.interval.start(timestamp))
+            .or_default();
+        slice.requests += 1;
+        if status.is_server_error() {
+            slice.server_errors += 1;
+        }
+        if let Some(response_time) = response_time {
+            slice.latencies.record(response_time);
+        }
+        if let Some(path) = path {
+            slice.paths[path.to_string()] += 1;
+        }
+    }
+
+    /// Adds the requests of `other` to the slices they start in
+    pub fn merge(&mut self, other: Slices) {
+        for (start, counts) in other.slices {
+            let slice = self.slices.entry(self.interval.start(start)).or_default();
+            slice.requests += counts.requests;
+            slice.server_errors += counts.server_errors;
+            slice.latencies.merge(&counts.latencies);
+            for (path, count) in counts.paths.iter() {
+                slice.paths[path.clone()] += count;
+            }
+        }
+    }
+
+    /// The summary of every slice with requests, in order
+    pub fn report(&self) -> SliceReport {
+        SliceReport {
+            interval: self.interval.to_string(),
+            label: self.interval.label(),
+            slices: self
+                .slices
+                .iter()
+                .map(|(start, counts)| SliceSummary {
+                    start: *start,
+                    requests: counts.requests,
+                    server_errors: counts.server_errors,
+                    error_rate: percent(counts.server_errors, counts.requests),
+                    p95_response_time: counts.latencies.percentile(95.0),
+                    top_path: top_row(counts.paths.iter()),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The summaries of the time slices
+#[derive(Debug, Serialize)]
+pub struct SliceReport {
+    /// The length of the slices, like `1h`
+    pub interval: String,
+    #[serde(skip)]
+    pub label: String,
+    pub slices: Vec<SliceSummary>,
+}
+
+/// The requests of one time slice, like the headline of the whole logs
+#[derive(Debug, Serialize)]
+pub struct SliceSummary {
+    pub start: DateTime<FixedOffset>,
+    pub requests: usize,
+    pub server_errors: usize,
+    /// Percentage of requests with a 5xx response
+    pub error_rate: f64,
+    /// In seconds, only known for log formats that record response times
+    pub p95_response_time: Option<f64>,
+    /// Without query params
+    pub top_path: Option<ReportRow>,
+}
+
+impl SliceReport {
+    /// The column headers of `SliceSummary::cells`
+    pub const HEADERS: [&'static str; 5] = [
+        "Start",
+        "Requests",
+        "Error Rate (5xx)",
+        "p95 Response Time (s)",
+        "Top Path",
+    ];
+
+    pub fn title(&self) -> String {
+        format!("Summary per {}:", self.label)
+    }
+}
+
+impl SliceSummary {
+    /// The display value of each column, unknown values are `-`
+    pub fn cells(&self) -> Vec<String> {
+        vec![
+            self.start.to_string(),
+            self.requests.to_string(),
+            format!("{}% ({})", self.error_rate, self.server_errors),
+            self.p95_response_time
+                .map_or_else(|| "-".to_string(), |t| t.to_string()),
+            self.top_path.as_ref().map_or_else(
+                || "-".to_string(),
+                |row| format!("{} ({})", row.key, row.count),
+            ),
+        ]
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The counts of an analysis in a form that can be written to disk, so a run
+//! can be resumed or its counts combined with those of other runs.
+//!
+//! Only counts are saved, not settings. Counts are restored into a `TopInfo`
+//! set up with the same sections, filters, drills, segment and extra fields
+//! as the one that saved them.
+use crate::drill::DrillState;
+use crate::extract::ExtractState;
+use crate::segment::SegmentState;
+use crate::timeline::TimelineState;
+use crate::unique;
+use crate::{
+    Breakdown, DefaultHashMap, ExtraFields, Latencies, LogDuration, Outliers, Segment, Sessions,
+    Slices, Stats, Tags, TimeHistogram, TopInfo, UniqueCount,
+};
+use anyhow::{bail, Result};
+use chrono::prelude::*;
+use http::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::net::IpAddr;
+
+// bumped whenever the layout of `State` changes, older states can't be read then
+const VERSION: u32 = 21;
+
+type Counts<'a, K> = Cow<'a, DefaultHashMap<K, usize>>;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct State<'a> {
+    version: u32,
+    // how distinct values are hashed, see `unique::HASH`
+    unique_hash: Cow<'a, str>,
+    duration: LogDuration,
+    total_requests: usize,
+    errors: usize,
+    filtered: usize,
+    // status codes, methods and URIs as their text, `http` types aren't serializable
+    response_codes: Vec<(u16, usize)>,
+    invalid_statuses: usize,
+    request_methods: Vec<(String, usize)>,
+    invalid_methods: usize,
+    requests_no_query: Counts<'a, String>,
+    requests_query: Counts<'a, String>,
+    client_ips: Counts<'a, IpAddr>,
+    referrers: Vec<(String, usize)>,
+    user_agents: Counts<'a, String>,
+    backend_ips: Counts<'a, IpAddr>,
+    x_forwarded_fors: Counts<'a, String>,
+    hosts: Counts<'a, String>,
+    host_clients: Cow<'a, DefaultHashMap<String, UniqueCount>>,
+    app_ids: Counts<'a, String>,
+    app_indexes: Counts<'a, u16>,
+    response_times: Cow<'a, TimeHistogram>,
+    gorouter_times: Cow<'a, TimeHistogram>,
+    x_cf_routererrors: Counts<'a, String>,
+    requests_per_minute: Counts<'a, DateTime<FixedOffset>>,
+    latencies_per_minute: Cow<'a, DefaultHashMap<DateTime<FixedOffset>, Latencies>>,
+    requests_per_second: Counts<'a, DateTime<FixedOffset>>,
+    latencies_per_second: Cow<'a, DefaultHashMap<DateTime<FixedOffset>, Latencies>>,
+    server_errors_per_minute: Counts<'a, DateTime<FixedOffset>>,
+    client_errors_per_minute: Counts<'a, DateTime<FixedOffset>>,
+    server_errors_per_second: Counts<'a, DateTime<FixedOffset>>,
+    client_errors_per_second: Counts<'a, DateTime<FixedOffset>>,
+    bytes_sent_per_minute: Counts<'a, DateTime<FixedOffset>>,
+    bytes_sent_per_second: Counts<'a, DateTime<FixedOffset>>,
+    error_paths: Counts<'a, String>,
+    client_error_paths: Counts<'a, String>,
+    client_errors: Counts<'a, IpAddr>,
+    pii_paths: Counts<'a, String>,
+    malformed_requests: Counts<'a, String>,
+    extra_fields: Cow<'a, ExtraFields>,
+    tags: Cow<'a, Tags>,
+    unique_client_ips: Cow<'a, UniqueCount>,
+    unique_paths: Cow<'a, UniqueCount>,
+    unique_user_agents: Cow<'a, UniqueCount>,
+    response_time_stats: Stats,
+    gorouter_time_stats: Stats,
+    bytes_sent: u64,
+    bytes_received: Option<u64>,
+    request_sizes: Counts<'a, usize>,
+    bytes_received_by_path: Counts<'a, String>,
+    outliers: Cow<'a, Outliers>,
+    slowest: Option<Cow<'a, Outliers>>,
+    sessions: Option<Cow<'a, Sessions>>,
+    slices: Option<Cow<'a, Slices>>,
+    drills: Vec<DrillState>,
+    segment: Option<SegmentState>,
+    timelines: Vec<TimelineState>,
+    extracts: Vec<ExtractState>,
+    breakdowns: Cow<'a, [Breakdown]>,
+}
+
+impl TopInfo {
+    // the counts so far, borrowed where they don't have to be converted
+    pub(crate) fn state(&self) -> State<'_> {
+        State {
+            version: VERSION,
+            unique_hash: Cow::Borrowed(unique::HASH),
+            duration: self.duration.clone(),
+            total_requests: self.total_requests,
+            errors: self.errors,
+            filtered: self.filtered,
+            response_codes: self
+                .response_codes
+                .iter()
+                .map(|(status, count)| (status.as_u16(), *count))
+                .collect(),
+            invalid_statuses: self.invalid_statuses,
+            request_methods: self
+                .request_methods
+                .iter()
+                .map(|(method, count)| (method.to_string(), *count))
+                .collect(),
+            invalid_methods: self.invalid_methods,
+            requests_no_query: Cow::Borrowed(&self.requests_no_query),
+            requests_query: Cow::Borrowed(&self.requests_query),
+            client_ips: Cow::Borrowed(&self.client_ips),
+            referrers: self
+                .referrers
+                .iter()
+                .map(|(referrer, count)| (referrer.to_string(), *count))
+                .collect(),
+            user_agents: Cow::Borrowed(&self.user_agents),
+            backend_ips: Cow::Borrowed(&self.backend_ips),
+            x_forwarded_fors: Cow::Borrowed(&self.x_forwarded_fors),
+            hosts: Cow::Borrowed(&self.hosts),
+            host_clients: Cow::Borrowed(&self.host_clients),
+            app_ids: Cow::Borrowed(&self.app_ids),
+            app_indexes: Cow::Borrowed(&self.app_indexes),
+            response_times: Cow::Borrowed(&self.response_times),
+            gorouter_times: Cow::Borrowed(&self.gorouter_times),
+            x_cf_routererrors: Cow::Borrowed(&self.x_cf_routererrors),
+            requests_per_minute: Cow::Borrowed(&self.requests_per_minute),
+            latencies_per_minute: Cow::Borrowed(&self.latencies_per_minute),
+            requests_per_second: Cow::Borrowed(&self.requests_per_second),
+            latencies_per_second: Cow::Borrowed(&self.latencies_per_second),
+            server_errors_per_minute: Cow::Borrowed(&self.server_errors_per_minute),
+            client_errors_per_minute: Cow::Borrowed(&self.client_errors_per_minute),
+            server_errors_per_second: Cow::Borrowed(&self.server_errors_per_second),
+            client_errors_per_second: Cow::Borrowed(&self.client_errors_per_second),
+            bytes_sent_per_minute: Cow::Borrowed(&self.bytes_sent_per_minute),
+            bytes_sent_per_second: Cow::Borrowed(&self.bytes_sent_per_second),
+            error_paths: Cow::Borrowed(&self.error_paths),
+            client_error_paths: Cow::Borrowed(&self.client_error_paths),
+            client_errors: Cow::Borrowed(&self.client_errors),
+            pii_paths: Cow::Borrowed(&self.pii_paths),
+            malformed_requests: Cow::Borrowed(&self.malformed_requests),
+            extra_fields: Cow::Borrowed(&self.extra_fields),
+            tags: Cow::Borrowed(&self.tags),
+            unique_client_ips: Cow::Borrowed(&self.unique_client_ips),
+            unique_paths: Cow::Borrowed(&self.unique_paths),
+            unique_user_agents: Cow::Borrowed(&self.unique_user_agents),
+            response_time_stats: self.response_time_stats.clone(),
+            gorouter_time_stats: self.gorouter_time_stats.clone(),
+            bytes_sent: self.bytes_sent,
+            bytes_received: self.bytes_received,
+            request_sizes: Cow::Borrowed(&self.request_sizes),
+            bytes_received_by_path: Cow::Borrowed(&self.bytes_received_by_path),
+            outliers: Cow::Borrowed(&self.outliers),
+            slowest: self.slowest.as_ref().map(Cow::Borrowed),
+            sessions: self.sessions.as_ref().map(Cow::Borrowed),
+            slices: self.slices.as_ref().map(Cow::Borrowed),
+            drills: self.drills.iter().map(|drill| drill.state()).collect(),
+            segment: self.segment.as_ref().map(Segment::state),
+            timelines: self
+                .timelines
+                .iter()
+                .map(|timeline| timeline.state())
+                .collect(),
+            extracts: self
+                .extracts
+                .iter()
+                .map(|extract| extract.state())
+                .collect(),
+            breakdowns: Cow::Borrowed(&self.breakdowns),
+        }
+    }
+
+    // adds the counts of `state` to the ones counted so far. Sessions, slowest
+    // requests, slices, drills, timelines, extracts, latency breakdowns, the
+    // segment and extra fields are only restored when they're tracked here too.
+    pub(crate) fn restore(&mut self, state: State) -> Result<()> {
+        if state.version != VERSION {
+            bail!(
+                "saved with an incompatible version of top-logs, format {} instead of {VERSION}",
+                state.version
+            );
+        }
+        if state.unique_hash != unique::HASH {
+            bail!(
+                "distinct values were counted with the {} hash instead of {}",
+                state.unique_hash,
+                unique::HASH
+            );
+        }
+
+        let mut other = self.cleared();
+        other.duration = state.duration;
+        other.total_requests = state.total_requests;
+        other.errors = state.errors;
+        other.filtered = state.filtered;
+        for (status, count) in state.response_codes {
+            if let Ok(status) = StatusCode::from_u16(status) {
+                other.response_codes[status] += count;
+            }
+        }
+        other.invalid_statuses = state.invalid_statuses;
+        for (method, count) in state.request_methods {
+            if let Ok(method) = method.parse::<Method>() {
+                other.request_methods[method] += count;
+            }
+        }
+        other.invalid_methods = state.invalid_methods;
+        other.requests_no_query = state.requests_no_query.into_owned();
+        other.requests_query = state.requests_query.into_owned();
+        other.client_ips = state.client_ips.into_owned();
+        for (referrer, count) in state.referrers {
+            if let Ok(referrer) = referrer.parse::<http::Uri>() {
+                other.referrers[referrer] += count;
+            }
+        }
+        other.user_agents = state.user_agents.into_owned();
+        other.backend_ips = state.backend_ips.into_owned();
+        other.x_forwarded_fors = state.x_forwarded_fors.into_owned();
+        other.hosts = state.hosts.into_owned();
+        other.host_clients = state.host_clients.into_owned();
+        other.app_ids = state.app_ids.into_owned();
+        other.app_indexes = state.app_indexes.into_owned();
+        other.response_times = state.response_times.into_owned();
+        other.gorouter_times = state.gorouter_times.into_owned();
+        other.x_cf_routererrors = state.x_cf_routererrors.into_owned();
+        other.requests_per_minute = state.requests_per_minute.into_owned();
+        other.latencies_per_minute = state.latencies_per_minute.into_owned();
+        other.requests_per_second = state.requests_per_second.into_owned();
+        other.latencies_per_second = state.latencies_per_second.into_owned();
+        other.server_errors_per_minute = state.server_errors_per_minute.into_owned();
+        other.client_errors_per_minute = state.client_errors_per_minute.into_owned();
+        other.server_errors_per_second = state.server_errors_per_second.into_owned();
+        other.client_errors_per_second = state.client_errors_per_second.into_owned();
+        other.bytes_sent_per_minute = state.bytes_sent_per_minute.into_owned();
+        other.bytes_sent_per_second = state.bytes_sent_per_second.into_owned();
+        other.error_paths = state.error_paths.into_owned();
+        other.client_error_paths = state.client_error_paths.into_owned();
+        other.client_errors = state.client_errors.into_owned();
+        other.pii_paths = state.pii_paths.into_owned();
+        other.malformed_requests = state.malformed_requests.into_owned();
+        other.extra_fields = state.extra_fields.into_owned();
+        other.tags = state.tags.into_owned();
+        other.unique_client_ips = state.unique_client_ips.into_owned();
+        other.unique_paths = state.unique_paths.into_owned();
+        other.unique_user_agents = state.unique_user_agents.into_owned();
+        other.response_time_stats = state.response_time_stats;
+        other.gorouter_time_stats = state.gorouter_time_stats;
+        other.bytes_sent = state.bytes_sent;
+        other.bytes_received = state.bytes_received;
+        other.request_sizes = state.request_sizes.into_owned();
+        other.bytes_received_by_path = state.bytes_received_by_path.into_owned();
+        // kept up to the number of top entries of this run, not of the saved one
+        other.outliers.merge(&state.outliers);
+        if let (Some(slowest), Some(saved)) = (&mut other.slowest, state.slowest) {
+            slowest.merge(&saved);
+        }
+        if let (Some(sessions), Some(saved)) = (&mut other.sessions, state.sessions) {
+            sessions.merge(saved.into_owned());
+        }
+        if let (Some(slices), Some(saved)) = (&mut other.slices, state.slices) {
+            slices.merge(saved.into_owned());
+        }
+        for drill in &mut other.drills {
+            drill.restore(&state.drills);
+        }
+        if let (Some(segment), Some(saved)) = (&mut other.segment, &state.segment) {
+            segment.restore(saved);
+        }
+        for timeline in &mut other.timelines {
+            timeline.restore(&state.timelines);
+        }
+        for extract in &mut other.extracts {
+            extract.restore(&state.extracts);
+        }
+        for breakdown in &mut other.breakdowns {
+            breakdown.restore(&state.breakdowns);
+        }
+
+        self.merge(other);
+        Ok(())
+    }
+}
@@ -0,0 +1,327 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::diagnostics::{self, Kind};
+use crate::reader::{parse_line, ReadOptions};
+use access_log_parser::{LogEntry, LogType};
+use anyhow::{Context, Result};
+use std::io::{ErrorKind, Read};
+use std::net::{SocketAddr, TcpListener, TcpStream, UdpSocket};
+
+// the most messages read in one go, so a busy sender doesn't hold up the report
+const MAX_MESSAGES_PER_READ: usize = 100_000;
+
+// the most bytes read from one TCP connection in one go
+const MAX_BYTES_PER_READ: usize = 16 << 20;
+
+// a TCP sender that never ends a message is cut off after this many bytes
+const MAX_MESSAGE_LEN: usize = 1 << 20;
+
+// large enough for any UDP datagram
+const UDP_BUFFER_LEN: usize = 65_536;
+
+// a TCP connection from a syslog sender with the bytes not yet framed into messages
+struct Connection {
+    stream: TcpStream,
+    peer: SocketAddr,
+    buffer: Vec<u8>,
+}
+
+/// Receives syslog messages over UDP, TCP or both and passes the access log
+/// lines in them on, so log forwarders like gorouter can send their logs
+/// straight to top-logs
+pub struct SyslogListener {
+    udp: Option<UdpSocket>,
+    tcp: Option<TcpListener>,
+    connections: Vec<Connection>,
+}
+
+impl SyslogListener {
+    /// Binds `address`, like 0.0.0.0:5514, for the selected protocols
+    pub fn bind(address: &str, udp: bool, tcp: bool) -> Result<SyslogListener> {
+        let udp = udp
+            .then(|| -> Result<UdpSocket> {
+                let socket =
+                    UdpSocket::bind(address).with_context(|| format!("binding udp {address}"))?;
+                socket.set_nonblocking(true)?;
+                Ok(socket)
+            })
+            .transpose()?;
+        let tcp = tcp
+            .then(|| -> Result<TcpListener> {
+                let listener =
+                    TcpListener::bind(address).with_context(|| format!("binding tcp {address}"))?;
+                listener.set_nonblocking(true)?;
+                Ok(listener)
+            })
+            .transpose()?;
+
+        Ok(SyslogListener {
+            udp,
+            tcp,
+            connections: Vec::new(),
+        })
+    }
+
+    /// The bound addresses, like `udp 0.0.0.0:5514`
+    pub fn addresses(&self) -> Vec<String> {
+        let udp = self.udp.as_ref().and_then(|s| s.local_addr().ok());
+        let tcp = self.tcp.as_ref().and_then(|l| l.local_addr().ok());
+        udp.map(|a| format!("udp {a}"))
+            .into_iter()
+            .chain(tcp.map(|a| format!("tcp {a}")))
+            .collect()
+    }
+
+    /// Calls `f` with every entry, and its line, parsed from the messages
+    /// received since the last call and returns the number of messages that
+    /// couldn't be parsed
+    pub fn read_new<F>(
+        &mut self,
+        log_type: LogType,
+        options: &ReadOptions,
+        mut f: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(LogEntry, &str) -> Result<()>,
+    {
+        let mut messages = Vec::new();
+        self.receive_datagrams(&mut messages);
+        self.accept_connections();
+        self.receive_streams(&mut messages);
+
+        let mut errors = 0;
+        for message in &messages {
+            let line = strip_envelope(message);
+            if line.trim().is_empty() {
+                continue;
+            }
+            if !parse_line(line, log_type, options, &mut f)? {
+                errors += 1;
+            }
+        }
+        Ok(errors)
+    }
+
+    // every datagram is one message
+    fn receive_datagrams(&mut self, messages: &mut Vec<String>) {
+        let Some(socket) = &self.udp else {
+            return;
+        };
+        let mut buffer = vec![0; UDP_BUFFER_LEN];
+        while messages.len() < MAX_MESSAGES_PER_READ {
+            match socket.recv_from(&mut buffer) {
+                Ok((len, _)) => messages.push(String::from_utf8_lossy(&buffer[..len]).into_owned()),
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    diagnostics::emit(Kind::ReadError, &format!("receiving syslog: {err}"));
+                    break;
+                }
+            }
+        }
+    }
+
+    fn accept_connections(&mut self) {
+        let Some(listener) = &self.tcp else {
+            return;
+        };
+        loop {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    if let Err(err) = stream.set_nonblocking(true) {
+                        diagnostics::emit(Kind::ReadError, &format!("accepting {peer}: {err}"));
+                        continue;
+                    }
+                    self.connections.push(Connection {
+                        stream,
+                        peer,
+                        buffer: Vec::new(),
+                    });
+                }
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(err) => {
+                    diagnostics::emit(Kind::ReadError, &format!("accepting syslog: {err}"));
+                    break;
+                }
+            }
+        }
+    }
+
+    // reads what every connection sent and frames it into messages, dropping
+    // closed connections
+    fn receive_streams(&mut self, messages: &mut Vec<String>) {
+        let mut chunk = [0; 8192];
+        self.connections.retain_mut(|conn| {
+            let open = loop {
+                if conn.buffer.len() >= MAX_BYTES_PER_READ {
+                    break true;
+                }
+                match conn.stream.read(&mut chunk) {
+                    Ok(0) => break false,
+                    Ok(len) => conn.buffer.extend_from_slice(&chunk[..len]),
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => break true,
+                    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                    Err(err) => {
+                        diagnostics::emit(
+                            Kind::ReadError,
+                            &format!("reading {}: {err}", conn.peer),
+                        );
+                        break false;
+                    }
+                }
+            };
+
+            while let Some(message) = next_frame(&mut conn.buffer, !open) {
+                messages.push(String::from_utf8_lossy(&message).into_owned());
+            }
+            open
+        });
+    }
+}
+
+// takes the next message off `buffer`, which holds either octet counted
+// (`<length> <message>`) or newline terminated messages (RFC 6587). With
+// `closed`, the rest of the buffer is the last message.
+fn next_frame(buffer: &mut Vec<u8>, closed: bool) -> Option<Vec<u8>> {
+    if buffer.is_empty() {
+        return None;
+    }
+
+    if buffer[0].is_ascii_digit() {
+        if let Some(space) = buffer.iter().position(|b| *b == b' ') {
+            let len = std::str::from_utf8(&buffer[..space])
+                .ok()
+                .and_then(|digits| digits.parse::<usize>().ok());
+            if let Some(len) = len {
+                let end = space + 1 + len;
+                if buffer.len() >= end {
+                    let message = buffer[space + 1..end].to_vec();
+                    buffer.drain(..end);
+                    return Some(message);
+                }
+                if !closed {
+                    return None;
+                }
+            }
+        }
+    }
+
+    match buffer.iter().position(|b| *b == b'\n') {
+        Some(newline) => {
+            let message = buffer[..newline].to_vec();
+            buffer.drain(..=newline);
+            Some(message)
+        }
+        None if closed || buffer.len() >= MAX_MESSAGE_LEN => Some(std::mem::take(buffer)),
+        None => None,
+    }
+}
+
+/// Returns the message of an RFC 5424 or RFC 3164 syslog message, without
+/// the priority, timestamp, host name, app name and structured data in front
+/// of it. Lines without a syslog header are returned as they are.
+pub fn strip_envelope(message: &str) -> &str {
+    let message = message.trim_end_matches(['\r', '\n', '\0']);
+    let Some(rest) = message
+        .strip_prefix('<')
+        .and_then(|rest| rest.split_once('>'))
+        .filter(|(pri, _)| (1..=3).contains(&pri.len()) && pri.bytes().all(|b| b.is_ascii_digit()))
+        .map(|(_, rest)| rest)
+    else {
+        return message;
+    };
+
+    match rest.split_once(' ') {
+        Some((version, header))
+            if !version.is_empty() && version.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            strip_rfc5424(header)
+        }
+        _ => strip_rfc3164(rest),
+    }
+}
+
+// TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA [MSG]
+fn strip_rfc5424(header: &str) -> &str {
+    let mut rest = header;
+    for _ in 0..5 {
+        rest = match rest.split_once(' ') {
+            Some((_, rest)) => rest,
+            None => return "",
+        };
+    }
+
+    let message = match rest.strip_prefix('-') {
+        Some(message) => message,
+        None => skip_structured_data(rest),
+    };
+    let message = message.strip_prefix(' ').unwrap_or(message);
+    message.strip_prefix('\u{feff}').unwrap_or(message)
+}
+
+// skips `[id key="value" ...]` elements, values may contain escaped `"`, `]` and `\`
+fn skip_structured_data(data: &str) -> &str {
+    let mut in_element = false;
+    let mut in_value = false;
+    let mut escaped = false;
+    for (i, c) in data.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_value => escaped = true,
+            '"' if in_element => in_value = !in_value,
+            '[' if !in_element => in_element = true,
+            ']' if in_element && !in_value => in_element = false,
+            _ if !in_element => return &data[i..],
+            _ => {}
+        }
+    }
+    ""
+}
+
+// TIMESTAMP [HOSTNAME] [TAG:] MSG, where the timestamp is either like
+// `Jun  1 10:00:28` or, from newer senders, RFC 3339
+fn strip_rfc3164(header: &str) -> &str {
+    let rest = if is_bsd_timestamp(header) {
+        &header[15..]
+    } else {
+        match header.split_once(' ') {
+            Some((timestamp, rest)) if chrono::DateTime::parse_from_rfc3339(timestamp).is_ok() => {
+                rest
+            }
+            _ => header,
+        }
+    };
+    let rest = rest.trim_start_matches(' ');
+
+    // the tag, like `gorouter[42]:`, is optional and so is the host name before it
+    let (first, after_first) = rest.split_once(' ').unwrap_or((rest, ""));
+    if first.ends_with(':') {
+        return after_first;
+    }
+    match after_first.split_once(' ') {
+        Some((tag, message)) if tag.ends_with(':') => message,
+        _ => after_first,
+    }
+}
+
+// `Mmm dd hh:mm:ss `, the day is padded with a space
+fn is_bsd_timestamp(header: &str) -> bool {
+    let bytes = header.as_bytes();
+    bytes.len() > 15
+        && bytes[..3].iter().all(u8::is_ascii_alphabetic)
+        && bytes[3] == b' '
+        && bytes[6] == b' '
+        && bytes[9] == b':'
+        && bytes[12] == b':'
+        && bytes[15] == b' '
+}
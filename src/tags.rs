@@ -0,0 +1,100 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::reader::{matches_path, normalize_pattern};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Labels like `env=prod`, given for every input or only for the files
+/// matching a pattern, which are attached to the report so the reports of
+/// several runs can be told apart
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tags {
+    // only the values are saved with the counts
+    #[serde(skip)]
+    file_tags: Vec<(glob::Pattern, String, String)>,
+    values: BTreeMap<String, BTreeSet<String>>,
+}
+
+// `key=value`, where the key has to be a valid label name for every output
+fn parse_tag(tag: &str) -> Result<(String, String)> {
+    let (key, value) = tag
+        .split_once('=')
+        .ok_or_else(|| anyhow!("invalid tag '{tag}', expected KEY=VALUE"))?;
+    let key = key.trim();
+    let valid = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!("invalid tag '{tag}', keys may only contain letters, digits and '_' and can't start with a digit");
+    }
+    Ok((key.to_string(), value.trim().to_string()))
+}
+
+impl Tags {
+    /// Parses `key=value` tags for every input and `pattern=key=value` tags for
+    /// the files matching the pattern, like `gorouter/**=source=gorouter`
+    pub fn new(tags: &[String], file_tags: &[String]) -> Result<Tags> {
+        let mut values: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for tag in tags {
+            let (key, value) = parse_tag(tag)?;
+            values.entry(key).or_default().insert(value);
+        }
+
+        let file_tags = file_tags
+            .iter()
+            .map(|rule| {
+                let (pattern, tag) = rule.split_once('=').ok_or_else(|| {
+                    anyhow!("invalid file tag '{rule}', expected PATTERN=KEY=VALUE")
+                })?;
+                let pattern = glob::Pattern::new(&normalize_pattern(pattern))
+                    .with_context(|| format!("invalid pattern in file tag '{rule}'"))?;
+                let (key, value) = parse_tag(tag)?;
+                Ok((pattern, key, value))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Tags { file_tags, values })
+    }
+
+    /// Adds the tags of the file tag rules matching `path`
+    pub fn add_file(&mut self, path: &str) {
+        for (pattern, key, value) in &self.file_tags {
+            if matches_path(pattern, path) {
+                self.values
+                    .entry(key.clone())
+                    .or_default()
+                    .insert(value.clone());
+            }
+        }
+    }
+
+    // adds the values of `other`, like the file tags of files read by another thread
+    pub(crate) fn merge(&mut self, other: Tags) {
+        for (key, values) in other.values {
+            self.values.entry(key).or_default().extend(values);
+        }
+    }
+
+    /// The tags by key. Keys with several values, like a file tag that
+    /// differs between files, have them sorted and separated by commas.
+    pub fn values(&self) -> BTreeMap<String, String> {
+        self.values
+            .iter()
+            .map(|(key, values)| {
+                let values: Vec<&str> = values.iter().map(|v| v.as_str()).collect();
+                (key.clone(), values.join(","))
+            })
+            .collect()
+    }
+}
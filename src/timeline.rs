@@ -0,0 +1,182 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The requests with one path, client, host, user agent or status over time,
+//! like the 502 responses per minute. Requests are counted per second, so the
+//! counts can be shown for any `--interval`.
+use crate::drill::Selector;
+use crate::parallel::add_counts;
+use crate::report::percent;
+use crate::{DefaultHashMap, Entry, Interval, LogDuration, SeriesPoint};
+use anyhow::Result;
+use chrono::prelude::*;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+// the counts of a timeline, without its selector, as saved by `TopInfo::state`
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TimelineState {
+    key: String,
+    requests_per_second: DefaultHashMap<DateTime<FixedOffset>, usize>,
+}
+
+/// Counts the requests selected like a `--drill` per second
+#[derive(Debug, Clone)]
+pub struct Timeline {
+    key: String,
+    selector: Selector,
+    requests_per_second: DefaultHashMap<DateTime<FixedOffset>, usize>,
+}
+
+impl FromStr for Timeline {
+    type Err = anyhow::Error;
+
+    /// Parses `field=value`, like `status=502` or `path=/checkout`
+    fn from_str(s: &str) -> Result<Self> {
+        let (key, selector) = Selector::parse(s, "timeline")?;
+        Ok(Timeline {
+            key,
+            selector,
+            requests_per_second: DefaultHashMap::new(),
+        })
+    }
+}
+
+impl Timeline {
+    // the same selection, without any requests
+    pub(crate) fn cleared(&self) -> Timeline {
+        Timeline {
+            key: self.key.clone(),
+            selector: self.selector.clone(),
+            requests_per_second: DefaultHashMap::new(),
+        }
+    }
+
+    // adds the requests counted by `other`, a cleared copy of this timeline
+    pub(crate) fn merge(&mut self, other: &Timeline) {
+        add_counts(&mut self.requests_per_second, &other.requests_per_second);
+    }
+
+    // the requests counted so far, to be saved with the other counts
+    pub(crate) fn state(&self) -> TimelineState {
+        TimelineState {
+            key: self.key.clone(),
+            requests_per_second: self.requests_per_second.clone(),
+        }
+    }
+
+    // adds the requests of the saved timeline with the same key, if there is one
+    pub(crate) fn restore(&mut self, saved: &[TimelineState]) {
+        if let Some(state) = saved.iter().find(|state| state.key == self.key) {
+            add_counts(&mut self.requests_per_second, &state.requests_per_second);
+        }
+    }
+
+    pub(crate) fn record(&mut self, entry: &Entry) {
+        if !self.selector.matches(entry) {
+            return;
+        }
+        let second = entry
+            .timestamp
+            .with_nanosecond(0)
+            .unwrap_or(entry.timestamp);
+        self.requests_per_second[second] += 1;
+    }
+
+    /// The selected requests of every interval from the start to the end of
+    /// `duration`, including the intervals without any. `series` are the
+    /// requests of all intervals, for the share of the selected ones.
+    pub(crate) fn report(
+        &self,
+        interval: Interval,
+        duration: &LogDuration,
+        series: &[SeriesPoint],
+    ) -> TimelineReport {
+        let mut counts: BTreeMap<DateTime<FixedOffset>, usize> = BTreeMap::new();
+        for (time, count) in self.requests_per_second.iter() {
+            *counts.entry(interval.start(*time)).or_default() += count;
+        }
+        let totals: BTreeMap<DateTime<FixedOffset>, usize> = series
+            .iter()
+            .map(|point| (point.start, point.requests))
+            .collect();
+
+        let mut points = Vec::new();
+        if !series.is_empty() {
+            let step = Duration::seconds(interval.seconds() as i64);
+            let mut start = interval.start(duration.start);
+            while start <= duration.end {
+                let requests = counts.get(&start).copied().unwrap_or(0);
+                let total = totals.get(&start).copied().unwrap_or(0);
+                points.push(TimelinePoint {
+                    start,
+                    requests,
+                    percent: percent(requests, total),
+                });
+                start += step;
+            }
+        }
+
+        TimelineReport {
+            key: self.key.clone(),
+            interval: interval.to_string(),
+            label: interval.label(),
+            requests: counts.values().sum(),
+            points,
+        }
+    }
+}
+
+/// The requests selected by a `--timeline` per interval
+#[derive(Debug, Serialize)]
+pub struct TimelineReport {
+    /// The field and value of the timeline, like `status=502`
+    pub key: String,
+    /// The length of the intervals, like `1m`
+    pub interval: String,
+    #[serde(skip)]
+    pub label: String,
+    pub requests: usize,
+    pub points: Vec<TimelinePoint>,
+}
+
+/// The selected requests of one interval
+#[derive(Debug, Serialize)]
+pub struct TimelinePoint {
+    pub start: DateTime<FixedOffset>,
+    pub requests: usize,
+    /// Percentage of all requests of the interval
+    pub percent: f64,
+}
+
+impl TimelineReport {
+    /// The column headers of `TimelinePoint::cells`
+    pub const HEADERS: [&'static str; 3] = ["Start", "Requests", "% of All"];
+
+    pub fn title(&self) -> String {
+        format!("Timeline of {} per {}:", self.key, self.label)
+    }
+}
+
+impl TimelinePoint {
+    pub fn cells(&self) -> Vec<String> {
+        vec![
+            self.start.to_string(),
+            self.requests.to_string(),
+            format!("{}%", self.percent),
+        ]
+    }
+}
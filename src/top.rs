@@ -0,0 +1,203 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The rows of a count table in sorted order, picked with a bounded heap
+//! rather than sorting every entry, so very large tables can be listed lazily.
+use crate::report::{status_label, ReportRow};
+use crate::{DefaultHashMap, Section, SortOrder, TopInfo};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::hash::Hash;
+use std::vec;
+
+// rows picked by each pass over the table
+const PAGE: usize = 1024;
+
+type Entries<'a> = Box<dyn Fn() -> Box<dyn Iterator<Item = (Cow<'a, str>, usize)> + 'a> + 'a>;
+
+// the same order as `SortOrder::sort_by_val` and `SortOrder::sort_by_key`
+fn compare(order: SortOrder, a: (&str, usize), b: (&str, usize)) -> Ordering {
+    match order {
+        SortOrder::ByValue => b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)),
+        SortOrder::ByKey => a.0.cmp(b.0).then_with(|| b.1.cmp(&a.1)),
+    }
+}
+
+// the heap keeps the worst of the picked entries on top
+struct Ranked<'a> {
+    key: Cow<'a, str>,
+    count: usize,
+    order: SortOrder,
+}
+
+impl Ranked<'_> {
+    fn entry(&self) -> (&str, usize) {
+        (&self.key, self.count)
+    }
+}
+
+impl PartialEq for Ranked<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked<'_> {}
+
+impl PartialOrd for Ranked<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(self.order, self.entry(), other.entry())
+    }
+}
+
+/// Picks the first `n` entries in `order` that come after `after`, best first.
+/// Only `n` entries are held at any time.
+pub(crate) fn select<'a>(
+    entries: impl Iterator<Item = (Cow<'a, str>, usize)>,
+    order: SortOrder,
+    after: Option<(&str, usize)>,
+    n: usize,
+) -> Vec<(Cow<'a, str>, usize)> {
+    let mut heap: BinaryHeap<Ranked> = BinaryHeap::new();
+    for (key, count) in entries {
+        if after.is_some_and(|after| compare(order, (&key, count), after) != Ordering::Greater) {
+            continue;
+        }
+        if heap.len() == n {
+            match heap.peek() {
+                Some(worst) if compare(order, (&key, count), worst.entry()) == Ordering::Less => {
+                    heap.pop();
+                }
+                _ => continue,
+            }
+        }
+        heap.push(Ranked { key, count, order });
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|ranked| (ranked.key, ranked.count))
+        .collect()
+}
+
+fn counts<'a, K>(
+    map: &'a DefaultHashMap<K, usize>,
+    key: fn(&'a K) -> Cow<'a, str>,
+    invalid: usize,
+) -> Entries<'a>
+where
+    K: Eq + Hash,
+{
+    Box::new(move || {
+        Box::new(
+            map.iter()
+                .map(move |(k, count)| (key(k), *count))
+                .chain((invalid > 0).then_some((Cow::Borrowed("invalid"), invalid))),
+        )
+    })
+}
+
+fn borrowed<K: AsRef<str>>(key: &K) -> Cow<'_, str> {
+    Cow::Borrowed(key.as_ref())
+}
+
+fn owned<K: ToString>(key: &K) -> Cow<'_, str> {
+    Cow::Owned(key.to_string())
+}
+
+/// The rows of a section in sorted order, see `TopInfo::iter_top`
+pub struct TopRows<'a> {
+    entries: Entries<'a>,
+    order: SortOrder,
+    page: vec::IntoIter<(Cow<'a, str>, usize)>,
+    // the last row returned, the next page starts after it
+    last: Option<(String, usize)>,
+    done: bool,
+}
+
+impl<'a> TopRows<'a> {
+    fn new(entries: Entries<'a>, order: SortOrder) -> Self {
+        TopRows {
+            entries,
+            order,
+            page: Vec::new().into_iter(),
+            last: None,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for TopRows<'_> {
+    type Item = ReportRow;
+
+    fn next(&mut self) -> Option<ReportRow> {
+        if self.page.len() == 0 && !self.done {
+            let after = self
+                .last
+                .as_ref()
+                .map(|(key, count)| (key.as_str(), *count));
+            let page = select((self.entries)(), self.order, after, PAGE);
+            self.done = page.len() < PAGE;
+            self.page = page.into_iter();
+        }
+
+        let (key, count) = self.page.next()?;
+        let key = key.into_owned();
+        self.last = Some((key.clone(), count));
+        Some(ReportRow::new(key, count))
+    }
+}
+
+impl TopInfo {
+    /// Streams every row of a count section in `order`, without the `top`
+    /// limit of the report. Rows are picked a page at a time, so memory stays
+    /// bounded however large the section is, at the cost of a pass over the
+    /// counts for each page. Returns `None` for sections which aren't a table
+    /// of counts, like the response time buckets or the sessions.
+    pub fn iter_top(&self, section: Section, order: SortOrder) -> Option<TopRows<'_>> {
+        let entries = match section {
+            Section::ResponseCodes => counts(
+                &self.response_codes,
+                |code| Cow::Owned(status_label(code)),
+                self.invalid_statuses,
+            ),
+            Section::RequestMethods => counts(&self.request_methods, owned, self.invalid_methods),
+            Section::RequestsNoQuery => counts(&self.requests_no_query, borrowed, 0),
+            Section::RequestsQuery => counts(&self.requests_query, borrowed, 0),
+            Section::UserAgents => counts(&self.user_agents, borrowed, 0),
+            Section::Referrers => counts(&self.referrers, owned, 0),
+            Section::ClientIps => counts(&self.client_ips, owned, 0),
+            Section::ErrorClientIps => counts(&self.client_errors, owned, 0),
+            Section::BackendIps => counts(&self.backend_ips, owned, 0),
+            Section::XForwardedFors => counts(&self.x_forwarded_fors, borrowed, 0),
+            Section::Hosts => counts(&self.hosts, borrowed, 0),
+            Section::AppIds => counts(&self.app_ids, borrowed, 0),
+            Section::AppIndexes => counts(&self.app_indexes, owned, 0),
+            Section::UploadPaths => counts(&self.bytes_received_by_path, borrowed, 0),
+            Section::XCfRouterErrors => counts(&self.x_cf_routererrors, borrowed, 0),
+            Section::MalformedRequests => counts(&self.malformed_requests, borrowed, 0),
+            Section::ServerErrorPaths => counts(&self.error_paths, borrowed, 0),
+            Section::ClientErrorPaths => counts(&self.client_error_paths, borrowed, 0),
+            _ => return None,
+        };
+        Some(TopRows::new(entries, order))
+    }
+}
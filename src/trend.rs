@@ -0,0 +1,286 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::TopInfo;
+use anyhow::{bail, Context, Result};
+use chrono::prelude::*;
+use prettytable::{cell, Row, Table};
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::path::Path;
+
+// the number of paths stored for every run
+const TREND_PATHS: usize = 10;
+
+const TREND_RUNS_TABLE: &str = "CREATE TABLE IF NOT EXISTS trend_runs (
+    id INTEGER PRIMARY KEY,
+    added_at TEXT NOT NULL,
+    log_start TEXT NOT NULL,
+    log_end TEXT NOT NULL,
+    requests INTEGER NOT NULL,
+    server_errors INTEGER NOT NULL,
+    p95_response_time REAL
+)";
+
+const TREND_PATHS_TABLE: &str = "CREATE TABLE IF NOT EXISTS trend_paths (
+    run_id INTEGER NOT NULL REFERENCES trend_runs(id),
+    path TEXT NOT NULL,
+    count INTEGER NOT NULL
+)";
+
+/// The headline metrics of every run added in one week, the week a run
+/// belongs to is the one its last log entry falls into
+#[derive(Debug, Default)]
+pub struct TrendWeek {
+    /// The Monday the week starts on
+    pub start: NaiveDate,
+    pub runs: usize,
+    pub requests: usize,
+    pub server_errors: usize,
+    /// The request weighted mean of the p95 response time of the runs, in seconds
+    pub p95_response_time: Option<f64>,
+    /// The most requested paths, without query params, most requested first
+    pub top_paths: Vec<(String, usize)>,
+}
+
+impl TrendWeek {
+    /// Percentage of requests with a 5xx response
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.server_errors as f64 * 100.0 / self.requests as f64
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<Connection> {
+    let conn =
+        Connection::open(path).with_context(|| format!("opening database {}", path.display()))?;
+    conn.execute(TREND_RUNS_TABLE, [])?;
+    conn.execute(TREND_PATHS_TABLE, [])?;
+    Ok(conn)
+}
+
+impl TopInfo {
+    /// Adds the headline metrics of the logs read so far to the trend
+    /// database at `path` as a new run
+    pub fn add_trend(&self, path: &Path) -> Result<()> {
+        let mut conn = open(path)?;
+        let headline = self.headline();
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO trend_runs (added_at, log_start, log_end, requests, server_errors, p95_response_time)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                Utc::now().to_rfc3339(),
+                self.duration.start.to_rfc3339(),
+                self.duration.end.to_rfc3339(),
+                self.total_requests as i64,
+                headline.server_errors as i64,
+                headline.p95_response_time,
+            ],
+        )?;
+
+        let run_id = tx.last_insert_rowid();
+        let mut paths: Vec<(&String, &usize)> = self.requests_no_query.iter().collect();
+        paths.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        {
+            let mut stmt =
+                tx.prepare("INSERT INTO trend_paths (run_id, path, count) VALUES (?1, ?2, ?3)")?;
+            for (path, count) in paths.into_iter().take(TREND_PATHS) {
+                stmt.execute(params![run_id, path, *count as i64])?;
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// Reads every run from the trend database at `path` and sums them up by
+/// week, oldest week first
+pub fn load_trends(path: &Path) -> Result<Vec<TrendWeek>> {
+    if !path.is_file() {
+        bail!("trend database {} doesn't exist", path.display());
+    }
+    let conn = open(path)?;
+
+    let mut weeks: BTreeMap<NaiveDate, TrendWeek> = BTreeMap::new();
+    let mut p95_weights: HashMap<NaiveDate, (f64, usize)> = HashMap::new();
+    let mut paths: HashMap<NaiveDate, HashMap<String, usize>> = HashMap::new();
+
+    let mut stmt = conn.prepare(
+        "SELECT id, log_end, requests, server_errors, p95_response_time FROM trend_runs",
+    )?;
+    let runs = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, Option<f64>>(4)?,
+        ))
+    })?;
+    let mut path_stmt = conn.prepare("SELECT path, count FROM trend_paths WHERE run_id = ?1")?;
+
+    for run in runs {
+        let (id, log_end, requests, server_errors, p95) = run?;
+        let end = DateTime::parse_from_rfc3339(&log_end)
+            .with_context(|| format!("parsing the log end of run {id}"))?
+            .date_naive();
+        let monday = end - chrono::Duration::days(end.weekday().num_days_from_monday() as i64);
+
+        let week = weeks.entry(monday).or_insert_with(|| TrendWeek {
+            start: monday,
+            ..TrendWeek::default()
+        });
+        week.runs += 1;
+        week.requests += requests as usize;
+        week.server_errors += server_errors as usize;
+        if let Some(p95) = p95 {
+            let weights = p95_weights.entry(monday).or_default();
+            weights.0 += p95 * requests as f64;
+            weights.1 += requests as usize;
+        }
+
+        let week_paths = paths.entry(monday).or_default();
+        for path in path_stmt.query_map(params![id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })? {
+            let (path, count) = path?;
+            *week_paths.entry(path).or_insert(0) += count as usize;
+        }
+    }
+
+    Ok(weeks
+        .into_values()
+        .map(|mut week| {
+            week.p95_response_time = p95_weights
+                .get(&week.start)
+                .filter(|(_, requests)| *requests > 0)
+                .map(|(sum, requests)| sum / *requests as f64);
+            let mut top: Vec<(String, usize)> = paths
+                .remove(&week.start)
+                .unwrap_or_default()
+                .into_iter()
+                .collect();
+            top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            top.truncate(TREND_PATHS);
+            week.top_paths = top;
+            week
+        })
+        .collect())
+}
+
+// the relative change from `previous` to `current`, blank for the first week
+fn change(previous: Option<f64>, current: Option<f64>) -> String {
+    match (previous, current) {
+        (Some(previous), Some(current)) if previous != 0.0 => {
+            format!("{:+.1}%", (current - previous) * 100.0 / previous)
+        }
+        _ => String::new(),
+    }
+}
+
+/// Writes the last `max_weeks` of `weeks` as a table with the change of every
+/// metric from the week before, followed by the top paths of the most recent
+/// week and how they changed
+pub fn write_trends<W: Write>(
+    weeks: &[TrendWeek],
+    max_weeks: usize,
+    out: &mut W,
+) -> io::Result<()> {
+    let skip = weeks.len().saturating_sub(max_weeks);
+
+    writeln!(out)?;
+    writeln!(out, "Weekly Trends:")?;
+    writeln!(out)?;
+    let mut table = Table::new();
+    table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+    table.set_titles(Row::new(vec![
+        cell!("Week"),
+        cell!("Runs"),
+        cell!("Requests"),
+        cell!("Change"),
+        cell!("5xx Rate"),
+        cell!("Change"),
+        cell!("p95 (s)"),
+        cell!("Change"),
+        cell!("Top Path"),
+    ]));
+    for (i, week) in weeks.iter().enumerate().skip(skip) {
+        let previous = i.checked_sub(1).map(|i| &weeks[i]);
+        table.add_row(Row::new(vec![
+            cell!(week.start),
+            cell!(week.runs),
+            cell!(week.requests),
+            cell!(change(
+                previous.map(|p| p.requests as f64),
+                Some(week.requests as f64)
+            )),
+            cell!(format!("{:.3}%", week.error_rate())),
+            cell!(change(
+                previous.map(TrendWeek::error_rate),
+                Some(week.error_rate())
+            )),
+            cell!(week
+                .p95_response_time
+                .map(|p95| format!("{p95:.3}"))
+                .unwrap_or_default()),
+            cell!(change(
+                previous.and_then(|p| p.p95_response_time),
+                week.p95_response_time
+            )),
+            cell!(week
+                .top_paths
+                .first()
+                .map(|(path, _)| path.as_str())
+                .unwrap_or_default()),
+        ]));
+    }
+    table.print(out)?;
+    writeln!(out)?;
+
+    if let Some(latest) = weeks.last() {
+        let previous = weeks.len().checked_sub(2).map(|i| &weeks[i]);
+        writeln!(out, "Top Paths in the Week of {}:", latest.start)?;
+        writeln!(out)?;
+        let mut table = Table::new();
+        table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP);
+        table.set_titles(Row::new(vec![
+            cell!("Path"),
+            cell!("Count"),
+            cell!("Change"),
+        ]));
+        for (path, count) in &latest.top_paths {
+            let before = previous.map(|week| {
+                week.top_paths
+                    .iter()
+                    .find(|(p, _)| p == path)
+                    .map_or(0, |(_, count)| *count)
+            });
+            let change = match before {
+                Some(0) => "new".to_string(),
+                before => change(before.map(|b| b as f64), Some(*count as f64)),
+            };
+            table.add_row(Row::new(vec![cell!(path), cell!(count), cell!(change)]));
+        }
+        table.print(out)?;
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
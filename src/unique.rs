@@ -0,0 +1,152 @@
+// Copyright 2026 Daniel Mikusa
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+
+//     http://www.apache.org/licenses/LICENSE-2.0
+
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use serde::{Deserialize, Serialize};
+use siphasher::sip::SipHasher13;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+// values are counted exactly up to this many, then they're estimated
+pub(crate) const EXACT_LIMIT: usize = 4096;
+
+/// The hash of the counted values, saved with the counts, which can only be
+/// merged with counts of the same hash
+pub(crate) const HASH: &str = "siphash13-0-0";
+
+// 2^14 registers of the HyperLogLog sketch, the standard error is 1.04 / sqrt(2^14)
+// or about 0.8%
+const PRECISION: u32 = 14;
+const REGISTERS: usize = 1 << PRECISION;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Values {
+    // the hashes of every value seen
+    Exact(HashSet<u64>),
+    // the highest rank of the hashes falling into each register
+    Sketch(Vec<u8>),
+}
+
+/// Counts distinct values without keeping them. Small inputs are counted
+/// exactly, once there are more than a few thousand distinct values the
+/// count is estimated with a HyperLogLog sketch of fixed size.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UniqueCount {
+    values: Values,
+}
+
+impl Default for UniqueCount {
+    fn default() -> Self {
+        UniqueCount {
+            values: Values::Exact(HashSet::new()),
+        }
+    }
+}
+
+// the same value hashes the same in every run and build, so counts can be
+// merged. `DefaultHasher` may change with any Rust release, see `HASH`.
+fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = SipHasher13::new_with_keys(0, 0);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+// the register of `hash`, from its first bits, and its rank, the position of
+// the first set bit in the rest
+fn register(hash: u64) -> (usize, u8) {
+    let index = (hash >> (64 - PRECISION)) as usize;
+    let rest = hash << PRECISION;
+    let rank = rest.leading_zeros().min(64 - PRECISION) + 1;
+    (index, rank as u8)
+}
+
+fn sketch(hashes: &HashSet<u64>) -> Vec<u8> {
+    let mut registers = vec![0; REGISTERS];
+    for hash in hashes {
+        let (index, rank) = register(*hash);
+        registers[index] = registers[index].max(rank);
+    }
+    registers
+}
+
+impl UniqueCount {
+    pub fn insert<T: Hash + ?Sized>(&mut self, value: &T) {
+        let hash = hash(value);
+        match &mut self.values {
+            Values::Exact(hashes) => {
+                if hashes.insert(hash) && hashes.len() > EXACT_LIMIT {
+                    self.values = Values::Sketch(sketch(hashes));
+                }
+            }
+            Values::Sketch(registers) => {
+                let (index, rank) = register(hash);
+                registers[index] = registers[index].max(rank);
+            }
+        }
+    }
+
+    /// Adds the values counted by `other`
+    pub fn merge(&mut self, other: &UniqueCount) {
+        match (&mut self.values, &other.values) {
+            (Values::Exact(hashes), Values::Exact(other)) => {
+                hashes.extend(other);
+                if hashes.len() > EXACT_LIMIT {
+                    self.values = Values::Sketch(sketch(hashes));
+                }
+            }
+            (Values::Exact(hashes), Values::Sketch(other)) => {
+                let mut registers = sketch(hashes);
+                registers
+                    .iter_mut()
+                    .zip(other)
+                    .for_each(|(rank, other)| *rank = (*rank).max(*other));
+                self.values = Values::Sketch(registers);
+            }
+            (Values::Sketch(registers), Values::Exact(other)) => {
+                for hash in other {
+                    let (index, rank) = register(*hash);
+                    registers[index] = registers[index].max(rank);
+                }
+            }
+            (Values::Sketch(registers), Values::Sketch(other)) => registers
+                .iter_mut()
+                .zip(other)
+                .for_each(|(rank, other)| *rank = (*rank).max(*other)),
+        }
+    }
+
+    /// True once the count is an estimate
+    pub fn is_estimate(&self) -> bool {
+        matches!(self.values, Values::Sketch(_))
+    }
+
+    /// The number of distinct values, see `is_estimate`
+    pub fn count(&self) -> usize {
+        let registers = match &self.values {
+            Values::Exact(hashes) => return hashes.len(),
+            Values::Sketch(registers) => registers,
+        };
+
+        let m = REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = registers.iter().map(|r| 2f64.powi(-i32::from(*r))).sum();
+        let estimate = alpha * m * m / sum;
+
+        // small cardinalities are estimated better from the empty registers
+        let empty = registers.iter().filter(|r| **r == 0).count();
+        if estimate <= 2.5 * m && empty > 0 {
+            (m * (m / empty as f64).ln()).round() as usize
+        } else {
+            estimate.round() as usize
+        }
+    }
+}